@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
+use colored::Colorize;
 use dialoguer::Input;
 
 use crate::{
     db::{models::NewArticle, open_connection, queries},
+    goals, rules,
+    similarity::{self, Document},
     fetch::{
         content::convert_html_to_md,
         http::{extract_site, fetch_html},
@@ -11,47 +14,32 @@ use crate::{
     ui::list::{OutputFormat, render_articles},
 };
 
+const SUGGESTION_LIMIT: usize = 5;
+
 pub fn execute(
     url: Option<String>,
     tags: Vec<String>,
     title_by_user: Option<String>,
     no_fetch: bool,
+    no_rules: bool,
     extra_tags: Vec<String>
 ) -> Result<()> {
-    let (url, mut tags) = match url {
-        Some(u) => (u, tags),
-        None => {
-            let url = Input::new()
-                .with_prompt("URL")
-                .interact_text()
-                .context("Failed to read URL input")?;
-
-            let tags_input: String = Input::new()
-                .with_prompt("Tags (comma-separated, optional)")
-                .allow_empty(true)
-                .interact_text()
-                .context("Failed to read the tags")?;
-
-            let tags = if tags_input.is_empty() {
-                Vec::new()
-            } else {
-                tags_input
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect()
-            };
-
-            (url, tags)
-        }
+    // Without a URL argument we prompt for everything, asking for tags only
+    // after fetching so suggestions can be based on the article's content
+    let interactive = url.is_none();
+    let mut tags = tags;
+    let url = match url {
+        Some(u) => u,
+        None => Input::new()
+            .with_prompt("URL")
+            .interact_text()
+            .context("Failed to read URL input")?,
     };
 
+    let compiled_rules = if no_rules { Vec::new() } else { rules::load()? };
     let conn = open_connection()?;
 
-    let hash: String = blake3::hash(url.as_bytes())
-        .to_hex()
-        .chars()
-        .take(8)
-        .collect();
+    let hash = NewArticle::hash_url(&url);
 
     if let Some(existing) = queries::find_by_hash(&conn, &hash)? {
         println!("Article already stashed with ID: {}", existing.id);
@@ -80,11 +68,15 @@ pub fn execute(
 
             let domain = extract_site(&url);
             let fallback_title = title_by_user
-                .or_else(|| domain.clone().map(|d| d).or(Some("Untitled".to_string())));
+                .or_else(|| domain.clone().or(Some("Untitled".to_string())));
             (fallback_title, None, None, None)
         }
     };
 
+    if interactive {
+        tags.extend(prompt_for_tags(&conn, title.as_deref(), content_markdown.as_deref())?);
+    }
+
     tags.extend(
         extra_tags
             .iter()
@@ -93,6 +85,7 @@ pub fn execute(
     );
 
     let new_article = NewArticle {
+        id: None,
         hash,
         url: url.clone(),
         canonical_url: url.clone(),
@@ -102,11 +95,62 @@ pub fn execute(
         site: extract_site(&url),
         content_markdown,
         tags,
+        saved_at: None,
     };
 
     let article = queries::insert_article(&conn, new_article)
         .context("Failed to save article to database")?;
 
-    render_articles(&vec![article], OutputFormat::Table, false, false)?;
+    let matched = rules::apply_to_new_article(&conn, &compiled_rules, &article)?;
+    let article = if matched.is_empty() {
+        article
+    } else {
+        eprintln!("Applied rules: {}", matched.join(", "));
+        queries::get_article_by_id(&conn, article.id)?.unwrap_or(article)
+    };
+
+    render_articles(&[article], OutputFormat::Table, false, false)?;
+
+    if let Some(warning) = goals::check_budgets(&conn)? {
+        eprintln!("{} {}", "⚠".yellow().bold(), warning);
+    }
+
     Ok(())
 }
+
+fn prompt_for_tags(
+    conn: &rusqlite::Connection,
+    title: Option<&str>,
+    content: Option<&str>,
+) -> Result<Vec<String>> {
+    let document = Document {
+        id: None,
+        title,
+        content,
+        tags: &[],
+    };
+    let suggestions = similarity::suggest_tags(conn, &document, SUGGESTION_LIMIT)?;
+
+    let mut input = Input::<String>::new()
+        .with_prompt("Tags (comma-separated, optional)")
+        .allow_empty(true);
+
+    // Pre-fill the suggestions; they can be edited or cleared before accepting
+    if !suggestions.is_empty() {
+        let suggested = suggestions
+            .iter()
+            .map(|s| s.tag.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        eprintln!("Suggested from your library: {}", suggested);
+        input = input.with_initial_text(suggested);
+    }
+
+    let tags_input = input.interact_text().context("Failed to read the tags")?;
+
+    Ok(tags_input
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect())
+}
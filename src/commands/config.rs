@@ -5,6 +5,7 @@ use crate::config::{self, schema::Config};
 
 pub fn execute_set(key: String, value: String) -> Result<()> {
     let mut config = config::load_config()?;
+    let display_value = value.clone();
     
     // Parse the key and set the value
     let parts: Vec<&str> = key.split('.').collect();
@@ -56,11 +57,25 @@ pub fn execute_set(key: String, value: String) -> Result<()> {
             "user_agent" => config.fetch.user_agent = value,
             _ => anyhow::bail!("Unknown fetch field: {}", field),
         },
-        _ => anyhow::bail!("Unknown section: {}. Valid sections: defaults, colors, fetch", section),
+        "goals" => {
+            // "off" (or "none") stops tracking a goal
+            let goal = if ["off", "none"].contains(&value.as_str()) {
+                None
+            } else {
+                Some(value.parse::<u32>().context(format!("{} must be a number or off", field))?)
+            };
+            match field {
+                "reads_per_week" => config.goals.reads_per_week = goal,
+                "max_unread" => config.goals.max_unread = goal,
+                "max_unread_age_days" => config.goals.max_unread_age_days = goal,
+                _ => anyhow::bail!("Unknown goals field: {}", field),
+            }
+        }
+        _ => anyhow::bail!("Unknown section: {}. Valid sections: defaults, colors, fetch, goals", section),
     }
     
     config::save_config(&config)?;
-    println!("{} Config updated: {} = {}", "✓".green().bold(), key, config.defaults.editor);
+    println!("{} Config updated: {} = {}", "✓".green().bold(), key, display_value);
     
     Ok(())
 }
@@ -95,6 +110,15 @@ pub fn execute_get(key: String) -> Result<()> {
             "user_agent" => config.fetch.user_agent,
             _ => anyhow::bail!("Unknown fetch field: {}", field),
         },
+        "goals" => {
+            let goal = match field {
+                "reads_per_week" => config.goals.reads_per_week,
+                "max_unread" => config.goals.max_unread,
+                "max_unread_age_days" => config.goals.max_unread_age_days,
+                _ => anyhow::bail!("Unknown goals field: {}", field),
+            };
+            goal.map(|g| g.to_string()).unwrap_or_else(|| "off".to_string())
+        }
         _ => anyhow::bail!("Unknown section: {}", section),
     };
     
@@ -120,6 +144,12 @@ pub fn execute_list() -> Result<()> {
     println!("  follow_redirects = {}", config.fetch.follow_redirects);
     println!("  user_agent = {}", config.fetch.user_agent);
     
+    println!("\n{}", "[goals]".bold());
+    let show = |goal: Option<u32>| goal.map(|g| g.to_string()).unwrap_or_else(|| "off".to_string());
+    println!("  reads_per_week = {}", show(config.goals.reads_per_week));
+    println!("  max_unread = {}", show(config.goals.max_unread));
+    println!("  max_unread_age_days = {}", show(config.goals.max_unread_age_days));
+    
     Ok(())
 }
 
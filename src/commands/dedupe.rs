@@ -0,0 +1,159 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use dialoguer::Select;
+
+use crate::{
+    db::{models::Article, open_connection, queries},
+    dedupe::{self, Cluster},
+    ui::formatters::datetime_humanize,
+};
+
+fn describe(article: &Article) -> String {
+    let mut flags = Vec::new();
+    if article.starred {
+        flags.push("★");
+    }
+    if article.read {
+        flags.push("read");
+    }
+    if article.note.is_some() {
+        flags.push("note");
+    }
+    if !article.highlights.is_empty() {
+        flags.push("highlights");
+    }
+
+    format!(
+        "#{} {} ({}, saved {}){}",
+        article.id,
+        article.title.as_deref().unwrap_or("<no title>"),
+        article.site.as_deref().unwrap_or("-"),
+        datetime_humanize(article.saved_at),
+        if flags.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", flags.join(", "))
+        }
+    )
+}
+
+/// Ask which article of the cluster to keep; None skips the cluster
+fn choose_keep(cluster: &Cluster) -> Result<Option<usize>> {
+    let mut items: Vec<String> = cluster
+        .articles
+        .iter()
+        .map(|a| format!("Keep {}", describe(a)))
+        .collect();
+    items.push("Skip this cluster".to_string());
+
+    let choice = Select::new()
+        .with_prompt("Merge into")
+        .items(&items)
+        .default(cluster.suggested_keep())
+        .interact()
+        .context("Failed to read selection")?;
+
+    Ok((choice < cluster.articles.len()).then_some(choice))
+}
+
+pub fn execute(threshold: f64, yes: bool, dry_run: bool) -> Result<()> {
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        bail!("Threshold must be between 0 and 1 (got {})", threshold);
+    }
+
+    let conn = open_connection()?;
+
+    let mut articles: Vec<Article> = queries::list_articles(&conn, i64::MAX, true)?
+        .into_iter()
+        .filter(|a| !a.archived)
+        .collect();
+    // Articles with highlights are preferred when suggesting which one to keep
+    queries::attach_highlights(&conn, &mut articles)?;
+
+    let clusters = dedupe::find_clusters(&articles, threshold);
+
+    if clusters.is_empty() {
+        println!(
+            "No near-duplicates found among {} article(s)",
+            articles.len()
+        );
+        return Ok(());
+    }
+
+    println!("Found {} cluster(s) of near-duplicates\n", clusters.len());
+
+    let mut merged_clusters = 0;
+    let mut archived = 0;
+
+    for (idx, cluster) in clusters.iter().enumerate() {
+        println!(
+            "{}",
+            format!("Cluster {}/{}", idx + 1, clusters.len()).bold()
+        );
+        let suggested = cluster.suggested_keep();
+        for (i, article) in cluster.articles.iter().enumerate() {
+            let marker = if i == suggested {
+                "→".green()
+            } else {
+                " ".normal()
+            };
+            println!("  {} {}", marker, describe(article));
+        }
+
+        if dry_run {
+            println!();
+            continue;
+        }
+
+        let keep_idx = if yes {
+            Some(suggested)
+        } else {
+            choose_keep(cluster)?
+        };
+
+        let Some(keep_idx) = keep_idx else {
+            println!("  {} Skipped\n", "⊘".yellow());
+            continue;
+        };
+
+        let keep = &cluster.articles[keep_idx];
+        let others: Vec<&Article> = cluster
+            .articles
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != keep_idx)
+            .map(|(_, a)| a)
+            .collect();
+
+        dedupe::apply_merge(&conn, keep, &others)?;
+
+        println!(
+            "  {} Kept #{}, archived {}\n",
+            "✓".green(),
+            keep.id,
+            others
+                .iter()
+                .map(|a| format!("#{}", a.id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        merged_clusters += 1;
+        archived += others.len();
+    }
+
+    if dry_run {
+        println!(
+            "{} → marks the article that would be kept (dry run - no changes made)",
+            "ℹ".cyan().bold()
+        );
+    } else {
+        println!(
+            "{} Merged {} cluster(s), archived {} duplicate(s)",
+            "✓".green().bold(),
+            merged_clusters,
+            archived
+        );
+    }
+
+    Ok(())
+}
@@ -1,83 +1,431 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{Duration, Local, Utc};
 use colored::Colorize;
-use std::path::PathBuf;
+use rusqlite::Connection;
+use std::fmt;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-use crate::db::{open_connection, queries};
-use crate::export::{html, json, markdown};
+use crate::dates;
+use crate::db::{
+    models::Article,
+    open_connection,
+    queries::{self, ArticleFilter},
+    search_query,
+};
+use crate::export::{epub, feed, html, json, markdown, netscape, site, table, template};
 
+/// How far back `--digest` looks
+const DIGEST_DAYS: i64 = 7;
+
+/// Formats written an article at a time as they're read from the database
+const STREAMED: [&str; 3] = ["ndjson", "csv", "tsv"];
+
+/// Where an export goes: a file, or stdout for `-o -`
+enum Destination {
+    File(PathBuf),
+    Stdout,
+}
+
+impl Destination {
+    fn new(output: Option<String>, default_name: String) -> Self {
+        match output {
+            Some(path) if path == "-" => Destination::Stdout,
+            Some(path) => Destination::File(PathBuf::from(path)),
+            None => Destination::File(PathBuf::from(default_name)),
+        }
+    }
+
+    /// Write the export with `write`. A file is written under a temporary
+    /// name beside it and only takes its place once complete, so a failed
+    /// export leaves no partial file, and any earlier export stays as it was.
+    fn write<T>(&self, write: impl FnOnce(&mut dyn Write) -> Result<T>) -> Result<T> {
+        let path = match self {
+            Destination::File(path) => path,
+            Destination::Stdout => {
+                let mut out = BufWriter::new(io::stdout().lock());
+                let written = write(&mut out)?;
+                out.flush().context("Failed to write to stdout")?;
+                return Ok(written);
+            }
+        };
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut temp = tempfile::Builder::new();
+        temp.prefix(".stash-export");
+        // Created like any other file, rather than readable only by us
+        #[cfg(unix)]
+        {
+            use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+            temp.permissions(Permissions::from_mode(0o666));
+        }
+        let file = temp
+            .tempfile_in(dir)
+            .context(format!("Failed to create file: {}", path.display()))?;
+
+        let mut out = BufWriter::new(file);
+        let written = write(&mut out)?;
+        out.into_inner()
+            .map_err(|e| e.into_error())
+            .context(format!("Failed to write to {}", path.display()))?
+            .persist(path)
+            .context(format!("Failed to create file: {}", path.display()))?;
+        Ok(written)
+    }
+
+    /// Status messages go to stderr while the export itself is on stdout
+    fn report(&self, message: &str) {
+        match self {
+            Destination::File(_) => println!("{}", message),
+            Destination::Stdout => eprintln!("{}", message),
+        }
+    }
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Destination::File(path) => write!(f, "{}", path.display()),
+            Destination::Stdout => write!(f, "stdout"),
+        }
+    }
+}
+
+/// Whether an export stopped because its reader went away, as when piping
+/// into `head`. That's the reader's choice rather than an error.
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .map(|e| e.kind())
+            .or_else(|| {
+                cause
+                    .downcast_ref::<serde_json::Error>()
+                    .and_then(|e| e.io_error_kind())
+            })
+            == Some(io::ErrorKind::BrokenPipe)
+    })
+}
+
+/// Write NDJSON, CSV or TSV an article at a time as rows come from the
+/// database, so the library never has to fit in memory. Returns how many.
+fn write_streamed(
+    conn: &Connection,
+    format: &str,
+    filter: &ArticleFilter,
+    out: &mut dyn Write,
+    fields: &json::Fields,
+    columns: Vec<&'static str>,
+    options: table::Options,
+) -> Result<usize> {
+    let mut count = 0;
+    if format == "ndjson" {
+        queries::for_each_matching(conn, filter, |mut article| {
+            if fields.includes("highlights") {
+                article.highlights = queries::list_highlights(conn, Some(article.id))?;
+            }
+            json::write_ndjson(&article, fields, out)?;
+            count += 1;
+            Ok(())
+        })?;
+    } else {
+        let mut writer = table::TableWriter::new(out, format, columns, options)?;
+        queries::for_each_matching(conn, filter, |article| {
+            writer.write(&article)?;
+            count += 1;
+            Ok(())
+        })?;
+        writer.finish()?;
+    }
+
+    Ok(count)
+}
+
+fn export_streamed(
+    conn: &Connection,
+    format: &str,
+    filter: &ArticleFilter,
+    destination: &Destination,
+    fields: &json::Fields,
+    columns: Vec<&'static str>,
+    options: table::Options,
+) -> Result<()> {
+    // Checked before the file is created, so an export with nothing in it
+    // doesn't replace one that's already there
+    if !queries::any_matching(conn, filter)? {
+        destination.report("No articles to export");
+        return Ok(());
+    }
+
+    let count = destination
+        .write(|out| write_streamed(conn, format, filter, out, fields, columns, options))
+        .context(format!("Failed to export to {}", destination))?;
+
+    destination.report(&format!(
+        "{} Exported {} article(s) to {}",
+        "✓".green().bold(),
+        count,
+        destination
+    ));
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
-    format: String,
+    format: Option<String>,
     output: Option<String>,
     ids: Option<Vec<i64>>,
     tags: Option<Vec<String>>,
+    view: Option<String>,
+    starred: bool,
+    states: Vec<String>,
+    sites: Vec<String>,
+    since: Option<String>,
+    until: Option<String>,
+    query: Option<String>,
+    digest: bool,
+    template: Option<String>,
+    title: Option<String>,
+    columns: Vec<String>,
+    tag_delimiter: String,
+    date_format: Option<String>,
+    fields: Vec<String>,
 ) -> Result<()> {
     let conn = open_connection()?;
-    
-    // Fetch articles based on filters
-    let articles = if let Some(article_ids) = ids {
-        queries::find_by_ids(&conn, &article_ids)?
-    } else if let Some(tag_list) = tags {
-        // Get all articles and filter by tags
-        queries::list_articles_filtered(&conn, i64::MAX, true, false, false, &tag_list, "time", false)?
-    } else {
-        // Get all articles
-        queries::list_articles_filtered(&conn, i64::MAX, true, false, false, &[], "time", false)?
+    let now = Utc::now();
+
+    // A template is filled in as Markdown or HTML, going by its extension
+    let template = template.map(PathBuf::from);
+    let format = match (format, &template) {
+        (Some(format), Some(_)) if format != "markdown" && format != "html" => {
+            anyhow::bail!("--template only works with the markdown and html formats")
+        }
+        (Some(format), _) => format,
+        (None, Some(path)) if template::is_html(path) => "html".to_string(),
+        (None, Some(_)) => "markdown".to_string(),
+        (None, None) => "json".to_string(),
     };
-    
-    if articles.is_empty() {
-        println!("No articles to export");
-        return Ok(());
-    }
-    
-    // Determine output path
-    let output_path = match output {
-        Some(path) => PathBuf::from(path),
-        None => {
-            // Generate default filename
-            let default_name = match format.as_str() {
-                "json" => "stash-export.json",
-                "markdown" => "stash-export-md",
-                "html" => "stash-export.html",
-                _ => "stash-export",
-            };
-            PathBuf::from(default_name)
+
+    let default_name = match format.as_str() {
+        "json" => "stash-export.json".to_string(),
+        "ndjson" => "stash-export.ndjson".to_string(),
+        "markdown" if template.is_some() => "stash-export.md".to_string(),
+        "markdown" => "stash-export-md".to_string(),
+        "html" => "stash-export.html".to_string(),
+        "netscape" => "bookmarks.html".to_string(),
+        "epub" if digest => format!(
+            "stash-digest-{}.epub",
+            now.with_timezone(&Local).format("%Y-%m-%d")
+        ),
+        "epub" => "stash-export.epub".to_string(),
+        "atom" => "stash.atom".to_string(),
+        "rss" => "stash.rss".to_string(),
+        "csv" | "tsv" => format!("stash-export.{}", format),
+        "site" => "stash-site".to_string(),
+        _ => anyhow::bail!(
+            "Unknown format: {}. Use json, ndjson, markdown, html, netscape, epub, atom, rss, csv, tsv, or site",
+            format
+        ),
+    };
+    let destination = Destination::new(output, default_name);
+    if matches!(destination, Destination::Stdout) {
+        match format.as_str() {
+            "markdown" if template.is_none() => {
+                anyhow::bail!("Markdown exports are a directory of files and can't go to stdout")
+            }
+            "site" => anyhow::bail!("Sites are a directory of files and can't go to stdout"),
+            _ => {}
         }
+    }
+
+    let fields = json::Fields::parse(&fields)?;
+    if !matches!(fields, json::Fields::All) && format != "json" && format != "ndjson" {
+        anyhow::bail!("--fields only applies to json and ndjson exports");
+    }
+
+    let feed_title = title
+        .clone()
+        .unwrap_or_else(|| feed::feed_title(tags.as_deref().unwrap_or_default(), starred));
+
+    let mut filter = ArticleFilter {
+        ids,
+        tags: tags.unwrap_or_default(),
+        starred,
+        sites,
+        since: since.as_deref().map(dates::parse_since).transpose()?,
+        until: until.as_deref().map(dates::parse_before).transpose()?,
+        ..Default::default()
     };
-    
-    // Export based on format
-    match format.as_str() {
-        "json" => {
-            json::export_to_json(&articles, &output_path)?;
-            println!(
-                "{} Exported {} article(s) to {}",
-                "✓".green().bold(),
-                articles.len(),
-                output_path.display()
-            );
+    for state in &states {
+        match state.as_str() {
+            "unread" => filter.read = Some(false),
+            "read" => filter.read = Some(true),
+            "archived" => filter.archived = Some(true),
+            "snoozed" => filter.snoozed = Some(true),
+            _ => anyhow::bail!(
+                "Unknown state '{}'. Use unread, read, archived, or snoozed",
+                state
+            ),
         }
-        "markdown" => {
-            markdown::export_to_markdown(&articles, &output_path)?;
-            println!(
-                "{} Exported {} article(s) to {}",
-                "✓".green().bold(),
-                articles.len(),
-                output_path.display()
-            );
+    }
+    if let Some(name) = view {
+        let view = queries::find_saved_view_by_name(&conn, &name)?
+            .context(format!("No saved view named '{}'", name))?;
+        // Like `stash view`, unread articles unless the view says otherwise
+        let parsed = search_query::parse(&view.query)
+            .with_context(|| format!("Failed to evaluate view '{}'", view.name))?;
+        if !search_query::compile(&parsed).has_state_filter && states.is_empty() {
+            filter.read = Some(false);
+            filter.archived = Some(false);
         }
-        "html" => {
-            html::export_to_html(&articles, &output_path)?;
-            println!(
-                "{} Exported {} article(s) to {}",
-                "✓".green().bold(),
-                articles.len(),
-                output_path.display()
-            );
+        filter.queries.push(view.query);
+    }
+    filter.queries.extend(query);
+    // Unread articles from the past week, as a book to catch up with
+    if digest {
+        filter.read = Some(false);
+        filter.archived = Some(false);
+        filter.snoozed = Some(false);
+        filter.since = Some(now - Duration::days(DIGEST_DAYS));
+    }
+
+    let result = if STREAMED.contains(&format.as_str()) {
+        let columns = table::parse_columns(&columns)?;
+        let options = table::Options::new(&tag_delimiter, date_format, &[])?;
+        export_streamed(
+            &conn,
+            &format,
+            &filter,
+            &destination,
+            &fields,
+            columns,
+            options,
+        )
+    } else {
+        let mut articles = queries::list_matching(&conn, &filter)?;
+        if articles.is_empty() {
+            destination.report("No articles to export");
+            return Ok(());
         }
-        _ => {
-            anyhow::bail!("Unknown format: {}. Use json, markdown, or html", format);
+        queries::attach_highlights(&conn, &mut articles)?;
+
+        let title = title.unwrap_or_else(|| match filter.since {
+            Some(since) if digest => format!(
+                "Stash digest: {} to {}",
+                since.with_timezone(&Local).format("%b %-d"),
+                now.with_timezone(&Local).format("%b %-d, %Y")
+            ),
+            _ => "Stash".to_string(),
+        });
+        match &template {
+            Some(template) => {
+                export_with_template(&format, template, &articles, &destination, &title, &filter)
+            }
+            None => export_loaded(
+                &format,
+                &articles,
+                &destination,
+                &fields,
+                &title,
+                &feed_title,
+            ),
         }
+    };
+
+    match result {
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        result => result,
     }
-    
+}
+
+/// Fill a user's template in with the articles
+fn export_with_template(
+    format: &str,
+    template_path: &Path,
+    articles: &[Article],
+    destination: &Destination,
+    title: &str,
+    filter: &ArticleFilter,
+) -> Result<()> {
+    destination.write(|out| {
+        template::export_with_template(
+            template_path,
+            articles,
+            title,
+            filter.since,
+            filter.until,
+            format == "html",
+            out,
+        )
+    })?;
+
+    destination.report(&format!(
+        "{} Exported {} article(s) to {} with {}",
+        "✓".green().bold(),
+        articles.len(),
+        destination,
+        template_path.display()
+    ));
     Ok(())
 }
 
+/// Write a format that needs every article at once
+fn export_loaded(
+    format: &str,
+    articles: &[Article],
+    destination: &Destination,
+    fields: &json::Fields,
+    title: &str,
+    feed_title: &str,
+) -> Result<()> {
+    if let ("markdown" | "site", Destination::File(path)) = (format, destination) {
+        match format {
+            "markdown" => markdown::export_to_markdown(articles, path)?,
+            _ => site::export_to_site(articles, title, path)?,
+        }
+        destination.report(&format!(
+            "{} Exported {} article(s) to {}",
+            "✓".green().bold(),
+            articles.len(),
+            destination
+        ));
+        return Ok(());
+    }
+
+    let exported = destination.write(|out| {
+        Ok(match format {
+            "json" => {
+                json::export_to_json(articles, fields, out)?;
+                format!("{} article(s)", articles.len())
+            }
+            "html" => {
+                html::export_to_html(articles, out)?;
+                format!("{} article(s)", articles.len())
+            }
+            "netscape" => {
+                netscape::export_to_netscape(articles, out)?;
+                format!("{} bookmark(s)", articles.len())
+            }
+            "epub" => {
+                epub::export_to_epub(articles, title, out)?;
+                format!("{} article(s)", articles.len())
+            }
+            "atom" | "rss" => {
+                feed::export_to_feed(format, articles, feed_title, out)?;
+                format!("a feed of {} article(s)", articles.len())
+            }
+            _ => anyhow::bail!("Unknown format: {}", format),
+        })
+    })?;
+
+    destination.report(&format!(
+        "{} Exported {} to {}",
+        "✓".green().bold(),
+        exported,
+        destination
+    ));
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::{
+    db::{open_connection, queries},
+    fetch::{content::convert_html_to_md, http::fetch_html, metadata::extract_metadata},
+};
+
+/// Download content for the given articles, or for everything imports
+/// queued with `--fetch` when no IDs are given
+pub fn execute(ids: &[i64]) -> Result<()> {
+    let conn = open_connection()?;
+
+    let articles = if ids.is_empty() {
+        queries::list_fetch_pending(&conn)?
+    } else {
+        let found = queries::find_by_ids(&conn, ids)?;
+        if let Some(missing) = ids.iter().find(|id| !found.iter().any(|a| a.id == **id)) {
+            bail!("Article with ID {} not found", missing);
+        }
+        found
+    };
+
+    if articles.is_empty() {
+        println!("Nothing waiting to be fetched");
+        return Ok(());
+    }
+
+    let total = articles.len();
+    let mut fetched = 0;
+    let mut failed = 0;
+
+    for (idx, article) in articles.iter().enumerate() {
+        let progress = format!("[{}/{}]", idx + 1, total).dimmed();
+        match fetch_html(&article.url) {
+            Ok(html) => {
+                let meta = extract_metadata(&html).ok();
+                let updated = queries::save_fetched_content(
+                    &conn,
+                    article.id,
+                    meta.as_ref().and_then(|m| m.title.clone()),
+                    meta.as_ref().and_then(|m| m.description.clone()),
+                    meta.as_ref().and_then(|m| m.favicon_url.clone()),
+                    convert_html_to_md(&html),
+                )?;
+                println!(
+                    "  {} {} {}",
+                    progress,
+                    "✓".green(),
+                    updated.title.as_deref().unwrap_or(&updated.url)
+                );
+                fetched += 1;
+            }
+            Err(e) => {
+                eprintln!("  {} {} {}: {}", progress, "✗".red(), article.url, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "\n{} Fetched {} article(s), {} failed",
+        "✓".green().bold(),
+        fetched,
+        failed
+    );
+    if failed > 0 && ids.is_empty() {
+        println!("Failed articles stay queued; run `stash fetch` again to retry");
+    }
+
+    Ok(())
+}
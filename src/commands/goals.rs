@@ -0,0 +1,117 @@
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use colored::Colorize;
+use dialoguer::Confirm;
+
+use crate::{
+    config,
+    db::{open_connection, queries},
+    goals::{self, Progress},
+    ui::list::{OutputFormat, render_articles},
+};
+
+const BAR_WIDTH: usize = 20;
+
+/// Articles listed in the bankruptcy preview before "... and N more"
+const PREVIEW_LIMIT: usize = 20;
+
+pub fn execute() -> Result<()> {
+    let goals = config::load_config()?.goals;
+
+    if goals.is_empty() {
+        println!("No goals set. For example:");
+        println!("  stash config set goals.reads_per_week 5");
+        println!("  stash config set goals.max_unread 200");
+        println!("  stash config set goals.max_unread_age_days 90");
+        return Ok(());
+    }
+
+    let conn = open_connection()?;
+    let now = Utc::now();
+    let progress = Progress::load(&conn, now)?;
+
+    println!("{}\n", "Goals".bold());
+    for status in goals::evaluate(&goals, &progress, now) {
+        let filled = (status.fill * BAR_WIDTH as f64).round() as usize;
+        let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled));
+        println!(
+            "  {} {:<36} {}  {}",
+            if status.met {
+                "✓".green()
+            } else {
+                "✗".red()
+            },
+            status.goal,
+            if status.met {
+                bar.green()
+            } else {
+                bar.yellow()
+            },
+            status.current.dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+pub fn execute_bankruptcy(days: Option<u32>, yes: bool, dry_run: bool) -> Result<()> {
+    let days = match days.or(config::load_config()?.goals.max_unread_age_days) {
+        Some(days) => days,
+        None => bail!("Pass --days, or set goals.max_unread_age_days in the config"),
+    };
+
+    let conn = open_connection()?;
+    let candidates = goals::bankruptcy_candidates(
+        queries::list_articles(&conn, i64::MAX, false)?,
+        days,
+        Utc::now(),
+    );
+
+    if candidates.is_empty() {
+        println!("Nothing unread is older than {} days", days);
+        return Ok(());
+    }
+
+    println!(
+        "{} unread article(s) older than {} days would be archived (starred and snoozed ones are kept):\n",
+        candidates.len().to_string().bold(),
+        days
+    );
+    render_articles(
+        &candidates[..candidates.len().min(PREVIEW_LIMIT)],
+        OutputFormat::Table,
+        false,
+        false,
+    )?;
+    if candidates.len() > PREVIEW_LIMIT {
+        println!("  ... and {} more", candidates.len() - PREVIEW_LIMIT);
+    }
+
+    if dry_run {
+        println!("\n{} Dry run - no changes made", "ℹ".cyan().bold());
+        return Ok(());
+    }
+
+    if !yes {
+        let confirmed = Confirm::new()
+            .with_prompt(format!("Archive {} article(s)?", candidates.len()))
+            .default(false)
+            .interact()
+            .context("Failed to read confirmation")?;
+        if !confirmed {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let ids: Vec<i64> = candidates.iter().map(|a| a.id).collect();
+    let archived = queries::archive_by_ids(&conn, &ids)?;
+
+    println!(
+        "{} Archived {} article(s). Restore any with `stash restore <id>`",
+        "✓".green().bold(),
+        archived
+    );
+
+    Ok(())
+}
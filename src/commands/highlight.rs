@@ -0,0 +1,119 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::{
+    db::{open_connection, queries},
+    ui::formatters::datetime_humanize,
+};
+
+/// Character offset of `quote` in the article content, if it appears there verbatim
+pub(crate) fn find_position(content: Option<&str>, quote: &str) -> Option<i64> {
+    let content = content?;
+    let byte_offset = content.find(quote)?;
+    Some(content[..byte_offset].chars().count() as i64)
+}
+
+pub fn execute_add(id: i64, text: String, comment: Option<String>) -> Result<()> {
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("Highlight text cannot be empty");
+    }
+
+    let conn = open_connection()?;
+
+    let article = match queries::get_article_by_id(&conn, id)? {
+        Some(article) => article,
+        None => bail!("Article with ID {} not found", id),
+    };
+
+    let position = find_position(article.content_markdown.as_deref(), text);
+    let comment = comment.filter(|c| !c.trim().is_empty());
+
+    let highlight = queries::insert_highlight(&conn, id, text, position, comment.as_deref())
+        .context("Failed to save highlight")?;
+
+    println!(
+        "{} Added highlight {} to article {}",
+        "✓".green().bold(),
+        highlight.id,
+        id
+    );
+    if position.is_none() && article.content_markdown.is_some() {
+        println!(
+            "  {} Quote not found verbatim in the saved content; stored without a position",
+            "ℹ".cyan()
+        );
+    }
+
+    Ok(())
+}
+
+pub fn execute_list(id: Option<i64>, format: String) -> Result<()> {
+    let conn = open_connection()?;
+
+    if let Some(id) = id
+        && queries::get_article_by_id(&conn, id)?.is_none()
+    {
+        bail!("Article with ID {} not found", id);
+    }
+
+    let highlights = queries::list_highlights(&conn, id)?;
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&highlights)?);
+            return Ok(());
+        }
+        "text" => {}
+        _ => bail!("Invalid format '{}'. Use text or json", format),
+    }
+
+    if highlights.is_empty() {
+        println!("No highlights yet. Add one with `stash highlight add <id> \"quote\"`");
+        return Ok(());
+    }
+
+    let article_ids: Vec<i64> = highlights.iter().map(|h| h.article_id).collect();
+    let articles = queries::find_by_ids(&conn, &article_ids)?;
+
+    let mut current_article = None;
+    for highlight in &highlights {
+        if current_article != Some(highlight.article_id) {
+            if current_article.is_some() {
+                println!();
+            }
+            current_article = Some(highlight.article_id);
+
+            let title = articles
+                .iter()
+                .find(|a| a.id == highlight.article_id)
+                .and_then(|a| a.title.as_deref())
+                .unwrap_or("<no title>");
+            println!("{} {}", format!("#{}", highlight.article_id).cyan().bold(), title.bold());
+        }
+
+        println!(
+            "  {} “{}” {}",
+            format!("[{}]", highlight.id).dimmed(),
+            highlight.text,
+            datetime_humanize(highlight.created_at).dimmed()
+        );
+        if let Some(comment) = &highlight.comment {
+            println!("      {} {}", "↳".yellow(), comment);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn execute_delete(highlight_id: i64) -> Result<()> {
+    let conn = open_connection()?;
+
+    if queries::delete_highlight(&conn, highlight_id)? == 0 {
+        println!("No highlight with ID {}", highlight_id);
+    } else {
+        println!("{} Deleted highlight {}", "✓".green().bold(), highlight_id);
+    }
+
+    Ok(())
+}
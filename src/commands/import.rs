@@ -1,110 +1,449 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use crate::db::{models::NewArticle, open_connection, queries};
-use crate::export::json;
+use crate::db::{
+    models::{Article, Highlight, NewArticle},
+    open_connection, queries,
+};
+use crate::export::{
+    detect, json, markdown, netscape,
+    reconcile::{self, Incoming},
+    services, table, urls,
+};
+use crate::rules::{self, CompiledRule};
 
+use super::highlight::find_position;
+
+#[derive(Default)]
+struct Counts {
+    imported: usize,
+    merged: usize,
+    unchanged: usize,
+    skipped: usize,
+    errors: usize,
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.imported += other.imported;
+        self.merged += other.merged;
+        self.unchanged += other.unchanged;
+        self.skipped += other.skipped;
+        self.errors += other.errors;
+    }
+}
+
+impl std::fmt::Display for Counts {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} imported, {} merged, {} unchanged, {} skipped, {} errors",
+            self.imported, self.merged, self.unchanged, self.skipped, self.errors
+        )
+    }
+}
+
+/// A file to import, in the format it's read as
+struct Source {
+    path: PathBuf,
+    format: String,
+    articles: usize,
+    counts: Counts,
+}
+
+#[derive(Default)]
+struct Report {
+    kept_ids: usize,
+    imported_fields: HashMap<&'static str, usize>,
+    merged_fields: HashMap<&'static str, usize>,
+}
+
+fn count_fields(counts: &mut HashMap<&'static str, usize>, fields: &[&'static str]) {
+    for field in fields {
+        *counts.entry(field).or_default() += 1;
+    }
+}
+
+/// "title 12, tags 8, read 3" in the order of `reconcile::FIELDS`
+fn format_fields(counts: &HashMap<&'static str, usize>) -> String {
+    reconcile::FIELDS
+        .iter()
+        .filter_map(|field| {
+            counts
+                .get(field)
+                .map(|count| format!("{} {}", field, count))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn is_table(format: &str) -> bool {
+    format == "csv" || format == "tsv"
+}
+
+fn load(path: &Path, format: &str) -> Result<Vec<Incoming>> {
+    let articles = match format {
+        "json" => json::import_from_json(path)?,
+        "ndjson" => json::import_from_ndjson(path)?,
+        "markdown" => markdown::import_from_markdown(path)?,
+        "netscape" => netscape::import_from_netscape(path)?,
+        "urls" => urls::import_from_urls(path)?,
+        service if services::FORMATS.contains(&service) => services::import(service, path)?,
+        _ => anyhow::bail!(
+            "Unknown format: {}. Use json, ndjson, markdown, netscape, urls, csv, tsv, or one of: {}",
+            format,
+            services::FORMATS.join(", ")
+        ),
+    };
+
+    Ok(articles)
+}
+
+/// Files under each path with their formats, detected unless one was given.
+/// Files of no recognisable format are returned separately.
+fn find_sources(paths: &[String], format: Option<&str>) -> Result<(Vec<Source>, Vec<PathBuf>)> {
+    let mut sources = Vec::new();
+    let mut unrecognised = Vec::new();
+
+    for path in paths {
+        let path = PathBuf::from(path);
+        if !path.exists() {
+            anyhow::bail!("File or directory not found: {}", path.display());
+        }
+
+        for file in detect::collect_files(&path)? {
+            let format = match format {
+                Some(format) => Some(format),
+                None => detect::detect_file_format(&file)?,
+            };
+            match format {
+                Some(format) => sources.push(Source {
+                    path: file,
+                    format: format.to_string(),
+                    articles: 0,
+                    counts: Counts::default(),
+                }),
+                None => unrecognised.push(file),
+            }
+        }
+    }
+
+    Ok((sources, unrecognised))
+}
+
+/// The article's original ID, if keeping it was asked for and it's free
+fn free_id(conn: &rusqlite::Connection, article: &Article, keep_ids: bool) -> Result<Option<i64>> {
+    if !keep_ids || article.id <= 0 {
+        return Ok(None);
+    }
+    Ok(queries::get_article_by_id(conn, article.id)?
+        .is_none()
+        .then_some(article.id))
+}
+
+fn insert(
+    conn: &rusqlite::Connection,
+    incoming: &Incoming,
+    id: Option<i64>,
+    compiled_rules: &[CompiledRule],
+) -> Result<Article> {
+    let article = &incoming.article;
+    let new_article = NewArticle {
+        id,
+        hash: article.hash.clone(),
+        url: article.url.clone(),
+        canonical_url: article.canonical_url.clone(),
+        title: article.title.clone(),
+        site: article.site.clone(),
+        description: article.description.clone(),
+        favicon_url: article.favicon_url.clone(),
+        content_markdown: article.content_markdown.clone(),
+        tags: article.tags.clone(),
+        saved_at: incoming.saved_at,
+    };
+
+    let inserted = queries::insert_article(conn, new_article)?;
+    // Rules see the imported state, so they don't replace a note
+    let inserted = queries::restore_state(conn, inserted.id, article)?;
+    rules::apply_to_new_article(conn, compiled_rules, &inserted)?;
+    insert_highlights(conn, &inserted, &article.highlights)?;
+
+    Ok(inserted)
+}
+
+fn insert_highlights(
+    conn: &rusqlite::Connection,
+    article: &Article,
+    highlights: &[Highlight],
+) -> Result<()> {
+    for highlight in highlights {
+        let position = highlight
+            .position
+            .or_else(|| find_position(article.content_markdown.as_deref(), &highlight.text));
+        queries::insert_highlight(
+            conn,
+            article.id,
+            &highlight.text,
+            position,
+            highlight.comment.as_deref(),
+        )?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
-    path: String,
+    paths: Vec<String>,
+    format: Option<String>,
     merge: bool,
+    keep_ids: bool,
     dry_run: bool,
+    no_rules: bool,
+    fetch: bool,
+    map: Vec<String>,
+    tag_delimiter: String,
+    date_format: Option<String>,
 ) -> Result<()> {
-    let import_path = PathBuf::from(&path);
-    
-    if !import_path.exists() {
-        anyhow::bail!("File or directory not found: {}", path);
-    }
-    
-    // Import articles from JSON
-    let articles = if import_path.is_file() {
-        json::import_from_json(&import_path)?
-    } else {
-        anyhow::bail!("Directory import not yet implemented. Please provide a JSON file.");
-    };
-    
-    if articles.is_empty() {
+    if !map.is_empty() && !format.as_deref().is_some_and(is_table) {
+        anyhow::bail!("--map only applies with --format csv or tsv");
+    }
+    let table_options = table::Options::new(&tag_delimiter, date_format, &map)?;
+    let (mut sources, unrecognised) = find_sources(&paths, format.as_deref())?;
+
+    // Everything is read before anything is written, so a bad file stops
+    // the import before it starts. Tables are only checked here and read
+    // again row by row as they're imported, so they needn't fit in memory;
+    // only the IDs they'd like to keep are held on to.
+    let mut loaded: Vec<Option<Vec<Incoming>>> = Vec::new();
+    let mut wanted_ids = HashSet::new();
+    for source in sources.iter_mut() {
+        let context = || format!("Failed to import {}", source.path.display());
+        if is_table(&source.format) {
+            source.articles = table::read(&source.path, &source.format, &table_options)
+                .and_then(|mut rows| {
+                    rows.try_fold(0, |count, incoming| {
+                        incoming.map(|incoming| {
+                            if keep_ids {
+                                wanted_ids.insert(incoming.article.id);
+                            }
+                            count + 1
+                        })
+                    })
+                })
+                .with_context(context)?;
+            loaded.push(None);
+        } else {
+            let articles = load(&source.path, &source.format).with_context(context)?;
+            source.articles = articles.len();
+            if keep_ids {
+                wanted_ids.extend(articles.iter().map(|incoming| incoming.article.id));
+            }
+            loaded.push(Some(articles));
+        }
+    }
+    let found: usize = sources.iter().map(|source| source.articles).sum();
+
+    for path in &unrecognised {
+        println!(
+            "  {} Skipping {}: format not recognised",
+            "⊘".yellow(),
+            path.display()
+        );
+    }
+
+    if found == 0 {
         println!("No articles found to import");
         return Ok(());
     }
-    
-    println!("Found {} article(s) to import", articles.len());
-    
+
+    println!(
+        "Found {} article(s) to import in {} file(s)",
+        found,
+        sources.len()
+    );
     if dry_run {
-        println!("\n{} (dry run - no changes will be made)", "ℹ".cyan().bold());
-        for article in &articles {
-            println!(
-                "  {} - {}",
-                article.id,
-                article.title.as_deref().unwrap_or("<no title>")
-            );
-        }
-        return Ok(());
+        println!(
+            "\n{} (dry run - no changes will be made)",
+            "ℹ".cyan().bold()
+        );
     }
-    
+
+    let compiled_rules = if no_rules || dry_run {
+        Vec::new()
+    } else {
+        rules::load()?
+    };
     let conn = open_connection()?;
-    
-    let mut imported = 0;
-    let mut skipped = 0;
-    let mut errors = 0;
-    
-    for article in articles {
-        // Check if article already exists by hash
-        let existing = queries::find_by_hash(&conn, &article.hash)?;
-        
-        if existing.is_some() {
-            if !merge {
-                println!("  {} Skipping duplicate: {}", "⊘".yellow(), article.hash);
-                skipped += 1;
-                continue;
-            }
-            // For merge mode, we skip duplicates (could update instead in future)
-            println!("  {} Skipping existing: {}", "⊘".yellow(), article.hash);
-            skipped += 1;
-            continue;
-        }
-        
-        // Insert the article
-        let new_article = NewArticle {
-            hash: article.hash,
-            url: article.url,
-            canonical_url: article.canonical_url,
-            title: article.title,
-            site: article.site,
-            description: article.description,
-            favicon_url: article.favicon_url,
-            content_markdown: article.content_markdown,
-            tags: article.tags,
-        };
-        
-        match queries::insert_article(&conn, new_article) {
-            Ok(inserted) => {
+    // All files go in together or not at all
+    let mut tx = conn.unchecked_transaction()?;
+
+    let files: Vec<(PathBuf, String)> = sources
+        .iter()
+        .map(|source| (source.path.clone(), source.format.clone()))
+        .collect();
+    let articles: Box<dyn Iterator<Item = Result<(usize, Incoming)>>> = Box::new(
+        files
+            .into_iter()
+            .zip(loaded)
+            .enumerate()
+            .flat_map(move |(idx, ((path, format), loaded))| -> Box<dyn Iterator<Item = Result<(usize, Incoming)>>> {
+                match loaded {
+                    Some(articles) => Box::new(articles.into_iter().map(move |article| Ok((idx, article)))),
+                    None => match table::read(&path, &format, &table_options) {
+                        Ok(rows) => Box::new(rows.map(move |article| {
+                            article
+                                .map(|article| (idx, article))
+                                .with_context(|| format!("Failed to import {}", path.display()))
+                        })),
+                        Err(e) => Box::new(std::iter::once(Err(e))),
+                    },
+                }
+            }),
+    );
+
+    // Articles that can't keep their ID get one above every ID that's
+    // wanted, so they don't take one an article further down could keep
+    let mut next_id = queries::max_article_id(&tx)?
+        .max(wanted_ids.into_iter().max().unwrap_or(0))
+        + 1;
+
+    let mut report = Report::default();
+    let mut to_fetch = Vec::new();
+    // A dry run writes nothing, so repeats across files are caught here
+    let mut seen = HashSet::new();
+
+    for item in articles {
+        let (idx, incoming) = item?;
+        let article = &incoming.article;
+        let counts = &mut sources[idx].counts;
+        let title = article.title.as_deref().unwrap_or("<no title>").to_string();
+
+        match queries::find_by_hash(&tx, &article.hash)? {
+            Some(mut existing) if merge => {
+                existing.highlights = queries::list_highlights(&tx, Some(existing.id))?;
+                let merged = reconcile::merge(&existing, &incoming);
+                if merged.changed.is_empty() {
+                    counts.unchanged += 1;
+                    continue;
+                }
+
+                if !dry_run {
+                    let updated = queries::update_article(&tx, &merged.article)?;
+                    insert_highlights(&tx, &updated, &merged.new_highlights)?;
+                }
                 println!(
-                    "  {} Imported: {}",
-                    "✓".green(),
-                    inserted.title.as_deref().unwrap_or("<no title>")
+                    "  {} Merged: {} ({})",
+                    "⇄".cyan(),
+                    title,
+                    merged.changed.join(", ")
                 );
-                imported += 1;
+                count_fields(&mut report.merged_fields, &merged.changed);
+                counts.merged += 1;
+            }
+            Some(_) => {
+                println!("  {} Skipping duplicate: {}", "⊘".yellow(), article.hash);
+                counts.skipped += 1;
             }
-            Err(e) => {
-                eprintln!(
-                    "  {} Failed to import article: {}",
-                    "✗".red(),
-                    e
+            None if !seen.insert(article.hash.clone()) => {
+                println!("  {} Skipping duplicate: {}", "⊘".yellow(), article.hash);
+                counts.skipped += 1;
+            }
+            None => {
+                let kept_id = free_id(&tx, article, keep_ids)?;
+                let id = match kept_id {
+                    None if keep_ids => {
+                        next_id += 1;
+                        Some(next_id - 1)
+                    }
+                    id => id,
+                };
+                if !dry_run {
+                    // A failed article leaves nothing behind, not even half of itself
+                    let mut savepoint = tx.savepoint()?;
+                    match insert(&savepoint, &incoming, id, &compiled_rules) {
+                        Ok(inserted) => {
+                            savepoint.commit()?;
+                            if fetch && inserted.content_markdown.is_none() {
+                                to_fetch.push(inserted.id);
+                            }
+                        }
+                        Err(e) => {
+                            savepoint.rollback()?;
+                            eprintln!("  {} Failed to import article: {}", "✗".red(), e);
+                            counts.errors += 1;
+                            continue;
+                        }
+                    }
+                }
+                println!("  {} Imported: {}", "✓".green(), title);
+                count_fields(
+                    &mut report.imported_fields,
+                    &reconcile::present_fields(&incoming),
                 );
-                errors += 1;
+                report.kept_ids += kept_id.is_some() as usize;
+                counts.imported += 1;
             }
         }
     }
-    
+
+    let queued = if to_fetch.is_empty() {
+        0
+    } else {
+        queries::queue_fetch(&tx, &to_fetch)?
+    };
+    if !dry_run {
+        tx.commit()?;
+    }
+
+    let mut total = Counts::default();
+    for source in &sources {
+        total.add(&source.counts);
+    }
+
+    if dry_run || sources.len() > 1 {
+        println!();
+        for source in &sources {
+            println!(
+                "  {} ({}, {} article(s)): {}",
+                source.path.display(),
+                source.format,
+                source.articles,
+                source.counts
+            );
+        }
+    }
+
     println!(
-        "\n{} Import complete: {} imported, {} skipped, {} errors",
+        "\n{} Import {}: {}",
         "✓".green().bold(),
-        imported,
-        skipped,
-        errors
+        if dry_run { "preview" } else { "complete" },
+        total
     );
-    
+    if total.imported > 0 {
+        println!(
+            "  Imported fields: {}",
+            format_fields(&report.imported_fields)
+        );
+    }
+    if total.merged > 0 {
+        println!("  Merged fields: {}", format_fields(&report.merged_fields));
+    }
+    if keep_ids {
+        println!(
+            "  Kept {} of {} original ID(s)",
+            report.kept_ids, total.imported
+        );
+    }
+
+    if queued > 0 {
+        println!(
+            "{} {} article(s) queued for fetching. Run `stash fetch` to download their content",
+            "ℹ".cyan().bold(),
+            queued
+        );
+    }
+
     Ok(())
 }
-
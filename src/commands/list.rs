@@ -1,16 +1,19 @@
 use anyhow::{Context, Result, bail};
+use colored::Colorize;
 
 use crate::{
     db::{self, open_connection},
-    ui,
+    goals, ui,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     all: bool,
     archived: bool,
     format: String,
     limit: i64,
     starred: bool,
+    snoozed: bool,
     tags: Vec<String>,
     sort: String,
     reverse: bool,
@@ -29,7 +32,7 @@ pub fn execute(
     }
 
     let articles = db::queries::list_articles_filtered(
-        &conn, limit, all, archived, starred, &tags, &sort, reverse
+        &conn, limit, all, archived, starred, snoozed, &tags, &sort, reverse
     )
     .context("Failed to query articles")?;
 
@@ -40,7 +43,7 @@ pub fn execute(
 
     // If browser flag is set, render in browser instead
     if browser {
-        return crate::ui::browser::render_browser(&articles, all, archived)
+        return crate::ui::browser::render_browser(&conn, &articles, all, archived, None)
             .context("Failed to render articles in browser");
     }
 
@@ -56,5 +59,10 @@ pub fn execute(
     crate::ui::list::render_articles(&articles, output_format, all, archived)
         .context("Failed to render articles")?;
 
+    // Best effort: a broken config shouldn't stop listing
+    if let Ok(Some(warning)) = goals::check_budgets(&conn) {
+        eprintln!("{} {}", "⚠".yellow().bold(), warning);
+    }
+
     Ok(())
 }
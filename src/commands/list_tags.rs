@@ -1,10 +1,22 @@
 use anyhow::Result;
 
-use crate::db::{open_connection, queries::get_all_tags_with_counts};
+use crate::db::{open_connection, queries::{get_all_tags_with_counts, get_tag_counts}};
+use crate::ui::tags::render_tag_tree;
 
-pub fn execute() -> Result<()> {
+pub fn execute(tree: bool) -> Result<()> {
     let conn = open_connection()?;
     
+    if tree {
+        let counts = get_tag_counts(&conn)?;
+        
+        if counts.is_empty() {
+            println!("No tags found!");
+        } else {
+            render_tag_tree(&counts);
+        }
+        return Ok(());
+    }
+    
     let tags_with_counts = get_all_tags_with_counts(&conn)?;
     
     if tags_with_counts.is_empty() {
@@ -18,4 +30,3 @@ pub fn execute() -> Result<()> {
     
     Ok(())
 }
-
@@ -10,7 +10,7 @@ pub fn execute(ids: &[i64], all: bool) -> Result<()> {
     let articles_affected = if all {
         set_read_all(&conn, true, false)?
     } else {
-        set_read_by_ids(&conn, &ids, true)?
+        set_read_by_ids(&conn, ids, true)?
     };
 
     if articles_affected.is_empty() {
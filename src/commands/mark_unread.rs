@@ -11,7 +11,7 @@ pub fn execute(ids: &[i64], all: bool) -> Result<()> {
     let articles_affected = if all {
         set_read_all(&conn, false, false)?
     } else {
-        set_read_by_ids(&conn, &ids, false)?
+        set_read_by_ids(&conn, ids, false)?
     };
 
     if articles_affected.is_empty() {
@@ -2,6 +2,7 @@ pub mod add;
 pub mod config;
 pub mod edit;
 pub mod export;
+pub mod fetch;
 pub mod import;
 pub mod list;
 pub mod note;
@@ -20,4 +21,14 @@ pub mod tag_rename;
 pub mod tag_stats;
 pub mod list_tags;
 pub mod tui;
-pub mod search;
\ No newline at end of file
+pub mod search;
+pub mod view;
+pub mod rules;
+pub mod highlight;
+pub mod related;
+pub mod dedupe;
+pub mod queue;
+pub mod snooze;
+pub mod stats;
+pub mod goals;
+pub mod sync;
@@ -5,6 +5,7 @@ use crate::{
         open_connection,
         queries::{find_by_ids, get_random_articles, list_articles, mark_read_by_ids},
     },
+    queue,
     ui::list::{OutputFormat, render_articles},
 };
 
@@ -29,7 +30,7 @@ fn open_in_browser(url: &str) -> Result<()> {
     // Special handling for WSL - use Windows commands
     if is_wsl() {
         let output = std::process::Command::new("cmd.exe")
-            .args(&["/c", "start", url])
+            .args(["/c", "start", url])
             .output()
             .context("Failed to execute cmd.exe. Make sure Windows commands are accessible from WSL.")?;
         
@@ -62,7 +63,11 @@ pub fn execute(ids: &[i64], random: Option<i64>, keep_unread: bool) -> Result<()
         Some(count) => get_random_articles(&conn, count, false)?,
         None => {
             if ids.is_empty() {
-                list_articles(&conn, 1, false)?
+                // The head of the reading queue, else the newest unread article
+                match queue::head(&conn)? {
+                    Some(article) => vec![article],
+                    None => list_articles(&conn, 1, false)?,
+                }
             } else {
                 find_by_ids(&conn, ids)?
             }
@@ -0,0 +1,106 @@
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::{
+    db::{models::Article, open_connection, queries},
+    queue::{self, Placement},
+    ui::{self, formatters::datetime_humanize},
+};
+
+fn print_queue(articles: &[Article]) {
+    if articles.is_empty() {
+        println!("The reading queue is empty. Add articles with `stash queue add <ids>`");
+        return;
+    }
+
+    println!("{} ({})", "Reading queue".bold(), articles.len());
+    for (idx, article) in articles.iter().enumerate() {
+        println!(
+            "  {:>3}. {} {}  {}",
+            idx + 1,
+            format!("#{}", article.id).cyan(),
+            article.title.as_deref().unwrap_or("<no title>"),
+            format!(
+                "{} · saved {}",
+                article.site.as_deref().unwrap_or("-"),
+                datetime_humanize(article.saved_at)
+            )
+            .dimmed()
+        );
+    }
+}
+
+pub fn execute_add(ids: &[i64], top: bool, after: Option<i64>) -> Result<()> {
+    let placement = match (top, after) {
+        (true, _) => Placement::Top,
+        (false, Some(id)) => Placement::After(id),
+        (false, None) => Placement::Bottom,
+    };
+
+    let conn = open_connection()?;
+    let articles = queue::enqueue(&conn, ids, placement)?;
+
+    println!("{} Queued {} article(s)\n", "✓".green().bold(), ids.len());
+    print_queue(&articles);
+
+    Ok(())
+}
+
+pub fn execute_remove(ids: &[i64]) -> Result<()> {
+    let conn = open_connection()?;
+    let removed = queue::dequeue(&conn, ids)?;
+
+    if removed == 0 {
+        println!("None of the given articles were queued");
+    } else {
+        println!(
+            "{} Removed {} article(s) from the queue",
+            "✓".green().bold(),
+            removed
+        );
+    }
+
+    Ok(())
+}
+
+pub fn execute_move(id: i64, position: usize) -> Result<()> {
+    if position == 0 {
+        bail!("Queue positions start at 1");
+    }
+
+    let conn = open_connection()?;
+    if !queue::is_queued(&conn, id)? {
+        bail!("Article {} is not in the queue", id);
+    }
+
+    let articles = queue::enqueue(&conn, &[id], Placement::Position(position))?;
+    print_queue(&articles);
+
+    Ok(())
+}
+
+pub fn execute_next() -> Result<()> {
+    let conn = open_connection()?;
+
+    let Some(article) = queue::head(&conn)? else {
+        println!("The reading queue is empty. Add articles with `stash queue add <ids>`");
+        return Ok(());
+    };
+
+    // Opening marks the article read, which takes it out of the queue
+    super::open::execute(&[article.id], None, false)
+}
+
+pub fn execute_show(format: String) -> Result<()> {
+    let conn = open_connection()?;
+    let articles = queries::list_queue(&conn)?;
+
+    match format.as_str() {
+        "table" => print_queue(&articles),
+        "json" => ui::list::render_articles(&articles, ui::list::OutputFormat::Json, false, false)?,
+        "ids" => ui::list::render_articles(&articles, ui::list::OutputFormat::Ids, false, false)?,
+        _ => bail!("Invalid format '{}'. Use table, json or ids", format),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+
+use crate::{
+    db::{open_connection, queries},
+    similarity, ui,
+};
+
+pub fn execute(id: i64, limit: usize, format: String, browser: bool) -> Result<()> {
+    let conn = open_connection()?;
+
+    let article = match queries::get_article_by_id(&conn, id)? {
+        Some(article) => article,
+        None => bail!("Article with ID {} not found", id),
+    };
+
+    let related = similarity::related_articles(&conn, &article, limit)
+        .context("Failed to find related articles")?;
+
+    if related.is_empty() {
+        println!("No related articles found for {}", id);
+        return Ok(());
+    }
+
+    let articles: Vec<_> = related.into_iter().map(|r| r.article).collect();
+
+    if browser {
+        return ui::browser::render_browser(&conn, &articles, true, false, None)
+            .context("Failed to render articles in browser");
+    }
+
+    let output_format = match format.as_str() {
+        "json" => ui::list::OutputFormat::Json,
+        "ids" => ui::list::OutputFormat::Ids,
+        "table" => ui::list::OutputFormat::Table,
+        _ => {
+            bail!("Invalid format '{}'. Use table, json or ids", format);
+        }
+    };
+
+    if matches!(output_format, ui::list::OutputFormat::Table) {
+        println!(
+            "{} {}",
+            "Related to".dimmed(),
+            article.title.as_deref().unwrap_or("<no title>").bold()
+        );
+    }
+
+    ui::list::render_articles(&articles, output_format, true, false)
+        .context("Failed to render articles")?;
+
+    Ok(())
+}
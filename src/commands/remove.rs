@@ -8,9 +8,9 @@ use crate::db::{
 pub fn execute(ids: &[i64], force: bool) -> Result<()> {
     let conn = open_connection()?;
     let affected = if force {
-        delete_by_ids(&conn, &ids)?
+        delete_by_ids(&conn, ids)?
     } else {
-        archive_by_ids(&conn, &ids)?
+        archive_by_ids(&conn, ids)?
     };
 
     if affected == 0 {
@@ -19,6 +19,7 @@ pub fn execute(ids: &[i64], all: bool) -> Result<()> {
             true,  // all
             true,  // archived
             false, // starred
+            false, // snoozed
             &[],   // tags
             "time",
             false,
@@ -0,0 +1,192 @@
+use anyhow::{Result, bail};
+use colored::Colorize;
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table, presets};
+
+use crate::{
+    config,
+    db::{open_connection, queries},
+    rules::{self, Candidate},
+};
+
+pub fn execute_list() -> Result<()> {
+    let config = config::load_config()?;
+    // Compile first so broken rules are reported here too
+    rules::compile(&config.rules)?;
+
+    if config.rules.is_empty() {
+        println!(
+            "No rules configured. Add [[rules]] tables to {}",
+            config::get_config_path()?.display()
+        );
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(presets::NOTHING);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.set_header(vec![
+        Cell::new("Rule")
+            .fg(Color::Cyan)
+            .add_attribute(Attribute::Bold),
+        Cell::new("When")
+            .fg(Color::Cyan)
+            .add_attribute(Attribute::Bold),
+        Cell::new("Then")
+            .fg(Color::Cyan)
+            .add_attribute(Attribute::Bold),
+    ]);
+
+    for (idx, rule) in config.rules.iter().enumerate() {
+        let mut conditions = Vec::new();
+        if let Some(site) = &rule.site {
+            conditions.push(format!("site:{}", site));
+        }
+        if let Some(pattern) = &rule.url_pattern {
+            conditions.push(format!("url~{}", pattern));
+        }
+        if !rule.keywords.is_empty() {
+            conditions.push(format!("keywords:{}", rule.keywords.join("|")));
+        }
+        if let Some(content_type) = &rule.content_type {
+            conditions.push(format!("type:{}", content_type));
+        }
+
+        let mut actions: Vec<String> = rule.tags.iter().map(|t| format!("+{}", t)).collect();
+        if rule.star {
+            actions.push("star".to_string());
+        }
+        if rule.note.is_some() {
+            actions.push("note".to_string());
+        }
+
+        table.add_row(vec![
+            Cell::new(
+                rule.name
+                    .clone()
+                    .unwrap_or_else(|| format!("rule #{}", idx + 1)),
+            )
+            .fg(Color::White),
+            Cell::new(conditions.join(" ")).fg(Color::DarkGrey),
+            Cell::new(actions.join(" ")).fg(Color::Green),
+        ]);
+    }
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+/// Preview which rules match an existing article (by ID) or an arbitrary URL.
+/// URLs are not fetched, so keyword rules only see the title of saved articles.
+pub fn execute_test(target: String) -> Result<()> {
+    let compiled = rules::load()?;
+    let conn = open_connection()?;
+
+    let article = match target.parse::<i64>() {
+        Ok(id) => match queries::get_article_by_id(&conn, id)? {
+            Some(article) => Some(article),
+            None => bail!("Article with ID {} not found", id),
+        },
+        Err(_) => None,
+    };
+
+    let outcome = match &article {
+        Some(article) => rules::evaluate(&compiled, &Candidate::from_article(article)),
+        None => rules::evaluate(&compiled, &Candidate::new(&target, None, None)),
+    };
+
+    if outcome.matched.is_empty() {
+        println!("No rules match {}", target);
+        return Ok(());
+    }
+
+    println!("{} {}", "Matched:".bold(), outcome.matched.join(", "));
+    if !outcome.tags.is_empty() {
+        println!("{} {}", "Tags:".bold(), outcome.tags.join(", "));
+    }
+    if outcome.star {
+        println!("{} yes", "Star:".bold());
+    }
+    if let Some(note) = &outcome.note {
+        println!("{} {}", "Note:".bold(), note);
+    }
+
+    if let Some(article) = &article {
+        let changes = rules::changes_for(article, &outcome);
+        if changes.is_empty() {
+            println!("\nArticle {} already matches these rules", article.id);
+        } else {
+            println!(
+                "\nApplying would change article {}: {}",
+                article.id,
+                changes.describe()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn execute_apply(ids: &[i64], all: bool, dry_run: bool) -> Result<()> {
+    if ids.is_empty() && !all {
+        bail!("Specify article IDs or use --all");
+    }
+
+    let compiled = rules::load()?;
+    if compiled.is_empty() {
+        println!("No rules configured");
+        return Ok(());
+    }
+
+    let conn = open_connection()?;
+
+    let articles = if all {
+        queries::list_articles(&conn, i64::MAX, true)?
+            .into_iter()
+            .filter(|a| !a.archived)
+            .collect()
+    } else {
+        queries::find_by_ids(&conn, ids)?
+    };
+
+    let mut changed = 0;
+    for article in &articles {
+        let outcome = rules::evaluate(&compiled, &Candidate::from_article(article));
+        let changes = rules::changes_for(article, &outcome);
+        if changes.is_empty() {
+            continue;
+        }
+
+        println!(
+            "  {} {} {} ({})",
+            if dry_run { "~".yellow() } else { "✓".green() },
+            article.id,
+            article.title.as_deref().unwrap_or("<no title>"),
+            changes.describe()
+        );
+
+        if !dry_run {
+            rules::apply_changes(&conn, article, &changes)?;
+        }
+        changed += 1;
+    }
+
+    if dry_run {
+        println!(
+            "\n{} {} of {} article(s) would change (dry run - no changes made)",
+            "ℹ".cyan().bold(),
+            changed,
+            articles.len()
+        );
+    } else {
+        println!(
+            "\n{} Updated {} of {} article(s)",
+            "✓".green().bold(),
+            changed,
+            articles.len()
+        );
+    }
+
+    Ok(())
+}
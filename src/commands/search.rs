@@ -5,6 +5,7 @@ use crate::{
     ui,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     query: String,
     all: bool,
@@ -29,7 +30,7 @@ pub fn execute(
 
     // If browser flag is set, render in browser instead
     if browser {
-        return crate::ui::browser::render_browser(&articles, all, archived)
+        return crate::ui::browser::render_browser(&conn, &articles, all, archived, None)
             .context("Failed to render articles in browser");
     }
 
@@ -0,0 +1,43 @@
+use anyhow::{Result, bail};
+use chrono::Local;
+use colored::Colorize;
+
+use crate::{
+    dates,
+    db::{open_connection, queries},
+    ui::list::{OutputFormat, render_articles},
+};
+
+pub fn execute(ids: &[i64], until: Option<String>, clear: bool) -> Result<()> {
+    let until = match until {
+        Some(spec) if !clear => Some(dates::parse_until(&spec)?),
+        _ => None,
+    };
+
+    let conn = open_connection()?;
+
+    let found = queries::find_by_ids(&conn, ids)?;
+    if let Some(missing) = ids.iter().find(|id| !found.iter().any(|a| a.id == **id)) {
+        bail!("Article with ID {} not found", missing);
+    }
+
+    let articles = queries::snooze_by_ids(&conn, ids, until)?;
+
+    match until {
+        Some(until) => println!(
+            "{} Snoozed {} article(s) until {}",
+            "✓".green().bold(),
+            articles.len(),
+            until.with_timezone(&Local).format("%a %b %-d %Y, %H:%M")
+        ),
+        None => println!(
+            "{} Cleared snooze on {} article(s)",
+            "✓".green().bold(),
+            articles.len()
+        ),
+    }
+
+    render_articles(&articles, OutputFormat::Table, false, false)?;
+
+    Ok(())
+}
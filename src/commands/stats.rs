@@ -0,0 +1,187 @@
+use anyhow::{Context, Result, bail};
+use chrono::{Datelike, Duration, Local, NaiveDate, Utc};
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+use crate::{
+    dates,
+    db::{open_connection, queries},
+    stats::{self, ReadRatio, Stats},
+};
+
+const BAR_WIDTH: usize = 30;
+
+/// Weeks shown in the heatmap without `--since`, and at most with it
+const HEATMAP_WEEKS: i64 = 26;
+const MAX_HEATMAP_WEEKS: i64 = 53;
+
+fn bar(value: usize, max: usize) -> String {
+    if max == 0 || value == 0 {
+        return String::new();
+    }
+    "█".repeat(((value * BAR_WIDTH).div_ceil(max)).max(1))
+}
+
+fn format_days(days: f64) -> String {
+    if days < 1.0 {
+        format!("{:.0} hours", days * 24.0)
+    } else {
+        format!("{:.1} days", days)
+    }
+}
+
+fn print_ratios(heading: &str, ratios: &[ReadRatio]) {
+    println!("\n{}", heading.bold());
+    if ratios.is_empty() {
+        println!("  {}", "Not enough articles yet".dimmed());
+        return;
+    }
+
+    let width = ratios
+        .iter()
+        .map(|r| r.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for ratio in ratios {
+        println!(
+            "  {:<width$}  {:>3}/{:<3} {:>4.0}%",
+            ratio.name,
+            ratio.read,
+            ratio.saved,
+            ratio.ratio * 100.0,
+            width = width
+        );
+    }
+}
+
+/// GitHub-style grid: one column per week, one row per weekday
+fn print_heatmap(daily_reads: &BTreeMap<NaiveDate, usize>, first_day: NaiveDate, today: NaiveDate) {
+    let first_week = stats::week_start(first_day);
+    let weeks = ((stats::week_start(today) - first_week).num_weeks() + 1) as usize;
+    let max = daily_reads
+        .range(first_day..=today)
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0);
+
+    // Month labels above the first column of each month
+    let mut months = String::new();
+    let mut last_month = None;
+    for col in 0..weeks {
+        let monday = first_week + Duration::weeks(col as i64);
+        if last_month != Some(monday.month()) && months.chars().count() <= col * 2 {
+            months.push_str(&" ".repeat(col * 2 - months.chars().count()));
+            months.push_str(&monday.format("%b").to_string());
+            last_month = Some(monday.month());
+        }
+    }
+    println!("      {}", months.dimmed());
+
+    for (row, label) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().enumerate() {
+        let mut line = format!("  {:<4}", label).dimmed().to_string();
+        for col in 0..weeks {
+            let day = first_week + Duration::days((col * 7 + row) as i64);
+            let cell = if day > today || day < first_day {
+                "  ".normal()
+            } else {
+                match daily_reads.get(&day).copied().unwrap_or(0) {
+                    0 => "· ".dimmed(),
+                    count => {
+                        let level = (count * 4).div_ceil(max).clamp(1, 4);
+                        format!("{} ", ["░", "▒", "▓", "█"][level - 1]).green()
+                    }
+                }
+            };
+            line.push_str(&cell.to_string());
+        }
+        println!("{}", line);
+    }
+
+    println!("      {} less {} more", "·".dimmed(), "░ ▒ ▓ █".green());
+}
+
+fn print_stats(stats: &Stats) {
+    let today = Local::now().date_naive();
+
+    let window = match stats.since {
+        Some(since) => format!("since {}", stats::local_day(since)),
+        None => "all time".to_string(),
+    };
+    println!("{} ({})\n", "Reading stats".bold(), window);
+
+    println!(
+        "  Saved {}  ·  Read {}  ·  Backlog {}",
+        stats.saved.to_string().cyan().bold(),
+        stats.read.to_string().green().bold(),
+        stats.backlog.to_string().yellow().bold()
+    );
+    match (stats.avg_days_to_read, stats.median_days_to_read) {
+        (Some(avg), Some(median)) => println!(
+            "  Time to read: {} on average, {} median",
+            format_days(avg),
+            format_days(median)
+        ),
+        _ => println!("  Time to read: {}", "nothing read yet".dimmed()),
+    }
+    println!(
+        "  Streak: {} day(s), longest {}",
+        stats.streak.current.to_string().bold(),
+        stats.streak.longest
+    );
+
+    println!("\n{}  {}", "Per week".bold(), "saved / read".dimmed());
+    let max = stats
+        .weeks
+        .iter()
+        .map(|w| w.saved.max(w.read))
+        .max()
+        .unwrap_or(0);
+    for week in &stats.weeks {
+        println!(
+            "  {}  {:>3} {}",
+            week.week.format("%b %d"),
+            week.saved,
+            bar(week.saved, max).cyan()
+        );
+        println!("          {:>3} {}", week.read, bar(week.read, max).green());
+    }
+
+    println!("\n{}", "Backlog age".bold());
+    let max = stats.backlog_age.iter().map(|b| b.count).max().unwrap_or(0);
+    for bucket in &stats.backlog_age {
+        println!(
+            "  {:<12} {:>4} {}",
+            bucket.label,
+            bucket.count,
+            bar(bucket.count, max).yellow()
+        );
+    }
+
+    print_ratios("Top sites by read ratio", &stats.top_sites);
+    print_ratios("Top tags by read ratio", &stats.top_tags);
+
+    println!("\n{}", "Activity".bold());
+    let first_day = match stats.since {
+        Some(since) => stats::local_day(since).max(today - Duration::weeks(MAX_HEATMAP_WEEKS - 1)),
+        None => stats::week_start(today) - Duration::weeks(HEATMAP_WEEKS - 1),
+    };
+    print_heatmap(&stats.daily_reads, first_day, today);
+}
+
+pub fn execute(format: String, since: Option<String>) -> Result<()> {
+    let since = since.as_deref().map(dates::parse_since).transpose()?;
+
+    let conn = open_connection()?;
+    let articles =
+        queries::list_articles(&conn, i64::MAX, true).context("Failed to load articles")?;
+
+    let stats = stats::compute(&articles, since, Utc::now());
+
+    match format.as_str() {
+        "text" => print_stats(&stats),
+        "json" => println!("{}", serde_json::to_string_pretty(&stats)?),
+        _ => bail!("Invalid format '{}'. Use text or json", format),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use anyhow::{Result, bail};
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::{
+    db::open_connection,
+    sync::{
+        folder,
+        obsidian::{self, INDEX_NAME},
+    },
+};
+
+pub fn execute(dir: String) -> Result<()> {
+    let dir = PathBuf::from(dir);
+    let conn = open_connection()?;
+    let first = folder::device_id(&conn)?.is_none();
+    let summary = folder::sync(&conn, &dir)?;
+
+    if first {
+        println!(
+            "{} Syncing as device {}, starting with the whole library",
+            "→".cyan(),
+            summary.device
+        );
+    }
+    if summary.malformed > 0 {
+        println!(
+            "  {} Skipped {} unreadable line(s) in the logs of other devices",
+            "⚠".yellow(),
+            summary.malformed
+        );
+    }
+    if summary.pending > 0 {
+        println!(
+            "  {} {} change(s) wait for articles that haven't arrived yet",
+            "⚠".yellow(),
+            summary.pending
+        );
+    }
+
+    println!(
+        "{} Synced with {}: {} change(s) sent, {} merged from {} other device(s)",
+        "✓".green().bold(),
+        dir.display(),
+        summary.written,
+        summary.applied,
+        summary.devices
+    );
+
+    Ok(())
+}
+
+pub fn execute_status() -> Result<()> {
+    let conn = open_connection()?;
+    match folder::device_id(&conn)? {
+        Some(device) => println!(
+            "Syncing as device {}, with {} change(s) to send. Run `stash sync <dir>` to sync",
+            device,
+            folder::unsent_changes(&conn)?
+        ),
+        None => {
+            println!("Not syncing yet. Run `stash sync <dir>` with a folder your devices share")
+        }
+    }
+
+    Ok(())
+}
+
+pub fn execute_obsidian(vault: String, folder: String, pull_notes: bool) -> Result<()> {
+    let vault = PathBuf::from(vault);
+    if !vault.is_dir() {
+        bail!("Vault not found: {}", vault.display());
+    }
+
+    let dir = vault.join(&folder);
+    let conn = open_connection()?;
+    let summary = obsidian::sync(&conn, &dir, pull_notes)?;
+
+    if summary.pulled > 0 {
+        println!(
+            "{} Pulled {} note(s) edited in the vault",
+            "⇐".cyan(),
+            summary.pulled
+        );
+    }
+    for file in &summary.overwritten {
+        println!(
+            "  {} Overwrote {}, which was edited in the vault (use --pull-notes to keep note edits)",
+            "⚠".yellow(),
+            file
+        );
+    }
+
+    println!(
+        "{} Synced to {}: {} written, {} unchanged, {} removed",
+        "✓".green().bold(),
+        dir.display(),
+        summary.written,
+        summary.unchanged,
+        summary.removed
+    );
+    println!("  Open \"{}\" for links to every article", INDEX_NAME);
+
+    Ok(())
+}
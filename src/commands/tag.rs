@@ -1,44 +1,16 @@
 use anyhow::Result;
+use colored::Colorize;
 use std::collections::HashSet;
 
 use crate::{
-    db::{open_connection, queries::{get_article_by_id, update_tags}},
+    db::{models::Article, open_connection, queries::{get_article_by_id, update_tags}, tags as tag_tree},
+    similarity::{self, Document},
     ui::list::{OutputFormat, render_articles},
 };
 
-/// Validates that a tag is lowercase, alphanumeric, and may contain hyphens
-/// Valid examples: "rust", "rust-lang", "web-dev-101"
-/// Invalid: "Rust", "rust_lang", "rust--lang", "-rust", "rust-"
-fn validate_tag(tag: &str) -> bool {
-    if tag.is_empty() {
-        return false;
-    }
-    
-    // Must not start or end with hyphen
-    if tag.starts_with('-') || tag.ends_with('-') {
-        return false;
-    }
-    
-    let mut prev_was_hyphen = false;
-    for c in tag.chars() {
-        if c == '-' {
-            // No consecutive hyphens
-            if prev_was_hyphen {
-                return false;
-            }
-            prev_was_hyphen = true;
-        } else if c.is_ascii_lowercase() || c.is_ascii_digit() {
-            prev_was_hyphen = false;
-        } else {
-            // Invalid character (uppercase, underscore, special chars, etc.)
-            return false;
-        }
-    }
-    
-    true
-}
+const SUGGESTION_LIMIT: usize = 5;
 
-pub fn execute(id: &i64, operations: &[String]) -> Result<()> {
+pub fn execute(id: &i64, operations: &[String], suggest: bool) -> Result<()> {
     let conn = open_connection()?;
     
     // Fetch the article
@@ -50,6 +22,10 @@ pub fn execute(id: &i64, operations: &[String]) -> Result<()> {
         }
     };
     
+    if suggest {
+        return show_suggestions(&conn, &article);
+    }
+    
     // If no operations provided, just list current tags
     if operations.is_empty() {
         if article.tags.is_empty() {
@@ -71,8 +47,8 @@ pub fn execute(id: &i64, operations: &[String]) -> Result<()> {
         if let Some(tag) = op.strip_prefix('+') {
             // Add tag
             let tag = tag.to_lowercase();
-            if !validate_tag(&tag) {
-                eprintln!("Invalid tag format: '{}'. Tags must be lowercase alphanumeric with hyphens only.", tag);
+            if !tag_tree::is_valid(&tag) {
+                eprintln!("Invalid tag format: '{}'. Tags must be lowercase alphanumeric with hyphens, nested with '/'.", tag);
                 std::process::exit(2);
             }
             tags.insert(tag);
@@ -106,8 +82,32 @@ pub fn execute(id: &i64, operations: &[String]) -> Result<()> {
     let updated_article = update_tags(&conn, *id, tags_vec)?;
     
     // Display updated article
-    render_articles(&vec![updated_article], OutputFormat::Table, false, false)?;
+    render_articles(&[updated_article], OutputFormat::Table, false, false)?;
     
     Ok(())
 }
 
+
+fn show_suggestions(conn: &rusqlite::Connection, article: &Article) -> Result<()> {
+    let suggestions =
+        similarity::suggest_tags(conn, &Document::from_article(article), SUGGESTION_LIMIT)?;
+    
+    if suggestions.is_empty() {
+        println!("No tag suggestions for article {}", article.id);
+        return Ok(());
+    }
+    
+    println!("Suggested tags for article {}:", article.id);
+    for suggestion in &suggestions {
+        println!(
+            "  {:<24} {}",
+            suggestion.tag,
+            format!("{:.2}", suggestion.score).dimmed()
+        );
+    }
+    
+    let ops: Vec<String> = suggestions.iter().map(|s| format!("+{}", s.tag)).collect();
+    println!("\nAdd them with: stash tag {} {}", article.id, ops.join(" "));
+    
+    Ok(())
+}
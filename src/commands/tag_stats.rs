@@ -7,11 +7,12 @@ use crate::db::{open_connection, queries};
 pub fn execute(sort: String, min_count: i64) -> Result<()> {
     let conn = open_connection()?;
     
-    let mut tag_counts = queries::get_all_tags_with_counts(&conn)?;
+    // Counts for parent tags (lang) include their nested tags (lang/rust)
+    let mut tag_counts = queries::get_tag_counts(&conn)?;
     
     // Filter by minimum count
     if min_count > 1 {
-        tag_counts.retain(|(_, count)| *count >= min_count as usize);
+        tag_counts.retain(|c| c.total >= min_count as usize);
     }
     
     if tag_counts.is_empty() {
@@ -22,7 +23,7 @@ pub fn execute(sort: String, min_count: i64) -> Result<()> {
     // Sort based on option
     match sort.as_str() {
         "count" => {
-            tag_counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            tag_counts.sort_by(|a, b| b.total.cmp(&a.total).then(a.tag.cmp(&b.tag)));
         }
         "alpha" => {
             // Already sorted alphabetically by default
@@ -44,15 +45,20 @@ pub fn execute(sort: String, min_count: i64) -> Result<()> {
         Cell::new("Count")
             .fg(Color::Cyan)
             .add_attribute(Attribute::Bold),
+        Cell::new("Direct")
+            .fg(Color::Cyan)
+            .add_attribute(Attribute::Bold),
     ]);
     
     let total_tags = tag_counts.len();
-    let total_uses: usize = tag_counts.iter().map(|(_, count)| count).sum();
+    // Parent totals overlap their children, so only direct uses are summed
+    let total_uses: usize = tag_counts.iter().map(|c| c.direct).sum();
     
-    for (tag, count) in &tag_counts {
+    for count in &tag_counts {
         table.add_row(vec![
-            Cell::new(tag).fg(Color::White),
-            Cell::new(count).fg(Color::Green),
+            Cell::new(&count.tag).fg(Color::White),
+            Cell::new(count.total).fg(Color::Green),
+            Cell::new(count.direct).fg(Color::DarkGrey),
         ]);
     }
     
@@ -0,0 +1,152 @@
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table, presets};
+
+use crate::{
+    db::{open_connection, queries, search_query},
+    ui,
+};
+
+/// `stash view <name>` resolves these to subcommands before looking up a view
+const RESERVED_VIEW_NAMES: [&str; 5] = ["save", "list", "delete", "rm", "help"];
+
+/// View names are used on the command line and in browser URLs, so keep them
+/// to lowercase letters, digits and hyphens (same rules as tags)
+fn validate_view_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && !name.contains("--")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+pub fn execute_save(name: String, query: Vec<String>, force: bool) -> Result<()> {
+    if RESERVED_VIEW_NAMES.contains(&name.as_str()) {
+        bail!(
+            "'{}' is a view subcommand, so a view with that name could never be opened. Pick another name.",
+            name
+        );
+    }
+
+    if !validate_view_name(&name) {
+        bail!(
+            "Invalid view name '{}'. Use lowercase letters, digits and hyphens only.",
+            name
+        );
+    }
+
+    let query = query.join(" ");
+
+    // Reject broken queries now rather than every time the view is opened
+    search_query::parse(&query).context("Invalid search query")?;
+
+    let conn = open_connection()?;
+
+    if queries::find_saved_view_by_name(&conn, &name)?.is_some() && !force {
+        bail!(
+            "View '{}' already exists. Use --force to overwrite it.",
+            name
+        );
+    }
+
+    let view = queries::upsert_saved_view(&conn, &name, &query)?;
+    let count = queries::count_saved_view(&conn, &view)?;
+
+    println!(
+        "{} Saved view '{}' ({} matching article(s))",
+        "✓".green().bold(),
+        view.name,
+        count
+    );
+
+    Ok(())
+}
+
+pub fn execute_show(name: String, format: String, limit: i64, browser: bool) -> Result<()> {
+    let conn = open_connection()?;
+
+    let view = match queries::find_saved_view_by_name(&conn, &name)? {
+        Some(view) => view,
+        None => bail!("View '{}' not found. See `stash view list`.", name),
+    };
+
+    let articles = queries::evaluate_saved_view(&conn, &view, limit)?;
+
+    if articles.is_empty() {
+        println!("No articles in view '{}'", view.name);
+        return Ok(());
+    }
+
+    if browser {
+        return ui::browser::render_browser(&conn, &articles, false, false, Some(&view.name))
+            .context("Failed to render articles in browser");
+    }
+
+    let output_format = match format.as_str() {
+        "json" => ui::list::OutputFormat::Json,
+        "ids" => ui::list::OutputFormat::Ids,
+        "table" => ui::list::OutputFormat::Table,
+        _ => {
+            bail!("Invalid format '{}'. Use table, json or ids", format);
+        }
+    };
+
+    ui::list::render_articles(&articles, output_format, false, false)
+        .context("Failed to render articles")?;
+
+    Ok(())
+}
+
+pub fn execute_list() -> Result<()> {
+    let conn = open_connection()?;
+
+    let views = queries::list_saved_views(&conn)?;
+
+    if views.is_empty() {
+        println!("No saved views. Create one with `stash view save <name> <query>`");
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(presets::NOTHING);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.set_header(vec![
+        Cell::new("View")
+            .fg(Color::Cyan)
+            .add_attribute(Attribute::Bold),
+        Cell::new("Query")
+            .fg(Color::Cyan)
+            .add_attribute(Attribute::Bold),
+        Cell::new("Count")
+            .fg(Color::Cyan)
+            .add_attribute(Attribute::Bold),
+    ]);
+
+    for view in &views {
+        let count = queries::count_saved_view(&conn, view)?;
+        table.add_row(vec![
+            Cell::new(&view.name).fg(Color::White),
+            Cell::new(&view.query).fg(Color::DarkGrey),
+            Cell::new(count).fg(Color::Green),
+        ]);
+    }
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+pub fn execute_delete(name: String) -> Result<()> {
+    let conn = open_connection()?;
+
+    if queries::delete_saved_view(&conn, &name)? == 0 {
+        println!("No view named '{}'", name);
+    } else {
+        println!("{} Deleted view '{}'", "✓".green().bold(), name);
+    }
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub defaults: Defaults,
@@ -10,16 +10,12 @@ pub struct Config {
     
     #[serde(default)]
     pub fetch: Fetch,
-}
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            defaults: Defaults::default(),
-            colors: Colors::default(),
-            fetch: Fetch::default(),
-        }
-    }
+    #[serde(default, skip_serializing_if = "Goals::is_empty")]
+    pub goals: Goals,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<Rule>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +84,62 @@ impl Default for Fetch {
     }
 }
 
+/// Reading goals and backlog budgets; unset ones aren't tracked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Goals {
+    /// Articles to read each week
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reads_per_week: Option<u32>,
+
+    /// Most unread articles to keep around
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unread: Option<u32>,
+
+    /// Oldest an unread article may get, in days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unread_age_days: Option<u32>,
+}
+
+impl Goals {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// An automatic tagging rule, written as a `[[rules]]` table in config.toml.
+/// Every condition that is set must match; at least one is required.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Domain, including subdomains (`github.com` matches `gist.github.com`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+
+    /// Regular expression tested against the URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_pattern: Option<String>,
+
+    /// Case-insensitive keywords; any one found in the title or content matches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+
+    /// One of `article`, `pdf`, `video`, `image` or `link`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub star: bool,
+
+    /// Note template; `{title}`, `{site}`, `{url}` and `{date}` are filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
 // Default functions for serde
 fn default_editor() -> String {
     std::env::var("EDITOR")
@@ -0,0 +1,175 @@
+// User-supplied dates for flags like `snooze --until` and `--since`
+//
+// Accepts an ISO date (2026-11-01), a relative offset (12h, 3d, 2w),
+// `tomorrow`/`today`/`yesterday` or a weekday name (monday, fri), depending
+// on the direction. Calendar dates resolve to local midnight.
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc, Weekday};
+
+pub fn local_midnight(date: NaiveDate) -> DateTime<Utc> {
+    let naive = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| naive.and_utc())
+}
+
+// Offsets too large to represent are treated like any other invalid date
+fn parse_offset(spec: &str) -> Option<Duration> {
+    let unit = spec.chars().last()?;
+    let amount: i64 = spec[..spec.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => None,
+    }
+}
+
+/// Resolve a future date spec relative to `now`
+pub fn parse_until_at(spec: &str, now: DateTime<Local>) -> Result<DateTime<Utc>> {
+    let spec = spec.trim().to_lowercase();
+    let today = now.date_naive();
+
+    let until = if let Some(until) =
+        parse_offset(&spec).and_then(|offset| now.with_timezone(&Utc).checked_add_signed(offset))
+    {
+        until
+    } else if spec == "tomorrow" {
+        local_midnight(today + Duration::days(1))
+    } else if let Ok(weekday) = spec.parse::<Weekday>() {
+        // The next such day, a full week ahead if it's today
+        let days_ahead =
+            (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        let days_ahead = if days_ahead == 0 { 7 } else { days_ahead };
+        local_midnight(today + Duration::days(days_ahead as i64))
+    } else if let Ok(date) = NaiveDate::parse_from_str(&spec, "%Y-%m-%d") {
+        local_midnight(date)
+    } else {
+        bail!(
+            "Invalid date '{}'. Use YYYY-MM-DD, an offset like 3d/12h/2w, tomorrow or a weekday",
+            spec
+        );
+    };
+
+    if until <= now.with_timezone(&Utc) {
+        bail!("'{}' is not in the future", spec);
+    }
+
+    Ok(until)
+}
+
+pub fn parse_until(spec: &str) -> Result<DateTime<Utc>> {
+    parse_until_at(spec, Local::now())
+}
+
+/// Resolve a past date spec relative to `now`; offsets count backwards
+pub fn parse_since_at(spec: &str, now: DateTime<Local>) -> Result<DateTime<Utc>> {
+    let since = parse_bound_at(spec, now)?;
+    if since > now.with_timezone(&Utc) {
+        bail!("'{}' is in the future", spec.trim().to_lowercase());
+    }
+
+    Ok(since)
+}
+
+/// The end of a date range, like `--since` but allowed in the future
+pub fn parse_before(spec: &str) -> Result<DateTime<Utc>> {
+    parse_bound_at(spec, Local::now())
+}
+
+fn parse_bound_at(spec: &str, now: DateTime<Local>) -> Result<DateTime<Utc>> {
+    let spec = spec.trim().to_lowercase();
+    let today = now.date_naive();
+
+    let date = if let Some(date) =
+        parse_offset(&spec).and_then(|offset| now.with_timezone(&Utc).checked_sub_signed(offset))
+    {
+        date
+    } else if spec == "today" {
+        local_midnight(today)
+    } else if spec == "yesterday" {
+        local_midnight(today - Duration::days(1))
+    } else if let Ok(date) = NaiveDate::parse_from_str(&spec, "%Y-%m-%d") {
+        local_midnight(date)
+    } else {
+        bail!(
+            "Invalid date '{}'. Use YYYY-MM-DD, an offset like 7d/12h/4w, today or yesterday",
+            spec
+        );
+    };
+
+    Ok(date)
+}
+
+pub fn parse_since(spec: &str) -> Result<DateTime<Utc>> {
+    parse_since_at(spec, Local::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        // A Wednesday
+        Local.with_ymd_and_hms(2026, 10, 14, 15, 30, 0).unwrap()
+    }
+
+    fn day(spec: &str) -> NaiveDate {
+        parse_until_at(spec, now())
+            .unwrap()
+            .with_timezone(&Local)
+            .date_naive()
+    }
+
+    #[test]
+    fn test_parse_until() {
+        assert_eq!(
+            day("2026-11-01"),
+            NaiveDate::from_ymd_opt(2026, 11, 1).unwrap()
+        );
+        assert_eq!(day("3d"), NaiveDate::from_ymd_opt(2026, 10, 17).unwrap());
+        assert_eq!(day("2w"), NaiveDate::from_ymd_opt(2026, 10, 28).unwrap());
+        assert_eq!(
+            day("tomorrow"),
+            NaiveDate::from_ymd_opt(2026, 10, 15).unwrap()
+        );
+        assert_eq!(
+            day("Monday"),
+            NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+        );
+        assert_eq!(day("wed"), NaiveDate::from_ymd_opt(2026, 10, 21).unwrap());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let day = |spec: &str| {
+            parse_since_at(spec, now())
+                .unwrap()
+                .with_timezone(&Local)
+                .date_naive()
+        };
+        assert_eq!(day("7d"), NaiveDate::from_ymd_opt(2026, 10, 7).unwrap());
+        assert_eq!(
+            day("yesterday"),
+            NaiveDate::from_ymd_opt(2026, 10, 13).unwrap()
+        );
+        assert_eq!(
+            day("2026-01-01"),
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+        );
+        assert!(parse_since_at("2027-01-01", now()).is_err());
+        assert!(parse_since_at("monday", now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_until_rejects_past_and_garbage() {
+        assert!(parse_until_at("2026-10-01", now()).is_err());
+        assert!(parse_until_at("0d", now()).is_err());
+        assert!(parse_until_at("someday", now()).is_err());
+        assert!(parse_until_at("999999999d", now()).is_err());
+        assert!(parse_since_at("99999999999999d", now()).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+-- Saved searches ("views") that are re-evaluated every time they are shown
+CREATE TABLE IF NOT EXISTS saved_views (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    -- Name used on the command line: `stash view <name>`
+    name TEXT NOT NULL UNIQUE,
+
+    -- Search query in `stash search` syntax (e.g. "tag:rust is:starred")
+    query TEXT NOT NULL,
+
+    created_at INTEGER NOT NULL  -- Unix timestamp
+);
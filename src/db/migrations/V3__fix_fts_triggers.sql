@@ -0,0 +1,20 @@
+-- articles_fts is an external-content table, so stale rows have to be removed
+-- with the special 'delete' command and the *old* values. A plain UPDATE reads
+-- the already-updated row from `articles` and corrupts the index.
+DROP TRIGGER IF EXISTS articles_fts_update;
+DROP TRIGGER IF EXISTS articles_fts_delete;
+
+CREATE TRIGGER IF NOT EXISTS articles_fts_update AFTER UPDATE ON articles BEGIN
+    INSERT INTO articles_fts(articles_fts, rowid, title, description, content_markdown, tags)
+    VALUES ('delete', old.id, old.title, old.description, old.content_markdown, old.tags);
+    INSERT INTO articles_fts(rowid, title, description, content_markdown, tags)
+    VALUES (new.id, new.title, new.description, new.content_markdown, new.tags);
+END;
+
+CREATE TRIGGER IF NOT EXISTS articles_fts_delete AFTER DELETE ON articles BEGIN
+    INSERT INTO articles_fts(articles_fts, rowid, title, description, content_markdown, tags)
+    VALUES ('delete', old.id, old.title, old.description, old.content_markdown, old.tags);
+END;
+
+-- Repair any index already damaged by the old triggers
+INSERT INTO articles_fts(articles_fts) VALUES ('rebuild');
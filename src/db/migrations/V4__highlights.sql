@@ -0,0 +1,46 @@
+-- Quotes kept from an article, each with an optional comment
+CREATE TABLE IF NOT EXISTS highlights (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_id INTEGER NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+
+    -- The quoted text, as it appears in the article
+    text TEXT NOT NULL,
+
+    -- Character offset of the quote in content_markdown, when it was found there
+    position INTEGER,
+
+    comment TEXT,
+    created_at INTEGER NOT NULL  -- Unix timestamp
+);
+
+CREATE INDEX IF NOT EXISTS idx_highlights_article ON highlights(article_id);
+
+-- Foreign keys aren't enabled on our connections, so clean up explicitly
+CREATE TRIGGER IF NOT EXISTS highlights_article_delete AFTER DELETE ON articles BEGIN
+    DELETE FROM highlights WHERE article_id = old.id;
+END;
+
+-- Full-text index over quotes and comments, searched alongside articles_fts
+CREATE VIRTUAL TABLE IF NOT EXISTS highlights_fts USING fts5(
+    text,
+    comment,
+    content=highlights,
+    content_rowid=id
+);
+
+CREATE TRIGGER IF NOT EXISTS highlights_fts_insert AFTER INSERT ON highlights BEGIN
+    INSERT INTO highlights_fts(rowid, text, comment)
+    VALUES (new.id, new.text, new.comment);
+END;
+
+CREATE TRIGGER IF NOT EXISTS highlights_fts_update AFTER UPDATE ON highlights BEGIN
+    INSERT INTO highlights_fts(highlights_fts, rowid, text, comment)
+    VALUES ('delete', old.id, old.text, old.comment);
+    INSERT INTO highlights_fts(rowid, text, comment)
+    VALUES (new.id, new.text, new.comment);
+END;
+
+CREATE TRIGGER IF NOT EXISTS highlights_fts_delete AFTER DELETE ON highlights BEGIN
+    INSERT INTO highlights_fts(highlights_fts, rowid, text, comment)
+    VALUES ('delete', old.id, old.text, old.comment);
+END;
@@ -0,0 +1,17 @@
+-- Cached term frequencies per article, used for related articles and tag
+-- suggestions. Rows are dropped whenever the text they were built from
+-- changes and rebuilt lazily on the next lookup.
+CREATE TABLE IF NOT EXISTS term_vectors (
+    article_id INTEGER PRIMARY KEY REFERENCES articles(id) ON DELETE CASCADE,
+
+    -- JSON object of term -> weighted frequency
+    terms TEXT NOT NULL
+);
+
+CREATE TRIGGER IF NOT EXISTS term_vectors_invalidate AFTER UPDATE OF title, content_markdown ON articles BEGIN
+    DELETE FROM term_vectors WHERE article_id = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS term_vectors_article_delete AFTER DELETE ON articles BEGIN
+    DELETE FROM term_vectors WHERE article_id = old.id;
+END;
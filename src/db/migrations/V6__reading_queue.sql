@@ -0,0 +1,7 @@
+-- Reading queue: articles explicitly lined up to read next.
+-- NULL means not queued; lower positions are read first. Positions only
+-- need to be ordered, gaps are allowed.
+ALTER TABLE articles ADD COLUMN queue_position INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_articles_queue_position ON articles(queue_position)
+    WHERE queue_position IS NOT NULL;
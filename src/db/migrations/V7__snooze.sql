@@ -0,0 +1,7 @@
+-- Snoozed articles are hidden from default listings until this Unix
+-- timestamp. Once it passes the value is kept, marking the article as
+-- "woke up", until the article is read or archived.
+ALTER TABLE articles ADD COLUMN snoozed_until INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_articles_snoozed_until ON articles(snoozed_until)
+    WHERE snoozed_until IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Imported articles waiting for `stash fetch` to download their content
+ALTER TABLE articles ADD COLUMN fetch_pending INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_articles_fetch_pending ON articles(fetch_pending)
+    WHERE fetch_pending = 1;
@@ -0,0 +1,194 @@
+-- Multi-device sync through a shared folder (`stash sync <dir>`).
+-- Once this database has a device, triggers log every change to an
+-- article's synced fields, its tags and its highlights. `stash sync` writes
+-- the log to this device's file in the folder and merges the other
+-- devices' files in, field by field with the latest change winning.
+
+-- This database's device, created by the first sync. `applying` is set
+-- while other devices' changes are merged in, so they aren't logged again.
+CREATE TABLE IF NOT EXISTS sync_device (
+    id TEXT NOT NULL,
+    applying INTEGER NOT NULL DEFAULT 0
+);
+
+-- Changes made here that haven't been written to the sync folder yet.
+-- `field` is a column, `tag:<tag>`, `highlight:<created_at>:<text>`, or
+-- `article` for adding (the article's details) or deleting (null) one.
+CREATE TABLE IF NOT EXISTS sync_changes (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    hash TEXT NOT NULL,
+    field TEXT NOT NULL,
+    value TEXT NOT NULL,  -- JSON
+    changed_at INTEGER NOT NULL  -- Unix milliseconds
+);
+
+-- The change each field currently holds, for latest-wins merges.
+-- Ties on time go to the higher device ID, then the later change.
+CREATE TABLE IF NOT EXISTS sync_clock (
+    hash TEXT NOT NULL,
+    field TEXT NOT NULL,
+    changed_at INTEGER NOT NULL,
+    device TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    PRIMARY KEY (hash, field)
+);
+
+-- How far into each other device's log this database has merged
+CREATE TABLE IF NOT EXISTS sync_peers (
+    device TEXT PRIMARY KEY,
+    applied INTEGER NOT NULL
+);
+
+-- Changes from other devices to articles this database hasn't seen added
+-- yet, kept until the article turns up
+CREATE TABLE IF NOT EXISTS sync_pending (
+    hash TEXT NOT NULL,
+    field TEXT NOT NULL,
+    value TEXT NOT NULL,
+    changed_at INTEGER NOT NULL,
+    device TEXT NOT NULL,
+    seq INTEGER NOT NULL
+);
+
+CREATE TRIGGER IF NOT EXISTS sync_article_insert AFTER INSERT ON articles
+WHEN EXISTS (SELECT 1 FROM sync_device WHERE applying = 0)
+BEGIN
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    VALUES (new.hash, 'article', json_object(
+        'url', new.url,
+        'canonical_url', new.canonical_url,
+        'title', new.title,
+        'site', new.site,
+        'description', new.description,
+        'favicon_url', new.favicon_url,
+        'content_markdown', new.content_markdown,
+        'saved_at', new.saved_at
+    ), CAST(unixepoch('subsec') * 1000 AS INTEGER));
+
+    -- Imports can insert state along with the article
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'read', 'true', CAST(unixepoch('subsec') * 1000 AS INTEGER) WHERE new.read;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'archived', 'true', CAST(unixepoch('subsec') * 1000 AS INTEGER) WHERE new.archived;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'starred', 'true', CAST(unixepoch('subsec') * 1000 AS INTEGER) WHERE new.starred;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'note', json_quote(new.note), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE new.note IS NOT NULL;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'last_opened_at', json_quote(new.last_opened_at), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE new.last_opened_at IS NOT NULL;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'snoozed_until', json_quote(new.snoozed_until), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE new.snoozed_until IS NOT NULL;
+
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'tag:' || value, 'true', CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    FROM json_each(new.tags);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_article_update AFTER UPDATE ON articles
+WHEN EXISTS (SELECT 1 FROM sync_device WHERE applying = 0)
+BEGIN
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'url', json_quote(new.url), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.url IS NOT new.url;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'title', json_quote(new.title), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.title IS NOT new.title;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'description', json_quote(new.description), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.description IS NOT new.description;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'favicon_url', json_quote(new.favicon_url), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.favicon_url IS NOT new.favicon_url;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'content_markdown', json_quote(new.content_markdown), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.content_markdown IS NOT new.content_markdown;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'saved_at', json_quote(new.saved_at), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.saved_at IS NOT new.saved_at;
+
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'read', CASE WHEN new.read THEN 'true' ELSE 'false' END, CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.read IS NOT new.read;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'archived', CASE WHEN new.archived THEN 'true' ELSE 'false' END, CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.archived IS NOT new.archived;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'starred', CASE WHEN new.starred THEN 'true' ELSE 'false' END, CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.starred IS NOT new.starred;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'note', json_quote(new.note), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.note IS NOT new.note;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'last_opened_at', json_quote(new.last_opened_at), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.last_opened_at IS NOT new.last_opened_at;
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'snoozed_until', json_quote(new.snoozed_until), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    WHERE old.snoozed_until IS NOT new.snoozed_until;
+
+    -- Each tag is merged on its own, so tags added on different devices add up
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'tag:' || value, 'true', CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    FROM json_each(new.tags)
+    WHERE old.tags IS NOT new.tags AND value NOT IN (SELECT value FROM json_each(old.tags));
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT new.hash, 'tag:' || value, 'false', CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    FROM json_each(old.tags)
+    WHERE old.tags IS NOT new.tags AND value NOT IN (SELECT value FROM json_each(new.tags));
+END;
+
+-- A tombstone, so the article is deleted everywhere rather than synced back
+CREATE TRIGGER IF NOT EXISTS sync_article_delete AFTER DELETE ON articles
+WHEN EXISTS (SELECT 1 FROM sync_device WHERE applying = 0)
+BEGIN
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    VALUES (old.hash, 'article', 'null', CAST(unixepoch('subsec') * 1000 AS INTEGER));
+END;
+
+-- Highlights are known by when they were made and what they quote, since
+-- their IDs differ between devices
+CREATE TRIGGER IF NOT EXISTS sync_highlight_insert AFTER INSERT ON highlights
+WHEN EXISTS (SELECT 1 FROM sync_device WHERE applying = 0)
+BEGIN
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT hash, 'highlight:' || new.created_at || ':' || new.text, json_object(
+        'text', new.text,
+        'position', new.position,
+        'comment', new.comment,
+        'created_at', new.created_at
+    ), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    FROM articles WHERE id = new.article_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_highlight_update AFTER UPDATE ON highlights
+WHEN EXISTS (SELECT 1 FROM sync_device WHERE applying = 0)
+BEGIN
+    -- Moved to another article (merging duplicates), or requoted
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT hash, 'highlight:' || old.created_at || ':' || old.text, 'null', CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    FROM articles
+    WHERE id = old.article_id
+        AND (old.article_id IS NOT new.article_id OR old.text IS NOT new.text OR old.created_at IS NOT new.created_at);
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT hash, 'highlight:' || new.created_at || ':' || new.text, json_object(
+        'text', new.text,
+        'position', new.position,
+        'comment', new.comment,
+        'created_at', new.created_at
+    ), CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    FROM articles
+    WHERE id = new.article_id
+        AND (old.article_id IS NOT new.article_id OR old.text IS NOT new.text OR old.created_at IS NOT new.created_at
+            OR old.position IS NOT new.position OR old.comment IS NOT new.comment);
+END;
+
+-- Highlights deleted along with their article are covered by its tombstone
+CREATE TRIGGER IF NOT EXISTS sync_highlight_delete AFTER DELETE ON highlights
+WHEN EXISTS (SELECT 1 FROM sync_device WHERE applying = 0)
+BEGIN
+    INSERT INTO sync_changes (hash, field, value, changed_at)
+    SELECT hash, 'highlight:' || old.created_at || ':' || old.text, 'null', CAST(unixepoch('subsec') * 1000 AS INTEGER)
+    FROM articles WHERE id = old.article_id;
+END;
@@ -12,6 +12,8 @@ mod embedded {
 pub mod models;
 pub mod queries;
 pub mod schema;
+pub mod search_query;
+pub mod tags;
 
 pub fn get_db_path() -> Result<PathBuf> {
     let project_dirs = ProjectDirs::from("", "", "stash")
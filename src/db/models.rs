@@ -19,9 +19,31 @@ pub struct Article {
     pub starred: bool,
     pub note: Option<String>,
     pub tags: Vec<String>,
+    /// Position in the reading queue, None when not queued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<i64>,
+    /// Hidden from default listings until then; kept afterwards as a
+    /// "woke up" marker until the article is read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// Only filled in by `queries::attach_highlights`, for exports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<Highlight>,
+}
+
+impl Article {
+    pub fn is_snoozed(&self) -> bool {
+        self.snoozed_until.is_some_and(|until| until > Utc::now())
+    }
+
+    pub fn woke_up(&self) -> bool {
+        self.snoozed_until.is_some_and(|until| until <= Utc::now())
+    }
 }
 
 pub struct NewArticle {
+    /// Row ID to insert under, for imports keeping their IDs; None picks the next one
+    pub id: Option<i64>,
     pub hash: String,
     pub url: String,
     pub canonical_url: String,
@@ -31,4 +53,53 @@ pub struct NewArticle {
     pub favicon_url: Option<String>,
     pub content_markdown: Option<String>,
     pub tags: Vec<String>,
+    /// When the article was saved, for imports; None means now
+    pub saved_at: Option<DateTime<Utc>>,
+}
+
+impl NewArticle {
+    /// Git-style short hash identifying an article by its URL
+    pub fn hash_url(url: &str) -> String {
+        blake3::hash(url.as_bytes())
+            .to_hex()
+            .chars()
+            .take(8)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedView {
+    pub id: i64,
+    pub name: String,
+    pub query: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An article's ID and tags, for comparing it with others (see `similarity`)
+/// without loading its content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArticleTags {
+    pub id: i64,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCount {
+    pub tag: String,
+    /// Articles tagged with exactly this tag
+    pub direct: usize,
+    /// Articles tagged with this tag or any tag nested under it
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub id: i64,
+    pub article_id: i64,
+    pub text: String,
+    /// Character offset of the quote in the article's `content_markdown`
+    pub position: Option<i64>,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
@@ -1,11 +1,32 @@
-use crate::db::models::{Article, NewArticle};
+use crate::db::models::{Article, ArticleTags, Highlight, NewArticle, SavedView, TagCount};
+use crate::db::search_query::{self, Expr, StateFilter, Term};
+use crate::db::tags as tag_tree;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter};
+use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter, types::Value};
+use std::collections::HashMap;
+
+// Current Unix timestamp, evaluated by SQLite
+macro_rules! now_unix {
+    () => {
+        "CAST(strftime('%s', 'now') AS INTEGER)"
+    };
+}
+
+// Articles snoozed into the future are left out of default listings. Once
+// the date passes they "wake up" and sort ahead of everything else.
+const NOT_SNOOZED: &str = concat!("(snoozed_until IS NULL OR snoozed_until <= ", now_unix!(), ")");
+const SNOOZED: &str = concat!("snoozed_until > ", now_unix!());
+const WOKE_UP_FIRST: &str = concat!(
+    "(snoozed_until IS NOT NULL AND snoozed_until <= ",
+    now_unix!(),
+    ") DESC"
+);
 
 pub fn row_to_article(row: &Row) -> rusqlite::Result<Article> {
     let saved_at_unix: i64 = row.get("saved_at")?;
     let last_opened_unix: Option<i64> = row.get("last_opened_at")?;
+    let snoozed_until_unix: Option<i64> = row.get("snoozed_until")?;
 
     let tags_json: String = row.get("tags")?;
 
@@ -19,28 +40,32 @@ pub fn row_to_article(row: &Row) -> rusqlite::Result<Article> {
         description: row.get("description")?,
         favicon_url: row.get("favicon_url")?,
         content_markdown: row.get("content_markdown")?,
-        saved_at: DateTime::from_timestamp(saved_at_unix, 0).unwrap_or_else(|| Utc::now()),
+        saved_at: DateTime::from_timestamp(saved_at_unix, 0).unwrap_or_else(Utc::now),
         last_opened_at: last_opened_unix.and_then(|ts| DateTime::from_timestamp(ts, 0)),
         read: row.get::<_, i64>("read")? != 0,
         archived: row.get::<_, i64>("archived")? != 0,
         starred: row.get::<_, i64>("starred")? != 0,
         note: row.get("note")?,
         tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+        queue_position: row.get("queue_position")?,
+        snoozed_until: snoozed_until_unix.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+        highlights: Vec::new(),
     })
 }
 
 pub fn insert_article(conn: &Connection, article: NewArticle) -> Result<Article> {
-    let now = Utc::now().timestamp();
+    let saved_at = article.saved_at.unwrap_or_else(Utc::now).timestamp();
     let tags_json = serde_json::to_string(&article.tags)?;
 
     let inserted_article = conn
         .query_row(
             "INSERT INTO articles (
-            hash, url, canonical_url, title, site, description, 
+            id, hash, url, canonical_url, title, site, description, 
             favicon_url, content_markdown, saved_at, tags
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
         RETURNING *",
             params![
+                article.id,
                 article.hash,
                 article.url,
                 article.canonical_url,
@@ -49,7 +74,7 @@ pub fn insert_article(conn: &Connection, article: NewArticle) -> Result<Article>
                 article.description,
                 article.favicon_url,
                 article.content_markdown,
-                now,
+                saved_at,
                 tags_json,
             ],
             row_to_article,
@@ -90,14 +115,20 @@ pub fn find_by_ids(conn: &Connection, ids: &[i64]) -> Result<Vec<Article>> {
 
 pub fn list_articles(conn: &Connection, limit: i64, all: bool) -> Result<Vec<Article>> {
     let query = if all {
-        "SELECT * FROM articles
-         ORDER BY starred DESC, saved_at DESC LIMIT ?1"
+        format!(
+            "SELECT * FROM articles
+             ORDER BY {}, starred DESC, saved_at DESC LIMIT ?1",
+            WOKE_UP_FIRST
+        )
     } else {
-        "SELECT * FROM articles WHERE read = 0 AND archived = 0
-         ORDER BY starred DESC, saved_at DESC LIMIT ?1"
+        format!(
+            "SELECT * FROM articles WHERE read = 0 AND archived = 0 AND {}
+             ORDER BY {}, starred DESC, saved_at DESC LIMIT ?1",
+            NOT_SNOOZED, WOKE_UP_FIRST
+        )
     };
 
-    let mut stmt = conn.prepare(query)?;
+    let mut stmt = conn.prepare(&query)?;
     let articles = stmt
         .query_map(params![limit], row_to_article)?
         .collect::<rusqlite::Result<Vec<_>>>()
@@ -106,23 +137,33 @@ pub fn list_articles(conn: &Connection, limit: i64, all: bool) -> Result<Vec<Art
     Ok(articles)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn list_articles_filtered(
     conn: &Connection,
     limit: i64,
     all: bool,
     archived: bool,
     starred: bool,
+    snoozed: bool,
     tags: &[String],
     sort_field: &str,
     reverse: bool,
 ) -> Result<Vec<Article>> {
     let mut conditions = Vec::new();
+    let mut filter_params = Vec::new();
     
+    if snoozed {
+        conditions.push(SNOOZED.to_string());
+    }
+
     if !all {
         conditions.push("read = 0".to_string());
         if !archived {
             conditions.push("archived = 0".to_string());
         }
+        if !snoozed {
+            conditions.push(NOT_SNOOZED.to_string());
+        }
     }
     
     if archived {
@@ -133,16 +174,9 @@ pub fn list_articles_filtered(
         conditions.push("starred = 1".to_string());
     }
     
-    // Filter by tags - article must contain ALL specified tags
-    if !tags.is_empty() {
-        for tag in tags {
-            // Escape single quotes in tags to prevent SQL injection
-            let escaped_tag = tag.replace('\'', "''");
-            conditions.push(format!(
-                "EXISTS (SELECT 1 FROM json_each(tags) WHERE value = '{}')",
-                escaped_tag
-            ));
-        }
+    // Filter by tags - article must contain ALL specified tags (or a descendant of each)
+    for tag in tags {
+        conditions.push(tag_tree::sql_condition("tags", tag, &mut filter_params));
     }
     
     let where_clause = if conditions.is_empty() {
@@ -174,29 +208,174 @@ pub fn list_articles_filtered(
         default_order
     };
     
+    // Pending snoozes sort by wake-up date, soonest first
+    let order_by = if snoozed {
+        format!("snoozed_until {}", if reverse { "DESC" } else { "ASC" })
+    } else {
+        format!("{}, {} {}", WOKE_UP_FIRST, sort_column, sort_order)
+    };
+
     let query = format!(
-        "SELECT * FROM articles {} ORDER BY {} {} LIMIT ?1",
-        where_clause, sort_column, sort_order
+        "SELECT * FROM articles {} ORDER BY {} LIMIT ?",
+        where_clause, order_by
     );
+    filter_params.push(Value::Integer(limit));
     
     let mut stmt = conn.prepare(&query)?;
     let articles = stmt
-        .query_map(params![limit], row_to_article)?
+        .query_map(params_from_iter(filter_params), row_to_article)?
         .collect::<rusqlite::Result<Vec<_>>>()
         .context("Failed to list articles")?;
     
     Ok(articles)
 }
 
+/// What an export selects. Every filter that's set must match; the query
+/// uses `stash search` syntax.
+#[derive(Default)]
+pub struct ArticleFilter {
+    pub ids: Option<Vec<i64>>,
+    /// Search queries, such as a saved view's and one given directly
+    pub queries: Vec<String>,
+    pub read: Option<bool>,
+    pub archived: Option<bool>,
+    pub starred: bool,
+    /// Whether the article is still snoozed
+    pub snoozed: Option<bool>,
+    pub tags: Vec<String>,
+    /// Any of these sites, subdomains included
+    pub sites: Vec<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl ArticleFilter {
+    fn to_sql(&self) -> Result<(String, Vec<Value>)> {
+        let mut terms = Vec::new();
+        for query in &self.queries {
+            terms.push(search_query::parse(query).context("Invalid search query")?);
+        }
+        if let Some(read) = self.read {
+            terms.push(state_term(if read { StateFilter::Read } else { StateFilter::Unread }));
+        }
+        if self.starred {
+            terms.push(state_term(StateFilter::Starred));
+        }
+        for tag in &self.tags {
+            terms.push(Expr::Term(Term::Tag(tag.clone())));
+        }
+        if !self.sites.is_empty() {
+            terms.push(Expr::Or(
+                self.sites
+                    .iter()
+                    .map(|site| {
+                        let site = site.to_lowercase();
+                        let site = site.strip_prefix("www.").unwrap_or(&site);
+                        Expr::Term(Term::Site(site.to_string()))
+                    })
+                    .collect(),
+            ));
+        }
+
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if !terms.is_empty() {
+            let compiled = search_query::compile(&Expr::And(terms));
+            conditions.push(compiled.where_sql);
+            params = compiled.params;
+        }
+        if let Some(ids) = &self.ids {
+            conditions.push(format!(
+                "articles.id IN ({})",
+                ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ")
+            ));
+            params.extend(ids.iter().map(|id| Value::Integer(*id)));
+        }
+        if let Some(archived) = self.archived {
+            conditions.push(format!("articles.archived = {}", archived as i64));
+        }
+        match self.snoozed {
+            Some(true) => conditions.push(SNOOZED.to_string()),
+            Some(false) => conditions.push(NOT_SNOOZED.to_string()),
+            None => {}
+        }
+        if let Some(since) = self.since {
+            conditions.push("articles.saved_at >= ?".to_string());
+            params.push(Value::Integer(since.timestamp()));
+        }
+        if let Some(until) = self.until {
+            conditions.push("articles.saved_at < ?".to_string());
+            params.push(Value::Integer(until.timestamp()));
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        Ok((
+            format!(
+                "SELECT articles.* FROM articles {} ORDER BY articles.saved_at DESC",
+                where_clause
+            ),
+            params,
+        ))
+    }
+}
+
+fn state_term(state: StateFilter) -> Expr {
+    Expr::Term(Term::State(state))
+}
+
+/// Every article the filter matches, newest first
+pub fn list_matching(conn: &Connection, filter: &ArticleFilter) -> Result<Vec<Article>> {
+    let mut articles = Vec::new();
+    for_each_matching(conn, filter, |article| {
+        articles.push(article);
+        Ok(())
+    })?;
+    Ok(articles)
+}
+
+/// Hand each article the filter matches to `f` as it's read, rather than
+/// collecting them, so exports of any size stream
+pub fn for_each_matching(
+    conn: &Connection,
+    filter: &ArticleFilter,
+    mut f: impl FnMut(Article) -> Result<()>,
+) -> Result<()> {
+    let (query, params) = filter.to_sql()?;
+    let mut stmt = conn.prepare(&query)?;
+    let mut rows = stmt.query(params_from_iter(params))?;
+    while let Some(row) = rows.next().context("Failed to list articles")? {
+        f(row_to_article(row)?)?;
+    }
+    Ok(())
+}
+
+/// Whether the filter matches any article at all
+pub fn any_matching(conn: &Connection, filter: &ArticleFilter) -> Result<bool> {
+    let (query, params) = filter.to_sql()?;
+    conn.query_row(
+        &format!("SELECT EXISTS({})", query),
+        params_from_iter(params),
+        |row| row.get(0),
+    )
+    .context("Failed to check for matching articles")
+}
+
 pub fn get_random_articles(conn: &Connection, count: i64, all: bool) -> Result<Vec<Article>> {
     let query = if all {
-        "SELECT * FROM articles ORDER BY RANDOM() LIMIT ?1"
+        "SELECT * FROM articles ORDER BY RANDOM() LIMIT ?1".to_string()
     } else {
-        "SELECT * FROM articles WHERE read = 0 AND archived = 0 
-         ORDER BY RANDOM() LIMIT ?1"
+        format!(
+            "SELECT * FROM articles WHERE read = 0 AND archived = 0 AND {}
+             ORDER BY RANDOM() LIMIT ?1",
+            NOT_SNOOZED
+        )
     };
 
-    let mut stmt = conn.prepare(query)?;
+    let mut stmt = conn.prepare(&query)?;
     let articles = stmt
         .query_map(params![count], row_to_article)?
         .collect::<rusqlite::Result<Vec<_>>>()
@@ -214,7 +393,8 @@ pub fn archive_by_ids(conn: &Connection, ids: &[i64]) -> Result<usize> {
     let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
 
     let query = format!(
-        "UPDATE articles SET archived = 1 WHERE id IN ({})",
+        "UPDATE articles SET archived = 1, queue_position = NULL, snoozed_until = NULL
+         WHERE id IN ({})",
         placeholders
     );
 
@@ -295,7 +475,9 @@ pub fn mark_read_by_ids(conn: &Connection, ids: &[i64]) -> Result<Vec<Article>>
     let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
 
     let query = format!(
-        "UPDATE articles SET read = 1 WHERE id IN ({})",
+        "UPDATE articles SET read = 1, last_opened_at = {}, queue_position = NULL,
+         snoozed_until = NULL WHERE id IN ({})",
+        now_unix!(),
         placeholders
     );
 
@@ -319,7 +501,17 @@ pub fn set_read_by_ids(conn: &Connection, ids: &[i64], read: bool) -> Result<Vec
 
     let query = format!(
         "UPDATE articles SET read = {} WHERE id IN ({})",
-        if read { 1 } else { 0 },
+        // Marking read without opening still dates the read for `stats`,
+        // unless the article was opened before
+        if read {
+            concat!(
+                "1, last_opened_at = COALESCE(last_opened_at, ",
+                now_unix!(),
+                "), queue_position = NULL, snoozed_until = NULL"
+            )
+        } else {
+            "0"
+        },
         placeholders
     );
 
@@ -366,6 +558,14 @@ pub fn get_article_by_id(conn: &Connection, id: i64) -> Result<Option<Article>>
     Ok(article)
 }
 
+/// The highest article ID in use, 0 for an empty library
+pub fn max_article_id(conn: &Connection) -> Result<i64> {
+    conn.query_row("SELECT COALESCE(MAX(id), 0) FROM articles", [], |row| {
+        row.get(0)
+    })
+    .context("Failed to query the highest article ID")
+}
+
 pub fn update_tags(conn: &Connection, id: i64, tags: Vec<String>) -> Result<Article> {
     let tags_json = serde_json::to_string(&tags)?;
 
@@ -381,8 +581,15 @@ pub fn update_tags(conn: &Connection, id: i64, tags: Vec<String>) -> Result<Arti
     Ok(article)
 }
 
+/// Tag counts where parent tags include their whole subtree, sorted alphabetically
 pub fn get_all_tags_with_counts(conn: &Connection) -> Result<Vec<(String, usize)>> {
-    use std::collections::HashMap;
+    let counts = get_tag_counts(conn)?;
+
+    Ok(counts.into_iter().map(|c| (c.tag, c.total)).collect())
+}
+
+pub fn get_tag_counts(conn: &Connection) -> Result<Vec<TagCount>> {
+    use std::collections::{BTreeMap, HashSet};
     
     // Get all articles
     let mut stmt = conn.prepare("SELECT tags FROM articles")?;
@@ -394,23 +601,39 @@ pub fn get_all_tags_with_counts(conn: &Connection) -> Result<Vec<(String, usize)
         .collect::<rusqlite::Result<Vec<_>>>()
         .context("Failed to query tags")?;
     
-    // Count occurrences of each tag
-    let mut tag_counts: HashMap<String, usize> = HashMap::new();
+    // Count direct uses of each tag, and articles anywhere in each tag's subtree
+    let mut direct: BTreeMap<String, usize> = BTreeMap::new();
+    let mut total: BTreeMap<String, usize> = BTreeMap::new();
     
     for tags_json in tags_list {
         let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-        for tag in tags {
-            *tag_counts.entry(tag).or_insert(0) += 1;
+        
+        // An article tagged lang/rust and lang/go still counts once for lang
+        let mut subtree_tags: HashSet<&str> = HashSet::new();
+        for tag in &tags {
+            *direct.entry(tag.clone()).or_insert(0) += 1;
+            subtree_tags.extend(tag_tree::with_ancestors(tag));
+        }
+        
+        for tag in subtree_tags {
+            *total.entry(tag.to_string()).or_insert(0) += 1;
         }
     }
     
-    // Convert to vector and sort alphabetically
-    let mut result: Vec<(String, usize)> = tag_counts.into_iter().collect();
-    result.sort_by(|a, b| a.0.cmp(&b.0));
+    // BTreeMap keeps the result sorted alphabetically
+    let result = total
+        .into_iter()
+        .map(|(tag, total)| TagCount {
+            direct: direct.get(&tag).copied().unwrap_or(0),
+            tag,
+            total,
+        })
+        .collect();
     
     Ok(result)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_article_metadata(
     conn: &Connection,
     id: i64,
@@ -432,7 +655,8 @@ pub fn update_article_metadata(
             tags = ?4, 
             starred = ?5, 
             read = ?6, 
-            archived = ?7 
+            archived = ?7,
+            queue_position = CASE WHEN ?6 OR ?7 THEN NULL ELSE queue_position END
          WHERE id = ?8",
         params![
             title,
@@ -483,10 +707,19 @@ pub fn rename_tag(conn: &Connection, old_tag: &str, new_tag: &str) -> Result<usi
     for (id, tags_json) in articles_with_tags {
         let mut tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
         
-        // Check if this article has the old tag
-        if let Some(pos) = tags.iter().position(|t| t == old_tag) {
-            // Replace with new tag
-            tags[pos] = new_tag.to_string();
+        // Move the old tag and everything nested under it (lang/rust/* -> lang/rs/*)
+        let mut changed = false;
+        for tag in tags.iter_mut() {
+            if let Some(renamed) = tag_tree::replace_prefix(tag, old_tag, new_tag) {
+                *tag = renamed;
+                changed = true;
+            }
+        }
+        
+        if changed {
+            // Renaming can collide with a tag the article already has
+            let mut seen = std::collections::HashSet::new();
+            tags.retain(|t| seen.insert(t.clone()));
             
             // Update the article
             let tags_json = serde_json::to_string(&tags)?;
@@ -585,92 +818,532 @@ pub fn delete_tag(conn: &Connection, tag: &str) -> Result<usize> {
     Ok(updated)
 }
 
-pub fn search_articles(
-    conn: &Connection,
+/// The WHERE clause `search_articles` filters by, its parameters, and the
+/// FTS expression to rank by, if the query has positive text terms
+fn search_filter(
     query: &str,
-    limit: i64,
     all: bool,
     archived: bool,
     starred: bool,
     tags: &[String],
-) -> Result<Vec<Article>> {
-    let mut conditions = Vec::new();
-    
-    // Build filter conditions similar to list_articles_filtered
-    if !all {
-        conditions.push("articles.read = 0".to_string());
-        if !archived {
-            conditions.push("articles.archived = 0".to_string());
+) -> Result<(String, Vec<Value>, Option<String>)> {
+    let parsed = search_query::parse(query).context("Invalid search query")?;
+    let compiled = search_query::compile(&parsed);
+
+    let mut conditions = vec![compiled.where_sql];
+    let mut filter_params = compiled.params;
+
+    // Default to unread articles unless the query asks for a state itself (is:read, is:archived)
+    if !compiled.has_state_filter {
+        if !all {
+            conditions.push("articles.read = 0".to_string());
+            if !archived {
+                conditions.push("articles.archived = 0".to_string());
+            }
+        }
+
+        if archived {
+            conditions.push("articles.archived = 1".to_string());
         }
     }
-    
-    if archived {
-        conditions.push("articles.archived = 1".to_string());
-    }
-    
+
     if starred {
         conditions.push("articles.starred = 1".to_string());
     }
-    
-    // Filter by tags - article must contain ALL specified tags
-    if !tags.is_empty() {
-        for tag in tags {
-            // Escape single quotes in tags to prevent SQL injection
-            let escaped_tag = tag.replace('\'', "''");
-            conditions.push(format!(
-                "EXISTS (SELECT 1 FROM json_each(articles.tags) WHERE value = '{}')",
-                escaped_tag
-            ));
-        }
+
+    // Filter by tags - article must contain ALL specified tags (or a descendant of each)
+    for tag in tags {
+        conditions.push(tag_tree::sql_condition("articles.tags", tag, &mut filter_params));
     }
-    
-    let where_clause = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!("AND {}", conditions.join(" AND "))
-    };
-    
-    // Escape FTS5 special characters by wrapping each word in quotes
-    // FTS5 special characters: - " ( ) * AND OR NOT
-    // If the query contains these, wrap the entire query in quotes for phrase search
-    let fts_query = if query.contains(&['-', '(', ')', '*', '.', ':', '/', '@'][..]) {
-        // For queries with special chars, use phrase search
-        format!("\"{}\"", query.replace('"', "\"\""))
-    } else {
-        // For simple queries, allow FTS5 to parse naturally
-        query.to_string()
+
+    Ok((conditions.join(" AND "), filter_params, compiled.rank_match))
+}
+
+pub fn search_articles(
+    conn: &Connection,
+    query: &str,
+    limit: i64,
+    all: bool,
+    archived: bool,
+    starred: bool,
+    tags: &[String],
+) -> Result<Vec<Article>> {
+    let (where_sql, filter_params, rank_match) = search_filter(query, all, archived, starred, tags)?;
+
+    // Rank by bm25 over the positive text terms (lower is better). Queries made
+    // only of qualifiers fall back to newest first.
+    let mut params = Vec::new();
+    let (rank_join, relevance) = match rank_match {
+        Some(rank_match) => {
+            params.push(Value::Text(rank_match));
+            (
+                "LEFT JOIN articles_fts ON articles_fts.rowid = articles.id AND articles_fts MATCH ?",
+                "CASE WHEN articles_fts.rowid IS NOT NULL THEN bm25(articles_fts) ELSE 0.0 END",
+            )
+        }
+        None => ("", "0.0"),
     };
-    
-    // Use FTS5 for full-text search across title, description, content_markdown, and tags
-    // We use LEFT JOIN to also capture URL matches that might not match FTS
-    // The query is ranked by relevance using bm25() function (lower is better)
-    // URL matches get a boost by having a very low (negative) score
-    let url_pattern = format!("%{}%", query);
-    
+    params.extend(filter_params);
+    params.push(Value::Integer(limit));
+
     let sql = format!(
-        "SELECT articles.id, articles.hash, articles.url, articles.canonical_url, 
-                articles.title, articles.site, articles.description, articles.favicon_url,
-                articles.content_markdown, articles.saved_at, articles.last_opened_at,
-                articles.read, articles.archived, articles.starred, articles.note, articles.tags,
-         CASE 
-           WHEN articles.url LIKE ?3 THEN -100.0
-           WHEN articles_fts.rowid IS NOT NULL THEN bm25(articles_fts)
-           ELSE 100.0
-         END as relevance
+        "SELECT articles.*, {} AS relevance
          FROM articles
-         LEFT JOIN articles_fts ON articles.id = articles_fts.rowid AND articles_fts MATCH ?1
-         WHERE (articles_fts.rowid IS NOT NULL OR articles.url LIKE ?3)
          {}
-         ORDER BY relevance ASC
-         LIMIT ?2",
-        where_clause
+         WHERE {}
+         ORDER BY relevance ASC, articles.saved_at DESC
+         LIMIT ?",
+        relevance, rank_join, where_sql
     );
-    
+
     let mut stmt = conn.prepare(&sql)?;
     let articles = stmt
-        .query_map(params![fts_query, limit, url_pattern], row_to_article)?
+        .query_map(params_from_iter(params), row_to_article)?
         .collect::<rusqlite::Result<Vec<_>>>()
         .context("Failed to search articles")?;
-    
+
+    Ok(articles)
+}
+
+pub fn row_to_saved_view(row: &Row) -> rusqlite::Result<SavedView> {
+    let created_at_unix: i64 = row.get("created_at")?;
+
+    Ok(SavedView {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        query: row.get("query")?,
+        created_at: DateTime::from_timestamp(created_at_unix, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+pub fn upsert_saved_view(conn: &Connection, name: &str, query: &str) -> Result<SavedView> {
+    let now = Utc::now().timestamp();
+
+    let view = conn
+        .query_row(
+            "INSERT INTO saved_views (name, query, created_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET query = excluded.query
+             RETURNING *",
+            params![name, query, now],
+            row_to_saved_view,
+        )
+        .context("Failed to save view")?;
+
+    Ok(view)
+}
+
+pub fn find_saved_view_by_name(conn: &Connection, name: &str) -> Result<Option<SavedView>> {
+    let mut stmt = conn.prepare("SELECT * FROM saved_views WHERE name = ?1")?;
+
+    let view = stmt
+        .query_row(params![name], row_to_saved_view)
+        .optional()
+        .context("Failed to query saved view")?;
+
+    Ok(view)
+}
+
+pub fn list_saved_views(conn: &Connection) -> Result<Vec<SavedView>> {
+    let mut stmt = conn.prepare("SELECT * FROM saved_views ORDER BY name")?;
+    let views = stmt
+        .query_map([], row_to_saved_view)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list saved views")?;
+
+    Ok(views)
+}
+
+pub fn delete_saved_view(conn: &Connection, name: &str) -> Result<usize> {
+    let affected = conn
+        .execute("DELETE FROM saved_views WHERE name = ?1", params![name])
+        .context("Failed to delete saved view")?;
+
+    Ok(affected)
+}
+
+/// Run a saved view's query against the current library
+pub fn evaluate_saved_view(conn: &Connection, view: &SavedView, limit: i64) -> Result<Vec<Article>> {
+    search_articles(conn, &view.query, limit, false, false, false, &[])
+        .with_context(|| format!("Failed to evaluate view '{}'", view.name))
+}
+
+/// How many articles `evaluate_saved_view` would return without a limit
+pub fn count_saved_view(conn: &Connection, view: &SavedView) -> Result<usize> {
+    let (where_sql, params, _) = search_filter(&view.query, false, false, false, &[])
+        .with_context(|| format!("Failed to evaluate view '{}'", view.name))?;
+    let count: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM articles WHERE {}", where_sql),
+            params_from_iter(params),
+            |row| row.get(0),
+        )
+        .with_context(|| format!("Failed to count view '{}'", view.name))?;
+
+    Ok(count as usize)
+}
+
+pub fn row_to_highlight(row: &Row) -> rusqlite::Result<Highlight> {
+    let created_at_unix: i64 = row.get("created_at")?;
+
+    Ok(Highlight {
+        id: row.get("id")?,
+        article_id: row.get("article_id")?,
+        text: row.get("text")?,
+        position: row.get("position")?,
+        comment: row.get("comment")?,
+        created_at: DateTime::from_timestamp(created_at_unix, 0).unwrap_or_else(Utc::now),
+    })
+}
+
+pub fn insert_highlight(
+    conn: &Connection,
+    article_id: i64,
+    text: &str,
+    position: Option<i64>,
+    comment: Option<&str>,
+) -> Result<Highlight> {
+    let now = Utc::now().timestamp();
+
+    let highlight = conn
+        .query_row(
+            "INSERT INTO highlights (article_id, text, position, comment, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             RETURNING *",
+            params![article_id, text, position, comment, now],
+            row_to_highlight,
+        )
+        .context("Failed to insert highlight")?;
+
+    Ok(highlight)
+}
+
+/// Highlights for one article, or for every article when `article_id` is None,
+/// in reading order (by position, then creation)
+pub fn list_highlights(conn: &Connection, article_id: Option<i64>) -> Result<Vec<Highlight>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM highlights
+         WHERE ?1 IS NULL OR article_id = ?1
+         ORDER BY article_id, position IS NULL, position, created_at, id",
+    )?;
+
+    let highlights = stmt
+        .query_map(params![article_id], row_to_highlight)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list highlights")?;
+
+    Ok(highlights)
+}
+
+pub fn delete_highlight(conn: &Connection, id: i64) -> Result<usize> {
+    let affected = conn
+        .execute("DELETE FROM highlights WHERE id = ?1", params![id])
+        .context("Failed to delete highlight")?;
+
+    Ok(affected)
+}
+
+/// Reassign highlights from `from_ids` to `to_id` (used when merging duplicates)
+pub fn move_highlights(conn: &Connection, from_ids: &[i64], to_id: i64) -> Result<usize> {
+    if from_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let placeholders = from_ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+    let query = format!(
+        "UPDATE highlights SET article_id = ? WHERE article_id IN ({})",
+        placeholders
+    );
+
+    let mut params = vec![to_id];
+    params.extend_from_slice(from_ids);
+
+    let affected = conn
+        .execute(&query, params_from_iter(params))
+        .context("Failed to move highlights")?;
+
+    Ok(affected)
+}
+
+/// Load each article's highlights into `Article::highlights`
+pub fn attach_highlights(conn: &Connection, articles: &mut [Article]) -> Result<()> {
+    let mut by_article: HashMap<i64, Vec<Highlight>> = HashMap::new();
+    for highlight in list_highlights(conn, None)? {
+        by_article.entry(highlight.article_id).or_default().push(highlight);
+    }
+
+    for article in articles {
+        article.highlights = by_article.remove(&article.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// ID and tags of every article, leaving out archived ones unless `include_archived`
+pub fn list_article_tags(conn: &Connection, include_archived: bool) -> Result<Vec<ArticleTags>> {
+    let mut stmt = conn.prepare("SELECT id, tags FROM articles WHERE ?1 OR archived = 0")?;
+    let articles = stmt
+        .query_map(params![include_archived], |row| {
+            let tags_json: String = row.get("tags")?;
+            Ok(ArticleTags {
+                id: row.get("id")?,
+                tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list article tags")?;
+
     Ok(articles)
 }
+
+/// Cached term frequencies keyed by article ID (see `similarity`)
+pub fn get_term_vectors(conn: &Connection) -> Result<HashMap<i64, HashMap<String, f64>>> {
+    let mut stmt = conn.prepare("SELECT article_id, terms FROM term_vectors")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load term vectors")?;
+
+    // A corrupt row is simply rebuilt, so skip anything that doesn't parse
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, json)| serde_json::from_str(&json).ok().map(|terms| (id, terms)))
+        .collect())
+}
+
+pub fn save_term_vectors(
+    conn: &Connection,
+    vectors: &[(i64, &HashMap<String, f64>)],
+) -> Result<()> {
+    if vectors.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO term_vectors (article_id, terms) VALUES (?1, ?2)
+             ON CONFLICT(article_id) DO UPDATE SET terms = excluded.terms",
+        )?;
+        for (id, terms) in vectors {
+            stmt.execute(params![id, serde_json::to_string(terms)?])
+                .context("Failed to save term vector")?;
+        }
+    }
+    tx.commit().context("Failed to save term vectors")?;
+
+    Ok(())
+}
+
+/// Hide articles until `until` (which also takes them out of the reading
+/// queue), or clear the snooze with None
+pub fn snooze_by_ids(
+    conn: &Connection,
+    ids: &[i64],
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<Article>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+
+    let query = match until {
+        Some(_) => format!(
+            "UPDATE articles SET snoozed_until = ?, queue_position = NULL WHERE id IN ({})",
+            placeholders
+        ),
+        None => format!(
+            "UPDATE articles SET snoozed_until = ? WHERE id IN ({})",
+            placeholders
+        ),
+    };
+
+    let mut query_params = vec![match until {
+        Some(until) => Value::Integer(until.timestamp()),
+        None => Value::Null,
+    }];
+    query_params.extend(ids.iter().map(|id| Value::Integer(*id)));
+
+    conn.execute(&query, params_from_iter(query_params))
+        .context("Failed to snooze articles")?;
+
+    find_by_ids(conn, ids)
+}
+
+/// Queued articles, head of the queue first. Read and archived articles are
+/// left out even if a stale position survived.
+pub fn list_queue(conn: &Connection) -> Result<Vec<Article>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM articles
+         WHERE queue_position IS NOT NULL AND read = 0 AND archived = 0
+         ORDER BY queue_position, id",
+    )?;
+    let articles = stmt
+        .query_map([], row_to_article)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list reading queue")?;
+
+    Ok(articles)
+}
+
+/// Replace the whole queue with `ids`, in order
+pub fn set_queue(conn: &Connection, ids: &[i64]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE articles SET queue_position = NULL WHERE queue_position IS NOT NULL",
+        [],
+    )?;
+    {
+        let mut stmt = tx.prepare("UPDATE articles SET queue_position = ?1 WHERE id = ?2")?;
+        for (idx, id) in ids.iter().enumerate() {
+            stmt.execute(params![idx as i64 + 1, id])
+                .context("Failed to update queue position")?;
+        }
+    }
+    tx.commit().context("Failed to save reading queue")?;
+
+    Ok(())
+}
+
+/// Number of unread, unarchived articles and when the oldest of them was saved
+pub fn backlog_summary(conn: &Connection) -> Result<(usize, Option<DateTime<Utc>>)> {
+    let (count, oldest): (i64, Option<i64>) = conn
+        .query_row(
+            "SELECT COUNT(*), MIN(saved_at) FROM articles WHERE read = 0 AND archived = 0",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .context("Failed to summarize backlog")?;
+
+    Ok((count as usize, oldest.and_then(|ts| DateTime::from_timestamp(ts, 0))))
+}
+
+/// Number of articles read (opened or marked read) in `[from, to)`
+pub fn count_read_between(conn: &Connection, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<usize> {
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM articles
+             WHERE read = 1 AND last_opened_at >= ?1 AND last_opened_at < ?2",
+            params![from.timestamp(), to.timestamp()],
+            |row| row.get(0),
+        )
+        .context("Failed to count read articles")?;
+
+    Ok(count as usize)
+}
+
+/// Overwrite when an article was last opened, e.g. with the latest open of
+/// duplicates merged into it
+pub fn set_last_opened_at(conn: &Connection, id: i64, at: Option<DateTime<Utc>>) -> Result<()> {
+    conn.execute(
+        "UPDATE articles SET last_opened_at = ?1 WHERE id = ?2",
+        params![at.map(|dt| dt.timestamp()), id],
+    )
+    .context("Failed to update last opened time")?;
+
+    Ok(())
+}
+
+/// Carry over the state `insert_article` leaves at its defaults, for imports
+pub fn restore_state(conn: &Connection, id: i64, from: &Article) -> Result<Article> {
+    conn.execute(
+        "UPDATE articles SET read = ?1, archived = ?2, starred = ?3, note = ?4,
+            last_opened_at = ?5, snoozed_until = ?6,
+            queue_position = CASE WHEN ?1 OR ?2 THEN NULL ELSE queue_position END
+         WHERE id = ?7",
+        params![
+            from.read as i64,
+            from.archived as i64,
+            from.starred as i64,
+            from.note,
+            from.last_opened_at.map(|dt| dt.timestamp()),
+            from.snoozed_until.map(|dt| dt.timestamp()),
+            id
+        ],
+    )
+    .context("Failed to restore article state")?;
+
+    get_article_by_id(conn, id)?.context("Article not found after update")
+}
+
+/// Write back every field an import merge can change
+pub fn update_article(conn: &Connection, article: &Article) -> Result<Article> {
+    conn.execute(
+        "UPDATE articles SET title = ?1, description = ?2, favicon_url = ?3,
+            content_markdown = ?4, tags = ?5, note = ?6, read = ?7, archived = ?8,
+            starred = ?9, saved_at = ?10, last_opened_at = ?11, snoozed_until = ?12,
+            queue_position = CASE WHEN ?7 OR ?8 THEN NULL ELSE queue_position END
+         WHERE id = ?13",
+        params![
+            article.title,
+            article.description,
+            article.favicon_url,
+            article.content_markdown,
+            serde_json::to_string(&article.tags)?,
+            article.note,
+            article.read as i64,
+            article.archived as i64,
+            article.starred as i64,
+            article.saved_at.timestamp(),
+            article.last_opened_at.map(|dt| dt.timestamp()),
+            article.snoozed_until.map(|dt| dt.timestamp()),
+            article.id
+        ],
+    )
+    .context("Failed to update article")?;
+
+    get_article_by_id(conn, article.id)?.context("Article not found after update")
+}
+
+/// Mark articles for `stash fetch` to download their content
+pub fn queue_fetch(conn: &Connection, ids: &[i64]) -> Result<usize> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+    let query = format!(
+        "UPDATE articles SET fetch_pending = 1 WHERE id IN ({})",
+        placeholders
+    );
+
+    conn.execute(&query, params_from_iter(ids))
+        .context("Failed to queue articles for fetching")
+}
+
+/// Articles waiting for their content, oldest first
+pub fn list_fetch_pending(conn: &Connection) -> Result<Vec<Article>> {
+    let mut stmt =
+        conn.prepare("SELECT * FROM articles WHERE fetch_pending = 1 ORDER BY saved_at, id")?;
+    let articles = stmt
+        .query_map([], row_to_article)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list articles waiting to be fetched")?;
+
+    Ok(articles)
+}
+
+/// Store downloaded content and take the article off the fetch queue. The
+/// title is only filled in when the article has none.
+pub fn save_fetched_content(
+    conn: &Connection,
+    id: i64,
+    title: Option<String>,
+    description: Option<String>,
+    favicon_url: Option<String>,
+    content_markdown: Option<String>,
+) -> Result<Article> {
+    conn.execute(
+        "UPDATE articles SET
+            title = COALESCE(title, ?1),
+            description = COALESCE(?2, description),
+            favicon_url = COALESCE(?3, favicon_url),
+            content_markdown = COALESCE(?4, content_markdown),
+            fetch_pending = 0
+         WHERE id = ?5",
+        params![title, description, favicon_url, content_markdown, id],
+    )
+    .context("Failed to save fetched content")?;
+
+    get_article_by_id(conn, id)?.context("Article not found after update")
+}
@@ -0,0 +1,797 @@
+// Structured search query language for `stash search`
+//
+// A query is a list of terms combined with implicit AND, explicit `OR`,
+// `NOT` (or a leading `-`) and parentheses. Terms are either free text
+// (words, "quoted phrases", prefix*) matched through the FTS5 index, or
+// qualifiers that become plain column filters:
+//
+//   title:rust  site:github.com  tag:cli  note:todo  highlight:quote
+//   is:unread|read|starred|archived  saved:>2025-01-01
+//
+// `parse` builds an expression tree and `compile` turns it into a SQL
+// WHERE fragment with bound parameters, so user input never ends up
+// concatenated into SQL.
+
+use anyhow::{Result, bail};
+use chrono::{Days, Local, NaiveDate, TimeZone};
+use rusqlite::types::Value;
+
+use crate::db::tags;
+
+const QUALIFIERS: [&str; 7] = ["title", "site", "tag", "note", "highlight", "is", "saved"];
+
+const HIGHLIGHT_MATCH: &str = "articles.id IN (SELECT highlights.article_id FROM highlights \
+     JOIN highlights_fts ON highlights_fts.rowid = highlights.id WHERE highlights_fts MATCH ?)";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    // Free text matched against the FTS index, optionally limited to one column
+    Text {
+        column: Option<&'static str>,
+        text: String,
+        prefix: bool,
+    },
+    Site(String),
+    Tag(String),
+    Note(String),
+    Highlight(String),
+    State(StateFilter),
+    Saved(Comparison, NaiveDate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateFilter {
+    Unread,
+    Read,
+    Starred,
+    Archived,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    On,
+    Before,
+    OnOrBefore,
+    After,
+    OnOrAfter,
+}
+
+/// Result of compiling a query: a WHERE fragment plus its parameters
+pub struct CompiledQuery {
+    pub where_sql: String,
+    pub params: Vec<Value>,
+    /// FTS5 expression built from the positive text terms, used for bm25 ranking
+    pub rank_match: Option<String>,
+    /// True when the query itself filters on read/archived state
+    pub has_state_filter: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word {
+        text: String,
+        quoted: bool,
+        prefix: bool,
+    },
+    Qualified {
+        key: String,
+        value: String,
+        quoted: bool,
+    },
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+}
+
+// Column positions are 1-based so they line up with what the user typed
+struct Spanned {
+    token: Token,
+    pos: usize,
+}
+
+pub fn parse(query: &str) -> Result<Expr> {
+    let tokens = tokenize(query)?;
+
+    if tokens.is_empty() {
+        bail!("Search query is empty");
+    }
+
+    let mut parser = Parser { tokens, idx: 0 };
+    let expr = parser.parse_or()?;
+
+    if let Some(spanned) = parser.tokens.get(parser.idx) {
+        bail!("Unexpected ')' at position {}", spanned.pos);
+    }
+
+    Ok(expr)
+}
+
+fn tokenize(query: &str) -> Result<Vec<Spanned>> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let pos = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '(' => {
+                tokens.push(Spanned {
+                    token: Token::LParen,
+                    pos,
+                });
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Spanned {
+                    token: Token::RParen,
+                    pos,
+                });
+                i += 1;
+            }
+            // A leading dash negates the following term: `-async`
+            '-' if chars.get(i + 1).is_some_and(|n| !n.is_whitespace()) => {
+                tokens.push(Spanned {
+                    token: Token::Not,
+                    pos,
+                });
+                i += 1;
+            }
+            '"' => {
+                let (text, next) = read_quoted(&chars, i)?;
+                let prefix = chars.get(next) == Some(&'*');
+                i = if prefix { next + 1 } else { next };
+
+                if text.trim().is_empty() {
+                    bail!("Empty phrase at position {}", pos);
+                }
+
+                tokens.push(Spanned {
+                    token: Token::Word {
+                        text,
+                        quoted: true,
+                        prefix,
+                    },
+                    pos,
+                });
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '(' | ')' | '"')
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+
+                // `title:"some phrase"` - the value is the quoted string that follows
+                let key = word.trim_end_matches(':').to_lowercase();
+                if word.ends_with(':')
+                    && chars.get(i) == Some(&'"')
+                    && QUALIFIERS.contains(&key.as_str())
+                {
+                    let (value, next) = read_quoted(&chars, i)?;
+                    i = next;
+                    tokens.push(Spanned {
+                        token: Token::Qualified {
+                            key,
+                            value,
+                            quoted: true,
+                        },
+                        pos,
+                    });
+                    continue;
+                }
+
+                tokens.push(Spanned {
+                    token: word_token(word, pos)?,
+                    pos,
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize)> {
+    let mut i = start + 1;
+    let mut text = String::new();
+
+    while i < chars.len() {
+        if chars[i] == '"' {
+            return Ok((text, i + 1));
+        }
+        text.push(chars[i]);
+        i += 1;
+    }
+
+    bail!("Unterminated quote starting at position {}", start + 1)
+}
+
+fn word_token(word: String, pos: usize) -> Result<Token> {
+    match word.as_str() {
+        "AND" => return Ok(Token::And),
+        "OR" => return Ok(Token::Or),
+        "NOT" => return Ok(Token::Not),
+        _ => {}
+    }
+
+    if let Some((key, value)) = word.split_once(':') {
+        // URLs like https://example.com and other words with a colon that
+        // isn't a known qualifier (10:30, std::io) are searched as plain text
+        let key = key.to_lowercase();
+        let looks_like_url = value.starts_with("//");
+
+        if QUALIFIERS.contains(&key.as_str()) && !looks_like_url {
+            if value.is_empty() {
+                bail!("Missing value after '{}:' at position {}", key, pos);
+            }
+
+            return Ok(Token::Qualified {
+                key,
+                value: value.to_string(),
+                quoted: false,
+            });
+        }
+    }
+
+    let (text, prefix) = match word.strip_suffix('*') {
+        Some(stem) => (stem.to_string(), true),
+        None => (word, false),
+    };
+
+    if text.is_empty() {
+        bail!(
+            "Prefix search needs at least one character before '*' at position {}",
+            pos
+        );
+    }
+
+    Ok(Token::Word {
+        text,
+        quoted: false,
+        prefix,
+    })
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    idx: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.idx).map(|s| &s.token)
+    }
+
+    // Position of the current token, or one past the end of the query
+    fn pos(&self) -> usize {
+        self.tokens
+            .get(self.idx)
+            .or_else(|| self.tokens.last())
+            .map(|s| s.pos)
+            .unwrap_or(1)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut items = vec![self.parse_and()?];
+
+        while self.peek() == Some(&Token::Or) {
+            self.idx += 1;
+            items.push(self.parse_and()?);
+        }
+
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::Or(items)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut items = Vec::new();
+
+        loop {
+            match self.peek() {
+                None | Some(Token::RParen) | Some(Token::Or) => break,
+                Some(Token::And) => {
+                    if items.is_empty() {
+                        bail!(
+                            "Expected a search term before AND at position {}",
+                            self.pos()
+                        );
+                    }
+                    self.idx += 1;
+                    items.push(self.parse_unary()?);
+                }
+                _ => items.push(self.parse_unary()?),
+            }
+        }
+
+        if items.is_empty() {
+            let found = match self.peek() {
+                Some(Token::Or) => "OR",
+                Some(Token::RParen) => "')'",
+                _ => "end of query",
+            };
+            bail!(
+                "Expected a search term but found {} at position {}",
+                found,
+                self.pos()
+            );
+        }
+
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::And(items)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            let pos = self.pos();
+            self.idx += 1;
+            if matches!(
+                self.peek(),
+                None | Some(Token::RParen) | Some(Token::Or) | Some(Token::And)
+            ) {
+                bail!("Expected a search term after NOT at position {}", pos);
+            }
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+        let token = match self.tokens.get(self.idx) {
+            Some(spanned) => spanned.token.clone(),
+            None => bail!("Unexpected end of query"),
+        };
+        self.idx += 1;
+
+        match token {
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    bail!("Unclosed '(' at position {}", pos);
+                }
+                self.idx += 1;
+                Ok(expr)
+            }
+            Token::Word { text, prefix, .. } => Ok(Expr::Term(Term::Text {
+                column: None,
+                text,
+                prefix,
+            })),
+            Token::Qualified { key, value, quoted } => {
+                Ok(Expr::Term(qualified_term(&key, value, quoted, pos)?))
+            }
+            Token::RParen => bail!("Unexpected ')' at position {}", pos),
+            Token::And | Token::Or | Token::Not => {
+                bail!("Unexpected operator at position {}", pos)
+            }
+        }
+    }
+}
+
+fn qualified_term(key: &str, value: String, quoted: bool, pos: usize) -> Result<Term> {
+    let term = match key {
+        "title" => {
+            let (text, prefix) = match value.strip_suffix('*') {
+                Some(stem) if !quoted && !stem.is_empty() => (stem.to_string(), true),
+                _ => (value, false),
+            };
+            Term::Text {
+                column: Some("title"),
+                text,
+                prefix,
+            }
+        }
+        "site" => {
+            let site = value.to_lowercase();
+            Term::Site(site.strip_prefix("www.").unwrap_or(&site).to_string())
+        }
+        "tag" => Term::Tag(value.to_lowercase()),
+        "note" => Term::Note(value),
+        "highlight" => Term::Highlight(value),
+        "is" => Term::State(match value.to_lowercase().as_str() {
+            "unread" => StateFilter::Unread,
+            "read" => StateFilter::Read,
+            "starred" => StateFilter::Starred,
+            "archived" => StateFilter::Archived,
+            other => bail!(
+                "Unknown value 'is:{}' at position {}. Use is:unread, is:read, is:starred or is:archived",
+                other,
+                pos
+            ),
+        }),
+        "saved" => {
+            let (comparison, date) = if let Some(rest) = value.strip_prefix(">=") {
+                (Comparison::OnOrAfter, rest)
+            } else if let Some(rest) = value.strip_prefix("<=") {
+                (Comparison::OnOrBefore, rest)
+            } else if let Some(rest) = value.strip_prefix('>') {
+                (Comparison::After, rest)
+            } else if let Some(rest) = value.strip_prefix('<') {
+                (Comparison::Before, rest)
+            } else {
+                (Comparison::On, value.as_str())
+            };
+
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid date '{}' in saved: at position {}. Use YYYY-MM-DD, optionally prefixed by >, >=, < or <=",
+                    date,
+                    pos
+                )
+            })?;
+            // The day after is needed for the end of the range
+            if date.checked_add_days(Days::new(1)).is_none() {
+                bail!(
+                    "Date '{}' in saved: at position {} is out of range",
+                    date,
+                    pos
+                );
+            }
+
+            Term::Saved(comparison, date)
+        }
+        _ => unreachable!("qualifier validated during tokenizing"),
+    };
+
+    Ok(term)
+}
+
+pub fn compile(expr: &Expr) -> CompiledQuery {
+    let mut params = Vec::new();
+    let where_sql = compile_expr(expr, &mut params);
+
+    let mut rank_terms = Vec::new();
+    collect_rank_terms(expr, &mut rank_terms);
+
+    CompiledQuery {
+        where_sql,
+        params,
+        rank_match: if rank_terms.is_empty() {
+            None
+        } else {
+            Some(rank_terms.join(" OR "))
+        },
+        has_state_filter: has_state_filter(expr),
+    }
+}
+
+fn compile_expr(expr: &Expr, params: &mut Vec<Value>) -> String {
+    match expr {
+        Expr::And(items) => format!(
+            "({})",
+            items
+                .iter()
+                .map(|e| compile_expr(e, params))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        ),
+        Expr::Or(items) => format!(
+            "({})",
+            items
+                .iter()
+                .map(|e| compile_expr(e, params))
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        ),
+        Expr::Not(inner) => format!("NOT {}", compile_expr(inner, params)),
+        Expr::Term(term) => compile_term(term, params),
+    }
+}
+
+fn compile_term(term: &Term, params: &mut Vec<Value>) -> String {
+    match term {
+        Term::Text {
+            column,
+            text,
+            prefix,
+        } => {
+            params.push(Value::Text(fts_expression(*column, text, *prefix)));
+            let fts = "articles.id IN (SELECT rowid FROM articles_fts WHERE articles_fts MATCH ?)";
+
+            if column.is_some() {
+                fts.to_string()
+            } else {
+                // Bare terms also match highlights and the URL
+                params.push(Value::Text(fts_expression(None, text, *prefix)));
+                params.push(Value::Text(like_contains(text)));
+                format!(
+                    "({} OR {} OR articles.url LIKE ? ESCAPE '\\')",
+                    fts, HIGHLIGHT_MATCH
+                )
+            }
+        }
+        Term::Site(site) => {
+            params.push(Value::Text(site.clone()));
+            params.push(Value::Text(format!("%.{}", escape_like(site))));
+            // COALESCE keeps a negated qualifier from dropping articles with no site
+            "(COALESCE(articles.site, '') = ? OR COALESCE(articles.site, '') LIKE ? ESCAPE '\\')"
+                .to_string()
+        }
+        Term::Tag(tag) => tags::sql_condition("articles.tags", tag, params),
+        Term::Note(text) => {
+            params.push(Value::Text(like_contains(text)));
+            "COALESCE(articles.note, '') LIKE ? ESCAPE '\\'".to_string()
+        }
+        Term::Highlight(text) => {
+            params.push(Value::Text(fts_expression(None, text, false)));
+            HIGHLIGHT_MATCH.to_string()
+        }
+        Term::State(state) => match state {
+            StateFilter::Unread => "articles.read = 0",
+            StateFilter::Read => "articles.read = 1",
+            StateFilter::Starred => "articles.starred = 1",
+            StateFilter::Archived => "articles.archived = 1",
+        }
+        .to_string(),
+        Term::Saved(comparison, date) => {
+            let start = local_midnight(*date);
+            let next_day = date
+                .checked_add_days(Days::new(1))
+                .expect("checked when the query was parsed");
+            let end = local_midnight(next_day);
+
+            match comparison {
+                Comparison::On => {
+                    params.push(Value::Integer(start));
+                    params.push(Value::Integer(end));
+                    "(articles.saved_at >= ? AND articles.saved_at < ?)".to_string()
+                }
+                Comparison::Before => {
+                    params.push(Value::Integer(start));
+                    "articles.saved_at < ?".to_string()
+                }
+                Comparison::OnOrBefore => {
+                    params.push(Value::Integer(end));
+                    "articles.saved_at < ?".to_string()
+                }
+                Comparison::After => {
+                    params.push(Value::Integer(end));
+                    "articles.saved_at >= ?".to_string()
+                }
+                Comparison::OnOrAfter => {
+                    params.push(Value::Integer(start));
+                    "articles.saved_at >= ?".to_string()
+                }
+            }
+        }
+    }
+}
+
+// Quote the text so FTS5 treats it as a phrase rather than query syntax
+fn fts_expression(column: Option<&str>, text: &str, prefix: bool) -> String {
+    let phrase = format!(
+        "\"{}\"{}",
+        text.replace('"', "\"\""),
+        if prefix { "*" } else { "" }
+    );
+
+    match column {
+        Some(column) => format!("{} : {}", column, phrase),
+        None => phrase,
+    }
+}
+
+fn collect_rank_terms(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Expr::And(items) | Expr::Or(items) => {
+            for item in items {
+                collect_rank_terms(item, out);
+            }
+        }
+        // Negated terms never match the returned articles, so they can't rank them
+        Expr::Not(_) => {}
+        Expr::Term(Term::Text {
+            column,
+            text,
+            prefix,
+        }) => {
+            out.push(fts_expression(*column, text, *prefix));
+        }
+        Expr::Term(_) => {}
+    }
+}
+
+fn has_state_filter(expr: &Expr) -> bool {
+    match expr {
+        Expr::And(items) | Expr::Or(items) => items.iter().any(has_state_filter),
+        Expr::Not(inner) => has_state_filter(inner),
+        Expr::Term(Term::State(state)) => *state != StateFilter::Starred,
+        Expr::Term(_) => false,
+    }
+}
+
+fn local_midnight(date: NaiveDate) -> i64 {
+    let naive = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| naive.and_utc().timestamp())
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn like_contains(text: &str) -> String {
+    format!("%{}%", escape_like(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(t: &str) -> Expr {
+        Expr::Term(Term::Text {
+            column: None,
+            text: t.to_string(),
+            prefix: false,
+        })
+    }
+
+    #[test]
+    fn test_parse_implicit_and() {
+        assert_eq!(
+            parse("rust cli").unwrap(),
+            Expr::And(vec![text("rust"), text("cli")])
+        );
+    }
+
+    #[test]
+    fn test_parse_or_binds_looser_than_and() {
+        assert_eq!(
+            parse("rust cli OR go").unwrap(),
+            Expr::Or(vec![Expr::And(vec![text("rust"), text("cli")]), text("go")])
+        );
+    }
+
+    #[test]
+    fn test_parse_not_and_dash() {
+        let expected = Expr::And(vec![text("rust"), Expr::Not(Box::new(text("async")))]);
+        assert_eq!(parse("rust NOT async").unwrap(), expected);
+        assert_eq!(parse("rust -async").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_hyphenated_word_is_not_negation() {
+        assert_eq!(parse("rust-lang").unwrap(), text("rust-lang"));
+    }
+
+    #[test]
+    fn test_parse_phrase_and_prefix() {
+        assert_eq!(parse("\"error handling\"").unwrap(), text("error handling"));
+        assert_eq!(
+            parse("asyn*").unwrap(),
+            Expr::Term(Term::Text {
+                column: None,
+                text: "asyn".to_string(),
+                prefix: true
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_qualifiers() {
+        assert_eq!(
+            parse("title:\"zero cost\"").unwrap(),
+            Expr::Term(Term::Text {
+                column: Some("title"),
+                text: "zero cost".to_string(),
+                prefix: false
+            })
+        );
+        assert_eq!(
+            parse("site:www.GitHub.com").unwrap(),
+            Expr::Term(Term::Site("github.com".to_string()))
+        );
+        assert_eq!(
+            parse("tag:Rust").unwrap(),
+            Expr::Term(Term::Tag("rust".to_string()))
+        );
+        assert_eq!(
+            parse("is:starred").unwrap(),
+            Expr::Term(Term::State(StateFilter::Starred))
+        );
+        assert_eq!(
+            parse("saved:>=2025-01-01").unwrap(),
+            Expr::Term(Term::Saved(
+                Comparison::OnOrAfter,
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_url_is_plain_text() {
+        assert_eq!(
+            parse("https://example.com").unwrap(),
+            text("https://example.com")
+        );
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        assert_eq!(
+            parse("(rust OR go) -async").unwrap(),
+            Expr::And(vec![
+                Expr::Or(vec![text("rust"), text("go")]),
+                Expr::Not(Box::new(text("async"))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_qualifier_as_text() {
+        assert_eq!(parse("10:30").unwrap(), text("10:30"));
+        assert_eq!(parse("std::io").unwrap(), text("std::io"));
+        assert_eq!(
+            parse("foo:\"bar\"").unwrap(),
+            Expr::And(vec![text("foo:"), text("bar")])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |q: &str| parse(q).unwrap_err().to_string();
+
+        assert!(err("").contains("empty"));
+        assert!(err("\"unterminated").contains("Unterminated quote starting at position 1"));
+        assert!(err("(rust").contains("Unclosed '(' at position 1"));
+        assert!(err("rust)").contains("Unexpected ')' at position 5"));
+        assert!(err("is:new").contains("is:new"));
+        assert!(err("saved:>yesterday").contains("Invalid date 'yesterday'"));
+        assert!(err("saved:+262142-12-31").contains("out of range"));
+        assert!(err("rust OR").contains("end of query"));
+        assert!(err("rust NOT").contains("after NOT"));
+        assert!(err("title:").contains("Missing value"));
+    }
+
+    #[test]
+    fn test_compile_binds_user_input() {
+        let compiled = compile(&parse("it's site:a.com -tag:x'y").unwrap());
+
+        assert!(!compiled.where_sql.contains("it's"));
+        assert!(!compiled.where_sql.contains("a.com"));
+        assert_eq!(
+            compiled.where_sql.matches('?').count(),
+            compiled.params.len()
+        );
+        assert_eq!(compiled.rank_match.as_deref(), Some("\"it's\""));
+        assert!(!compiled.has_state_filter);
+    }
+
+    #[test]
+    fn test_compile_state_filter_detection() {
+        assert!(compile(&parse("is:archived").unwrap()).has_state_filter);
+        assert!(!compile(&parse("is:starred").unwrap()).has_state_filter);
+    }
+}
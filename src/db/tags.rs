@@ -0,0 +1,184 @@
+// Hierarchical tag helpers
+//
+// Tags can be nested with `/` (e.g. `lang/rust/async`). A tag filter such as
+// `lang` matches the tag itself and every descendant, and counts for a parent
+// tag include all articles tagged somewhere in its subtree.
+
+use rusqlite::types::Value;
+
+pub const SEPARATOR: char = '/';
+
+/// Validates that a tag is lowercase, alphanumeric, and may contain hyphens.
+/// Tags may be nested with `/`, where every segment follows the same rules.
+/// Valid examples: "rust", "rust-lang", "web-dev-101", "lang/rust"
+/// Invalid: "Rust", "rust_lang", "rust--lang", "-rust", "rust-", "lang/", "lang//rust"
+pub fn is_valid(tag: &str) -> bool {
+    !tag.is_empty() && tag.split(SEPARATOR).all(is_valid_segment)
+}
+
+fn is_valid_segment(segment: &str) -> bool {
+    if segment.is_empty() {
+        return false;
+    }
+    
+    // Must not start or end with hyphen
+    if segment.starts_with('-') || segment.ends_with('-') {
+        return false;
+    }
+    
+    let mut prev_was_hyphen = false;
+    for c in segment.chars() {
+        if c == '-' {
+            // No consecutive hyphens
+            if prev_was_hyphen {
+                return false;
+            }
+            prev_was_hyphen = true;
+        } else if c.is_ascii_lowercase() || c.is_ascii_digit() {
+            prev_was_hyphen = false;
+        } else {
+            // Invalid character (uppercase, underscore, special chars, etc.)
+            return false;
+        }
+    }
+    
+    true
+}
+
+/// True if `tag` is `prefix` itself or one of its descendants
+pub fn is_within(tag: &str, prefix: &str) -> bool {
+    tag == prefix
+        || (tag.len() > prefix.len()
+            && tag.starts_with(prefix)
+            && tag[prefix.len()..].starts_with(SEPARATOR))
+}
+
+/// The tag and all of its ancestors: `a/b/c` yields `a`, `a/b`, `a/b/c`
+pub fn with_ancestors(tag: &str) -> impl Iterator<Item = &str> {
+    tag.match_indices(SEPARATOR)
+        .map(move |(idx, _)| &tag[..idx])
+        .chain(std::iter::once(tag))
+}
+
+/// Parent of a nested tag (`a/b` -> `a`), or None for a top-level tag
+pub fn parent(tag: &str) -> Option<&str> {
+    tag.rfind(SEPARATOR).map(|idx| &tag[..idx])
+}
+
+/// Last segment of a nested tag (`a/b` -> `b`)
+pub fn leaf(tag: &str) -> &str {
+    tag.rsplit(SEPARATOR).next().unwrap_or(tag)
+}
+
+/// Move `tag` from under `old` to under `new`, or None if it isn't in `old`'s subtree
+pub fn replace_prefix(tag: &str, old: &str, new: &str) -> Option<String> {
+    if is_within(tag, old) {
+        Some(format!("{}{}", new, &tag[old.len()..]))
+    } else {
+        None
+    }
+}
+
+/// Turn a free-form label (a bookmark folder, another app's tag) into a
+/// valid tag: "Rust Lang" -> "rust-lang", "News/Tech" -> "news/tech".
+/// None if nothing usable is left.
+pub fn slugify(label: &str) -> Option<String> {
+    let segments: Vec<String> = label
+        .split(SEPARATOR)
+        .filter_map(|segment| {
+            let slug = segment
+                .to_lowercase()
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join("-");
+            (!slug.is_empty()).then_some(slug)
+        })
+        .collect();
+
+    (!segments.is_empty()).then(|| segments.join(&SEPARATOR.to_string()))
+}
+
+/// SQL condition matching articles tagged with `tag` or any of its descendants
+pub fn sql_condition(tags_column: &str, tag: &str, params: &mut Vec<Value>) -> String {
+    let escaped = tag
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    params.push(Value::Text(tag.to_string()));
+    params.push(Value::Text(format!("{}{}%", escaped, SEPARATOR)));
+
+    format!(
+        "EXISTS (SELECT 1 FROM json_each({}) WHERE value = ? OR value LIKE ? ESCAPE '\\')",
+        tags_column
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid("rust"));
+        assert!(is_valid("web-dev-101"));
+        assert!(is_valid("lang/rust"));
+        assert!(!is_valid("Rust"));
+        assert!(!is_valid("rust_lang"));
+        assert!(!is_valid("rust--lang"));
+        assert!(!is_valid("-rust"));
+        assert!(!is_valid("lang/"));
+        assert!(!is_valid("lang//rust"));
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within("lang", "lang"));
+        assert!(is_within("lang/rust", "lang"));
+        assert!(is_within("lang/rust/async", "lang/rust"));
+        assert!(!is_within("language", "lang"));
+        assert!(!is_within("lang-rust", "lang"));
+        assert!(!is_within("lang", "lang/rust"));
+    }
+
+    #[test]
+    fn test_with_ancestors() {
+        assert_eq!(
+            with_ancestors("a/b/c").collect::<Vec<_>>(),
+            vec!["a", "a/b", "a/b/c"]
+        );
+        assert_eq!(with_ancestors("rust").collect::<Vec<_>>(), vec!["rust"]);
+    }
+
+    #[test]
+    fn test_parent_and_leaf() {
+        assert_eq!(parent("lang/rust"), Some("lang"));
+        assert_eq!(parent("lang"), None);
+        assert_eq!(leaf("lang/rust"), "rust");
+        assert_eq!(leaf("lang"), "lang");
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Rust Lang").as_deref(), Some("rust-lang"));
+        assert_eq!(slugify("C++ & Systems").as_deref(), Some("c-systems"));
+        assert_eq!(slugify("News / Tech").as_deref(), Some("news/tech"));
+        assert_eq!(slugify("web_dev").as_deref(), Some("web-dev"));
+        assert_eq!(slugify("  ").as_deref(), None);
+        assert_eq!(slugify("日本").as_deref(), None);
+    }
+
+    #[test]
+    fn test_replace_prefix() {
+        assert_eq!(
+            replace_prefix("lang/rust/async", "lang/rust", "lang/rs"),
+            Some("lang/rs/async".to_string())
+        );
+        assert_eq!(
+            replace_prefix("lang/rust", "lang/rust", "rust"),
+            Some("rust".to_string())
+        );
+        assert_eq!(replace_prefix("lang/rusty", "lang/rust", "lang/rs"), None);
+    }
+}
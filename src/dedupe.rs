@@ -0,0 +1,333 @@
+// Near-duplicate detection
+//
+// The same post is often saved from several URLs (syndication, AMP pages,
+// mirrors), so hash-based dedup on add can't catch it. Content is split into
+// overlapping word shingles and summarized with MinHash; LSH banding finds
+// candidate pairs cheaply, which are then confirmed on estimated Jaccard
+// similarity, with title similarity helping borderline and content-less cases.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::db::{models::Article, queries};
+
+/// Words per shingle
+const SHINGLE_SIZE: usize = 5;
+
+/// MinHash signature length, split into BANDS bands of ROWS rows for LSH
+const BANDS: usize = 20;
+const ROWS: usize = 4;
+const NUM_HASHES: usize = BANDS * ROWS;
+
+/// Content shorter than this many shingles is too thin to compare
+const MIN_SHINGLES: usize = 5;
+
+/// Content a bit below the threshold still counts when titles agree this much
+const TITLE_ASSIST: f64 = 0.8;
+const TITLE_ASSIST_MARGIN: f64 = 0.15;
+
+/// Without content, titles alone must be this similar (and not too short)
+const TITLE_ONLY_THRESHOLD: f64 = 0.9;
+const TITLE_ONLY_MIN_WORDS: usize = 3;
+
+/// Default similarity above which two articles are considered duplicates
+pub const DEFAULT_THRESHOLD: f64 = 0.8;
+
+pub struct Fingerprint {
+    signature: Option<Vec<u64>>,
+    title_words: HashSet<String>,
+}
+
+impl Fingerprint {
+    pub fn new(article: &Article) -> Self {
+        let shingles = shingles(article.content_markdown.as_deref().unwrap_or_default());
+        Self {
+            signature: (shingles.len() >= MIN_SHINGLES).then(|| minhash(&shingles)),
+            title_words: words(article.title.as_deref().unwrap_or_default())
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// A group of articles that look like copies of each other
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    pub articles: Vec<Article>,
+}
+
+impl Cluster {
+    /// Index of the article worth keeping by default: starred first, then one
+    /// with content, a note or highlights, then the earliest saved. Highlights
+    /// only count if they were attached with `queries::attach_highlights`.
+    pub fn suggested_keep(&self) -> usize {
+        self.articles
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, a)| {
+                (
+                    !a.starred,
+                    a.content_markdown.is_none(),
+                    a.note.is_none(),
+                    a.highlights.is_empty(),
+                    a.saved_at,
+                    a.id,
+                )
+            })
+            .map(|(idx, _)| idx)
+            .unwrap_or(0)
+    }
+}
+
+/// Fields of the kept article after folding the duplicates into it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    pub starred: bool,
+    pub read: bool,
+    pub last_opened_at: Option<DateTime<Utc>>,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn hash_of<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes of every run of `SHINGLE_SIZE` consecutive words
+pub fn shingles(text: &str) -> HashSet<u64> {
+    let words = words(text);
+    if words.len() < SHINGLE_SIZE {
+        return if words.is_empty() {
+            HashSet::new()
+        } else {
+            HashSet::from([hash_of(&words)])
+        };
+    }
+    words.windows(SHINGLE_SIZE).map(hash_of).collect()
+}
+
+/// Fixed pseudo-random (multiplier, increment) pairs, one per hash function
+fn hash_params() -> Vec<(u64, u64)> {
+    // splitmix64 with a constant seed, so signatures are stable across runs
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    (0..NUM_HASHES).map(|_| (next() | 1, next())).collect()
+}
+
+pub fn minhash(shingles: &HashSet<u64>) -> Vec<u64> {
+    hash_params()
+        .into_iter()
+        .map(|(a, b)| {
+            shingles
+                .iter()
+                .map(|s| s.wrapping_mul(a).wrapping_add(b))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// Estimated Jaccard similarity of two MinHash signatures
+pub fn signature_similarity(a: &[u64], b: &[u64]) -> f64 {
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / a.len().max(1) as f64
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+pub fn is_duplicate(a: &Fingerprint, b: &Fingerprint, threshold: f64) -> bool {
+    let title = jaccard(&a.title_words, &b.title_words);
+
+    match (&a.signature, &b.signature) {
+        (Some(sa), Some(sb)) => {
+            let content = signature_similarity(sa, sb);
+            content >= threshold
+                || (content >= threshold - TITLE_ASSIST_MARGIN && title >= TITLE_ASSIST)
+        }
+        _ => {
+            title >= TITLE_ONLY_THRESHOLD
+                && a.title_words.len().min(b.title_words.len()) >= TITLE_ONLY_MIN_WORDS
+        }
+    }
+}
+
+/// Group `articles` into clusters of near-duplicates. Articles without
+/// duplicates are left out.
+pub fn find_clusters(articles: &[Article], threshold: f64) -> Vec<Cluster> {
+    let fingerprints: Vec<Fingerprint> = articles.iter().map(Fingerprint::new).collect();
+
+    // Candidate pairs: articles sharing an LSH band, or the same title words
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for (idx, fingerprint) in fingerprints.iter().enumerate() {
+        if let Some(signature) = &fingerprint.signature {
+            for (band, rows) in signature.chunks(ROWS).enumerate() {
+                buckets.entry((band, hash_of(rows))).or_default().push(idx);
+            }
+        }
+        if !fingerprint.title_words.is_empty() {
+            let mut title: Vec<&String> = fingerprint.title_words.iter().collect();
+            title.sort();
+            buckets
+                .entry((BANDS, hash_of(&title)))
+                .or_default()
+                .push(idx);
+        }
+    }
+
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for members in buckets.values() {
+        for (i, &a) in members.iter().enumerate() {
+            for &b in &members[i + 1..] {
+                candidates.insert((a.min(b), a.max(b)));
+            }
+        }
+    }
+
+    // Union-find over confirmed pairs
+    let mut parent: Vec<usize> = (0..articles.len()).collect();
+    fn root(parent: &mut [usize], mut idx: usize) -> usize {
+        while parent[idx] != idx {
+            parent[idx] = parent[parent[idx]];
+            idx = parent[idx];
+        }
+        idx
+    }
+
+    for (a, b) in candidates {
+        if is_duplicate(&fingerprints[a], &fingerprints[b], threshold) {
+            let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+            if ra != rb {
+                parent[ra.max(rb)] = ra.min(rb);
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for idx in 0..articles.len() {
+        let r = root(&mut parent, idx);
+        groups.entry(r).or_default().push(idx);
+    }
+
+    groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| Cluster {
+            articles: members
+                .into_iter()
+                .map(|idx| articles[idx].clone())
+                .collect(),
+        })
+        .collect()
+}
+
+/// Fold tags, notes, starred and read state and the latest open of `others` into `keep`
+pub fn merge(keep: &Article, others: &[&Article]) -> Merged {
+    let mut tags: Vec<String> = keep.tags.clone();
+    let mut notes: Vec<&str> = keep.note.as_deref().into_iter().collect();
+
+    for other in others {
+        for tag in &other.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        if let Some(note) = other.note.as_deref()
+            && !note.trim().is_empty()
+            && !notes.contains(&note)
+        {
+            notes.push(note);
+        }
+    }
+    tags.sort();
+
+    Merged {
+        tags,
+        note: (!notes.is_empty()).then(|| notes.join("\n\n")),
+        starred: keep.starred || others.iter().any(|a| a.starred),
+        read: keep.read || others.iter().any(|a| a.read),
+        last_opened_at: others
+            .iter()
+            .map(|a| a.last_opened_at)
+            .fold(keep.last_opened_at, Option::max),
+    }
+}
+
+/// Write the merged fields to `keep`, move highlights over and archive the rest
+pub fn apply_merge(conn: &Connection, keep: &Article, others: &[&Article]) -> Result<Merged> {
+    let merged = merge(keep, others);
+    let other_ids: Vec<i64> = others.iter().map(|a| a.id).collect();
+
+    let tx = conn.unchecked_transaction()?;
+    queries::update_article_metadata(
+        &tx,
+        keep.id,
+        keep.title.clone(),
+        keep.url.clone(),
+        merged.note.clone(),
+        merged.tags.clone(),
+        merged.starred,
+        merged.read,
+        false,
+    )?;
+    queries::set_last_opened_at(&tx, keep.id, merged.last_opened_at)?;
+    queries::move_highlights(&tx, &other_ids, keep.id)?;
+    queries::archive_by_ids(&tx, &other_ids)?;
+    tx.commit()?;
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog while the farmer \
+        watches from the porch and drinks a cup of coffee on a sunny morning in late spring";
+
+    #[test]
+    fn test_identical_text_has_identical_signature() {
+        let a = minhash(&shingles(TEXT));
+        let b = minhash(&shingles(TEXT));
+        assert_eq!(signature_similarity(&a, &b), 1.0);
+    }
+
+    #[test]
+    fn test_small_edits_stay_similar() {
+        let edited = TEXT.replace("sunny", "cloudy");
+        let other = "Completely unrelated words about compilers, type systems, \
+            borrow checking and the joys of writing parsers by hand on weekends";
+
+        let base = minhash(&shingles(TEXT));
+        assert!(signature_similarity(&base, &minhash(&shingles(&edited))) > 0.5);
+        assert!(signature_similarity(&base, &minhash(&shingles(other))) < 0.2);
+    }
+
+    #[test]
+    fn test_shingles_of_short_text() {
+        assert!(shingles("").is_empty());
+        assert_eq!(shingles("two words").len(), 1);
+    }
+}
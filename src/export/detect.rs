@@ -0,0 +1,123 @@
+// Finding import files and telling their formats apart
+//
+// Formats are recognised by content rather than extension, since exports get
+// renamed: a JSON array is a stash export, JSON objects one per line are
+// NDJSON, front matter makes a Markdown export, the Netscape doctype a
+// bookmark file, and a file of nothing but URLs a URL list. Other services'
+// exports look too alike to tell apart and need `--format`.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::urls;
+
+/// Every file under `path` (or `path` itself, if it's a file), in path order.
+/// Hidden files and directories are left out.
+pub fn collect_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        walk(path, &mut files)?;
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).context(format!("Failed to read directory: {}", dir.display()))?;
+
+    for entry in entries {
+        let entry = entry.context("Failed to read directory entry")?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+        // Symlinked directories aren't followed, so links can't loop
+        if entry.file_type()?.is_dir() {
+            walk(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// The import format of a file's content, if it's one that can be told
+pub fn detect_format(text: &str) -> Option<&'static str> {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+
+    if text.starts_with('[') {
+        Some("json")
+    } else if text.starts_with('{') {
+        Some("ndjson")
+    } else if text.starts_with("---") {
+        Some("markdown")
+    } else if text
+        .get(..100)
+        .unwrap_or(text)
+        .to_ascii_lowercase()
+        .contains("<!doctype netscape-bookmark-file")
+    {
+        Some("netscape")
+    } else if urls::is_url_list(text) {
+        Some("urls")
+    } else {
+        None
+    }
+}
+
+/// The format of the file at `path`; binary files have none
+pub fn detect_file_format(path: &Path) -> Result<Option<&'static str>> {
+    let bytes = fs::read(path).context(format!("Failed to read file: {}", path.display()))?;
+    Ok(std::str::from_utf8(&bytes).ok().and_then(detect_format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format("\u{feff}\n[{\"id\": 1}]"), Some("json"));
+        assert_eq!(detect_format("{\"id\": 1}\n{\"id\": 2}\n"), Some("ndjson"));
+        assert_eq!(
+            detect_format("---\nurl: https://a.com\n---\nBody"),
+            Some("markdown")
+        );
+        assert_eq!(
+            detect_format("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n<DL><p>"),
+            Some("netscape")
+        );
+        assert_eq!(
+            detect_format("https://a.com\nhttps://b.com\n"),
+            Some("urls")
+        );
+        assert_eq!(detect_format("# Notes\n\nNothing to import"), None);
+        assert_eq!(detect_format("<!DOCTYPE html><ul></ul>"), None);
+    }
+
+    #[test]
+    fn test_collect_files_skips_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("b/nested")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        for file in ["a.json", "b/nested/c.md", ".hidden", ".git/config"] {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+
+        let files: Vec<PathBuf> = collect_files(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            files,
+            vec![PathBuf::from("a.json"), PathBuf::from("b/nested/c.md")]
+        );
+    }
+}
@@ -0,0 +1,416 @@
+// EPUB 3 export for e-readers
+//
+// The book opens with a title page and a table of contents, followed by a
+// chapter per article: its details (site, link, dates, tags), note and
+// highlights, then the content rendered from Markdown. Images are embedded
+// when they point at a file on this machine (a saved copy of the page);
+// e-readers are mostly offline, so remote images become links. A `toc.ncx`
+// is included too, for readers that predate EPUB 3.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+
+use super::html::html_escape;
+use super::render::markdown_to_html;
+use crate::db::models::Article;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const STYLE: &str = "body { font-family: serif; line-height: 1.5; margin: 0 5%; }
+h1 { font-size: 1.6em; margin-bottom: 0.3em; }
+.meta { font-size: 0.85em; color: #555; margin-bottom: 1em; }
+.meta p { margin: 0.2em 0; }
+.note, .highlights { border-left: 3px solid #999; padding-left: 0.8em; margin: 1em 0; }
+.highlights blockquote { margin: 0.6em 0; font-style: italic; }
+.comment { margin: 0 0 0.6em 1em; font-size: 0.9em; }
+pre { white-space: pre-wrap; font-size: 0.85em; }
+img { max-width: 100%; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #999; padding: 0.2em 0.4em; }
+.toc li { margin: 0.3em 0; }
+.missing { font-style: italic; color: #555; }
+";
+
+const IMAGE_TYPES: [(&str, &str); 6] = [
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+    ("gif", "image/gif"),
+    ("svg", "image/svg+xml"),
+    ("webp", "image/webp"),
+];
+
+/// Images copied into the book (or a static site), keyed by their path on disk
+#[derive(Default)]
+pub(crate) struct Images {
+    by_path: HashMap<PathBuf, String>,
+    pub(crate) files: Vec<(String, &'static str, Vec<u8>)>,
+}
+
+impl Images {
+    /// The path inside the book of a local image, copying it in on first use
+    pub(crate) fn embed(&mut self, src: &str) -> Option<String> {
+        let path = PathBuf::from(src.strip_prefix("file://").unwrap_or(src));
+        if !path.is_absolute() {
+            return None;
+        }
+        if let Some(href) = self.by_path.get(&path) {
+            return Some(href.clone());
+        }
+
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        let (_, media_type) = IMAGE_TYPES.iter().find(|(ext, _)| *ext == extension)?;
+        let data = fs::read(&path).ok()?;
+
+        let href = format!("images/{}.{}", self.files.len() + 1, extension);
+        self.files.push((href.clone(), media_type, data));
+        self.by_path.insert(path, href.clone());
+        Some(href)
+    }
+}
+
+fn xhtml_page(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE html>
+<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"en\" xml:lang=\"en\">
+<head>
+<meta charset=\"UTF-8\"/>
+<title>{}</title>
+<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>
+</head>
+<body>
+{}</body>
+</html>
+",
+        html_escape(title),
+        body
+    )
+}
+
+fn local_date(date: DateTime<Utc>) -> String {
+    date.with_timezone(&Local).format("%Y-%m-%d").to_string()
+}
+
+fn article_title(article: &Article) -> &str {
+    article.title.as_deref().unwrap_or(&article.url)
+}
+
+fn chapter(article: &Article, images: &mut Images) -> String {
+    let title = article_title(article);
+    let mut body = format!(
+        "<section epub:type=\"chapter\">\n<h1>{}</h1>\n<div class=\"meta\">\n",
+        html_escape(title)
+    );
+
+    let mut details = vec![format!("Saved {}", local_date(article.saved_at))];
+    if let Some(site) = &article.site {
+        details.insert(0, html_escape(site));
+    }
+    if let Some(opened) = article.last_opened_at {
+        details.push(format!("last opened {}", local_date(opened)));
+    }
+    if article.starred {
+        details.push("★ starred".to_string());
+    }
+    body.push_str(&format!("<p>{}</p>\n", details.join(" · ")));
+    body.push_str(&format!(
+        "<p><a href=\"{0}\">{0}</a></p>\n",
+        html_escape(&article.url)
+    ));
+    if !article.tags.is_empty() {
+        body.push_str(&format!(
+            "<p>Tags: {}</p>\n",
+            html_escape(&article.tags.join(", "))
+        ));
+    }
+    body.push_str("</div>\n");
+
+    if let Some(description) = &article.description {
+        body.push_str(&format!(
+            "<p class=\"description\"><em>{}</em></p>\n",
+            html_escape(description)
+        ));
+    }
+    if let Some(note) = article.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        body.push_str("<div class=\"note\">\n<h2>Note</h2>\n");
+        body.push_str(&markdown_to_html(note, &mut |_| None));
+        body.push_str("</div>\n");
+    }
+    if !article.highlights.is_empty() {
+        body.push_str("<div class=\"highlights\">\n<h2>Highlights</h2>\n");
+        for highlight in &article.highlights {
+            body.push_str(&format!(
+                "<blockquote><p>{}</p></blockquote>\n",
+                html_escape(&highlight.text)
+            ));
+            if let Some(comment) = &highlight.comment {
+                body.push_str(&format!(
+                    "<p class=\"comment\">{}</p>\n",
+                    html_escape(comment)
+                ));
+            }
+        }
+        body.push_str("</div>\n");
+    }
+
+    body.push_str("<hr/>\n");
+    match &article.content_markdown {
+        Some(content) => body.push_str(&markdown_to_html(content, &mut |src| {
+            images.embed(src)
+        })),
+        None => body.push_str(
+            "<p class=\"missing\">No content was saved for this article. Run <code>stash fetch</code> to download it.</p>\n",
+        ),
+    }
+    body.push_str("</section>\n");
+
+    xhtml_page(title, &body)
+}
+
+fn chapter_file(idx: usize) -> String {
+    format!("chapter-{}.xhtml", idx + 1)
+}
+
+fn title_page(title: &str, articles: &[Article], now: DateTime<Utc>) -> String {
+    let unread = articles.iter().filter(|a| !a.read).count();
+    xhtml_page(
+        title,
+        &format!(
+            "<section epub:type=\"titlepage\">\n<h1>{}</h1>\n<p>{} article(s), {} unread</p>\n<p>Exported from stash on {}</p>\n</section>\n",
+            html_escape(title),
+            articles.len(),
+            unread,
+            local_date(now)
+        ),
+    )
+}
+
+fn nav(title: &str, articles: &[Article]) -> String {
+    let mut body =
+        String::from("<nav epub:type=\"toc\" id=\"toc\" class=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+    // EPUB only allows a link in each entry
+    for (idx, article) in articles.iter().enumerate() {
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            chapter_file(idx),
+            html_escape(article_title(article))
+        ));
+    }
+    body.push_str("</ol>\n</nav>\n");
+    xhtml_page(title, &body)
+}
+
+fn ncx(identifier: &str, title: &str, articles: &[Article]) -> String {
+    let mut points = String::new();
+    for (idx, article) in articles.iter().enumerate() {
+        points.push_str(&format!(
+            "<navPoint id=\"chapter-{0}\" playOrder=\"{0}\"><navLabel><text>{1}</text></navLabel><content src=\"{2}\"/></navPoint>\n",
+            idx + 1,
+            html_escape(article_title(article)),
+            chapter_file(idx)
+        ));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\">
+<head><meta name=\"dtb:uid\" content=\"{}\"/></head>
+<docTitle><text>{}</text></docTitle>
+<navMap>
+{}</navMap>
+</ncx>
+",
+        html_escape(identifier),
+        html_escape(title),
+        points
+    )
+}
+
+fn package(
+    identifier: &str,
+    title: &str,
+    articles: &[Article],
+    images: &Images,
+    now: DateTime<Utc>,
+) -> String {
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>
+<item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>
+<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>
+<item id=\"title\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>
+",
+    );
+    let mut spine = String::from("<itemref idref=\"title\"/>\n<itemref idref=\"nav\"/>\n");
+    for idx in 0..articles.len() {
+        manifest.push_str(&format!(
+            "<item id=\"chapter-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            idx + 1,
+            chapter_file(idx)
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", idx + 1));
+    }
+    for (idx, (href, media_type, _)) in images.files.iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n",
+            idx + 1,
+            href,
+            media_type
+        ));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">
+<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">
+<dc:identifier id=\"book-id\">{}</dc:identifier>
+<dc:title>{}</dc:title>
+<dc:language>en</dc:language>
+<dc:creator>stash</dc:creator>
+<dc:date>{}</dc:date>
+<meta property=\"dcterms:modified\">{}</meta>
+</metadata>
+<manifest>
+{}</manifest>
+<spine toc=\"ncx\">
+{}</spine>
+</package>
+",
+        html_escape(identifier),
+        html_escape(title),
+        now.format("%Y-%m-%d"),
+        now.format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest,
+        spine
+    )
+}
+
+/// The EPUB file's bytes
+pub fn generate_epub(articles: &[Article], title: &str, now: DateTime<Utc>) -> Result<Vec<u8>> {
+    // The same selection of articles is the same book
+    let hashes: Vec<&str> = articles.iter().map(|a| a.hash.as_str()).collect();
+    let identifier = format!(
+        "urn:stash:{}",
+        &blake3::hash(hashes.join(",").as_bytes()).to_hex()[..16]
+    );
+
+    let mut images = Images::default();
+    let chapters: Vec<String> = articles
+        .iter()
+        .map(|article| chapter(article, &mut images))
+        .collect();
+
+    // Entries are stored uncompressed: EPUB requires that of `mimetype`, and
+    // article text is small next to what e-readers hold. Timestamps are
+    // fixed, so the same articles give the same file.
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .last_modified_time(zip::DateTime::default());
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut add = |name: &str, data: &[u8]| -> Result<()> {
+        let options = options.large_file(data.len() as u64 >= u32::MAX as u64);
+        zip.start_file(name, options)?;
+        zip.write_all(data)?;
+        Ok(())
+    };
+    add("mimetype", b"application/epub+zip")?;
+    add(
+        "META-INF/container.xml",
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+<rootfiles>
+<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>
+</rootfiles>
+</container>
+",
+    )?;
+    add(
+        "OEBPS/content.opf",
+        package(&identifier, title, articles, &images, now).as_bytes(),
+    )?;
+    add(
+        "OEBPS/toc.ncx",
+        ncx(&identifier, title, articles).as_bytes(),
+    )?;
+    add("OEBPS/nav.xhtml", nav(title, articles).as_bytes())?;
+    add("OEBPS/style.css", STYLE.as_bytes())?;
+    add(
+        "OEBPS/title.xhtml",
+        title_page(title, articles, now).as_bytes(),
+    )?;
+    for (idx, chapter) in chapters.iter().enumerate() {
+        add(&format!("OEBPS/{}", chapter_file(idx)), chapter.as_bytes())?;
+    }
+    for (href, _, data) in &images.files {
+        add(&format!("OEBPS/{}", href), data)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+pub fn export_to_epub(articles: &[Article], title: &str, out: &mut dyn Write) -> Result<()> {
+    let epub = generate_epub(articles, title, Utc::now())?;
+    out.write_all(&epub).context("Failed to write EPUB")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::imported_article;
+
+    #[test]
+    fn test_chapter_embeds_local_images_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("figure.png");
+        fs::write(&image, b"png").unwrap();
+
+        let mut article = imported_article("https://example.com/a");
+        article.title = Some("Fish & <Chips>".to_string());
+        article.content_markdown = Some(format!(
+            "![Figure]({})\n\n![Remote](https://example.com/r.png)\n\n![Again](file://{})",
+            image.display(),
+            image.display()
+        ));
+
+        let mut images = Images::default();
+        let page = chapter(&article, &mut images);
+        assert!(page.contains("<h1>Fish &amp; &lt;Chips&gt;</h1>"));
+        assert!(page.contains("<img src=\"images/1.png\" alt=\"Figure\" />"));
+        assert!(page.contains("<img src=\"images/1.png\" alt=\"Again\" />"));
+        assert!(page.contains("<a href=\"https://example.com/r.png\">[Remote]</a>"));
+        assert_eq!(images.files.len(), 1);
+    }
+
+    #[test]
+    fn test_generate_epub_lists_chapters() {
+        let articles = vec![
+            imported_article("https://example.com/a"),
+            imported_article("https://example.com/b"),
+        ];
+        let epub = generate_epub(&articles, "Digest", Utc::now()).unwrap();
+        let text = String::from_utf8_lossy(&epub);
+        assert!(text.starts_with("PK"));
+        assert_eq!(&epub[30..38], b"mimetype");
+        assert!(text.contains("<itemref idref=\"chapter-2\"/>"));
+        assert!(text.contains("<a href=\"chapter-1.xhtml\">https://example.com/a</a>"));
+    }
+
+    #[test]
+    fn test_generate_epub_reads_back() {
+        let articles = vec![imported_article("https://example.com/a")];
+        let epub = generate_epub(&articles, "Digest", Utc::now()).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(epub)).unwrap();
+
+        let mimetype = archive.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+        drop(mimetype);
+        assert!(archive.by_name("OEBPS/chapter-1.xhtml").is_ok());
+    }
+}
@@ -0,0 +1,268 @@
+// Atom and RSS feeds of articles, for sharing what you're reading
+//
+// Each entry links to the article, carries its tags as categories, and has
+// the note (rendered from Markdown) and highlights as content. Entry IDs are
+// `urn:stash:<hash>`, so they stay the same across exports and machines and
+// feed readers don't show an article twice.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::Write;
+
+use super::html::html_escape;
+use super::render::markdown_to_html;
+use crate::db::models::Article;
+
+/// Where `stash list --browser` serves the feeds
+pub const LOCAL_SERVER: &str = "http://localhost:8080/";
+
+/// The default feed title for a selection
+pub fn feed_title(tags: &[String], starred: bool) -> String {
+    let mut title = String::from("Stash");
+    if !tags.is_empty() {
+        let tags: Vec<String> = tags.iter().map(|t| format!("#{}", t)).collect();
+        title.push_str(&format!(": {}", tags.join(" ")));
+    }
+    if starred {
+        title.push_str(if tags.is_empty() {
+            ": starred"
+        } else {
+            ", starred"
+        });
+    }
+    title
+}
+
+/// A feed ID that's the same whenever the same selection is exported
+fn feed_id(title: &str) -> String {
+    format!(
+        "urn:stash:feed:{}",
+        &blake3::hash(title.as_bytes()).to_hex()[..16]
+    )
+}
+
+fn entry_id(article: &Article) -> String {
+    format!("urn:stash:{}", article.hash)
+}
+
+fn article_title(article: &Article) -> &str {
+    article.title.as_deref().unwrap_or(&article.url)
+}
+
+/// The note and highlights as HTML, if the article has either
+fn entry_content(article: &Article) -> Option<String> {
+    let mut content = String::new();
+    if let Some(note) = article.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        content.push_str(&markdown_to_html(note, &mut |src| Some(src.to_string())));
+    }
+    for highlight in &article.highlights {
+        content.push_str(&format!(
+            "<blockquote>{}</blockquote>\n",
+            html_escape(&highlight.text)
+        ));
+        if let Some(comment) = &highlight.comment {
+            content.push_str(&format!("<p>{}</p>\n", html_escape(comment)));
+        }
+    }
+    (!content.is_empty()).then_some(content)
+}
+
+fn rfc3339(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn latest(articles: &[Article], now: DateTime<Utc>) -> DateTime<Utc> {
+    articles.iter().map(|a| a.saved_at).max().unwrap_or(now)
+}
+
+pub fn generate_atom(articles: &[Article], title: &str, now: DateTime<Utc>) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+<title>{}</title>
+<id>{}</id>
+<updated>{}</updated>
+<author><name>stash</name></author>
+<generator>stash</generator>
+",
+        html_escape(title),
+        feed_id(title),
+        rfc3339(latest(articles, now))
+    );
+
+    for article in articles {
+        xml.push_str(&format!(
+            "<entry>
+<title>{}</title>
+<link href=\"{}\"/>
+<id>{}</id>
+<published>{}</published>
+<updated>{}</updated>
+",
+            html_escape(article_title(article)),
+            html_escape(&article.url),
+            entry_id(article),
+            rfc3339(article.saved_at),
+            rfc3339(article.saved_at)
+        ));
+        for tag in &article.tags {
+            xml.push_str(&format!("<category term=\"{}\"/>\n", html_escape(tag)));
+        }
+        if let Some(description) = &article.description {
+            xml.push_str(&format!(
+                "<summary>{}</summary>\n",
+                html_escape(description)
+            ));
+        }
+        if let Some(content) = entry_content(article) {
+            xml.push_str(&format!(
+                "<content type=\"html\">{}</content>\n",
+                html_escape(&content)
+            ));
+        }
+        xml.push_str("</entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+pub fn generate_rss(articles: &[Article], title: &str, now: DateTime<Utc>) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<rss version=\"2.0\">
+<channel>
+<title>{}</title>
+<link>{}</link>
+<description>{}</description>
+<lastBuildDate>{}</lastBuildDate>
+<generator>stash</generator>
+",
+        html_escape(title),
+        LOCAL_SERVER,
+        html_escape(title),
+        latest(articles, now).to_rfc2822()
+    );
+
+    for article in articles {
+        xml.push_str(&format!(
+            "<item>
+<title>{}</title>
+<link>{}</link>
+<guid isPermaLink=\"false\">{}</guid>
+<pubDate>{}</pubDate>
+",
+            html_escape(article_title(article)),
+            html_escape(&article.url),
+            entry_id(article),
+            article.saved_at.to_rfc2822()
+        ));
+        for tag in &article.tags {
+            xml.push_str(&format!("<category>{}</category>\n", html_escape(tag)));
+        }
+        let description =
+            entry_content(article).or_else(|| article.description.as_deref().map(html_escape));
+        if let Some(description) = description {
+            xml.push_str(&format!(
+                "<description>{}</description>\n",
+                html_escape(&description)
+            ));
+        }
+        xml.push_str("</item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+/// The feed in `format` (atom or rss)
+pub fn generate_feed(format: &str, articles: &[Article], title: &str) -> Result<String> {
+    let now = Utc::now();
+    match format {
+        "atom" => Ok(generate_atom(articles, title, now)),
+        "rss" => Ok(generate_rss(articles, title, now)),
+        _ => bail!("Unknown feed format: {}", format),
+    }
+}
+
+pub fn export_to_feed(
+    format: &str,
+    articles: &[Article],
+    title: &str,
+    out: &mut dyn Write,
+) -> Result<()> {
+    out.write_all(generate_feed(format, articles, title)?.as_bytes())
+        .context("Failed to write feed")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Highlight;
+    use crate::export::imported_article;
+
+    fn sample() -> Article {
+        let mut article = imported_article("https://example.com/a?x=1&y=2");
+        article.title = Some("Tom & Jerry".to_string());
+        article.tags = vec!["rust".to_string()];
+        article.note = Some("Worth *reading*".to_string());
+        article.highlights = vec![Highlight {
+            id: 1,
+            article_id: 0,
+            text: "A <quote>".to_string(),
+            position: None,
+            comment: None,
+            created_at: Utc::now(),
+        }];
+        article
+    }
+
+    #[test]
+    fn test_atom_entry() {
+        let article = sample();
+        let atom = generate_atom(std::slice::from_ref(&article), "Stash: #rust", Utc::now());
+        assert!(atom.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(atom.contains("<link href=\"https://example.com/a?x=1&amp;y=2\"/>"));
+        assert!(atom.contains(&format!("<id>urn:stash:{}</id>", article.hash)));
+        assert!(atom.contains("<category term=\"rust\"/>"));
+        assert!(atom.contains(
+            "<content type=\"html\">&lt;p&gt;Worth &lt;em&gt;reading&lt;/em&gt;&lt;/p&gt;\n\
+             &lt;blockquote&gt;A &amp;lt;quote&amp;gt;&lt;/blockquote&gt;\n</content>"
+        ));
+        // The same selection keeps its feed ID
+        assert_eq!(
+            generate_atom(&[], "Stash: #rust", Utc::now())
+                .lines()
+                .nth(3),
+            atom.lines().nth(3)
+        );
+    }
+
+    #[test]
+    fn test_rss_item() {
+        let mut article = sample();
+        let rss = generate_rss(std::slice::from_ref(&article), "Stash", Utc::now());
+        assert!(rss.contains(&format!(
+            "<guid isPermaLink=\"false\">urn:stash:{}</guid>",
+            article.hash
+        )));
+        assert!(rss.contains("<description>&lt;p&gt;Worth"));
+
+        article.note = None;
+        article.highlights.clear();
+        article.description = Some("About <things>".to_string());
+        let rss = generate_rss(&[article], "Stash", Utc::now());
+        assert!(rss.contains("<description>About &amp;lt;things&amp;gt;</description>"));
+    }
+
+    #[test]
+    fn test_feed_title() {
+        assert_eq!(feed_title(&[], false), "Stash");
+        assert_eq!(
+            feed_title(&["rust".to_string(), "cli".to_string()], true),
+            "Stash: #rust #cli, starred"
+        );
+    }
+}
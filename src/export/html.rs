@@ -1,19 +1,15 @@
 use anyhow::{Context, Result};
-use std::fs::File;
+use std::collections::HashMap;
 use std::io::Write;
-use std::path::Path;
 
 use crate::db::models::Article;
 use crate::ui::formatters::datetime_humanize;
 
-pub fn export_to_html(articles: &[Article], output_path: &Path) -> Result<()> {
+pub fn export_to_html(articles: &[Article], out: &mut dyn Write) -> Result<()> {
     let html = generate_html(articles);
     
-    let mut file = File::create(output_path)
-        .context(format!("Failed to create file: {}", output_path.display()))?;
-    
-    file.write_all(html.as_bytes())
-        .context("Failed to write HTML to file")?;
+    out.write_all(html.as_bytes())
+        .context("Failed to write HTML")?;
     
     Ok(())
 }
@@ -160,6 +156,22 @@ fn generate_html(articles: &[Article]) -> String {
             color: #f39c12;
             margin-bottom: 6px;
         }
+        .article-highlights {
+            margin-top: 12px;
+        }
+        .article-highlight {
+            border-left: 4px solid #3498db;
+            padding: 6px 12px;
+            margin-bottom: 8px;
+            color: #444;
+            font-style: italic;
+        }
+        .article-highlight-comment {
+            font-style: normal;
+            color: #7f8c8d;
+            font-size: 0.9em;
+            margin-top: 4px;
+        }
         .tags {
             display: flex;
             gap: 8px;
@@ -301,6 +313,24 @@ fn generate_html(articles: &[Article]) -> String {
 "#, html_escape(note)));
         }
         
+        if !article.highlights.is_empty() {
+            html.push_str(r#"<div class="article-highlights">"#);
+            for highlight in &article.highlights {
+                html.push_str(&format!(
+                    r#"<blockquote class="article-highlight">{}"#,
+                    html_escape(&highlight.text)
+                ));
+                if let Some(comment) = &highlight.comment {
+                    html.push_str(&format!(
+                        r#"<div class="article-highlight-comment">{}</div>"#,
+                        html_escape(comment)
+                    ));
+                }
+                html.push_str("</blockquote>");
+            }
+            html.push_str("</div>");
+        }
+        
         if !article.tags.is_empty() {
             html.push_str(r#"<div class="tags">"#);
             for tag in &article.tags {
@@ -309,12 +339,12 @@ fn generate_html(articles: &[Article]) -> String {
             html.push_str("</div>");
         }
         
-        html.push_str(r#"
+        html.push_str(&format!(r#"
                 <div style="margin-top: 12px;">
                     <a href="{url}" class="article-url" target="_blank">{url}</a>
                 </div>
             </div>
-"#);
+"#));
     }
     
     html.push_str(r#"
@@ -347,7 +377,7 @@ fn generate_html(articles: &[Article]) -> String {
     html
 }
 
-fn html_escape(text: &str) -> String {
+pub(crate) fn html_escape(text: &str) -> String {
     text.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -355,3 +385,83 @@ fn html_escape(text: &str) -> String {
         .replace('\'', "&#39;")
 }
 
+/// Lowercased tag name (`/a` for closing tags) and attributes
+pub(crate) fn parse_tag(tag: &str) -> (String, HashMap<String, String>) {
+    let tag = tag.trim().trim_end_matches('/');
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = tag[..name_end].to_lowercase();
+
+    let mut attrs = HashMap::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            rest = remaining.trim_start();
+            value.to_string()
+        } else {
+            String::new()
+        };
+
+        if !key.is_empty() {
+            attrs.insert(key, value);
+        }
+    }
+
+    (name, attrs)
+}
+
+/// Decode named and numeric character references
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let ch = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        });
+
+        match (entity, ch) {
+            (Some(entity), Some(ch)) => {
+                decoded.push(ch);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
@@ -1,30 +1,214 @@
-use anyhow::{Context, Result};
-use std::fs::File;
+use anyhow::{Context, Result, bail};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
 use std::io::Write;
 use std::path::Path;
 
+use super::reconcile::{Incoming, STATE};
 use crate::db::models::Article;
 
-pub fn export_to_json(articles: &[Article], output_path: &Path) -> Result<()> {
-    let json = serde_json::to_string_pretty(articles)
+/// An article's fields as they're named in JSON, in the order they're written
+pub const FIELDS: [&str; 19] = [
+    "id",
+    "hash",
+    "url",
+    "canonical_url",
+    "title",
+    "site",
+    "description",
+    "favicon_url",
+    "content_markdown",
+    "saved_at",
+    "last_opened_at",
+    "read",
+    "archived",
+    "starred",
+    "note",
+    "tags",
+    "queue_position",
+    "snoozed_until",
+    "highlights",
+];
+
+/// Which fields of each article an export writes
+#[derive(Default)]
+pub enum Fields {
+    #[default]
+    All,
+    Only(Vec<&'static str>),
+    Except(Vec<&'static str>),
+}
+
+impl Fields {
+    /// From a `--fields` list: names to keep, or names to drop with a
+    /// leading `-`. "content" is short for `content_markdown`.
+    pub fn parse(list: &[String]) -> Result<Self> {
+        let dropped = list.iter().filter(|name| name.starts_with('-')).count();
+        if dropped > 0 && dropped < list.len() {
+            bail!("List fields to keep, or fields to drop with a leading '-', but not both");
+        }
+
+        let names = list
+            .iter()
+            .map(|name| {
+                let name = name.trim_start_matches('-').trim();
+                let name = if name == "content" { "content_markdown" } else { name };
+                FIELDS
+                    .iter()
+                    .find(|field| **field == name)
+                    .copied()
+                    .context(format!("Unknown field '{}'. Fields: {}", name, FIELDS.join(", ")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(if names.is_empty() {
+            Fields::All
+        } else if dropped > 0 {
+            Fields::Except(names)
+        } else {
+            Fields::Only(names)
+        })
+    }
+
+    pub fn includes(&self, field: &str) -> bool {
+        match self {
+            Fields::All => true,
+            Fields::Only(names) => names.contains(&field),
+            Fields::Except(names) => !names.contains(&field),
+        }
+    }
+}
+
+/// An article serialized with only the selected fields
+struct Selected<'a> {
+    article: &'a Article,
+    fields: &'a Fields,
+}
+
+impl Serialize for Selected<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let a = self.article;
+        if let Fields::All = self.fields {
+            return a.serialize(serializer);
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        for field in FIELDS.iter().filter(|field| self.fields.includes(field)) {
+            match *field {
+                "id" => map.serialize_entry(field, &a.id)?,
+                "hash" => map.serialize_entry(field, &a.hash)?,
+                "url" => map.serialize_entry(field, &a.url)?,
+                "canonical_url" => map.serialize_entry(field, &a.canonical_url)?,
+                "title" => map.serialize_entry(field, &a.title)?,
+                "site" => map.serialize_entry(field, &a.site)?,
+                "description" => map.serialize_entry(field, &a.description)?,
+                "favicon_url" => map.serialize_entry(field, &a.favicon_url)?,
+                "content_markdown" => map.serialize_entry(field, &a.content_markdown)?,
+                "saved_at" => map.serialize_entry(field, &a.saved_at)?,
+                "last_opened_at" => map.serialize_entry(field, &a.last_opened_at)?,
+                "read" => map.serialize_entry(field, &a.read)?,
+                "archived" => map.serialize_entry(field, &a.archived)?,
+                "starred" => map.serialize_entry(field, &a.starred)?,
+                "note" => map.serialize_entry(field, &a.note)?,
+                "tags" => map.serialize_entry(field, &a.tags)?,
+                // Left out when unset, as in a full export
+                "queue_position" if a.queue_position.is_some() => {
+                    map.serialize_entry(field, &a.queue_position)?
+                }
+                "snoozed_until" if a.snoozed_until.is_some() => {
+                    map.serialize_entry(field, &a.snoozed_until)?
+                }
+                "highlights" if !a.highlights.is_empty() => {
+                    map.serialize_entry(field, &a.highlights)?
+                }
+                _ => {}
+            }
+        }
+        map.end()
+    }
+}
+
+pub fn export_to_json(articles: &[Article], fields: &Fields, out: &mut dyn Write) -> Result<()> {
+    let mut serializer = serde_json::Serializer::pretty(out);
+    serializer
+        .collect_seq(articles.iter().map(|article| Selected { article, fields }))
         .context("Failed to serialize articles to JSON")?;
+    serializer.into_inner().write_all(b"\n")?;
     
-    let mut file = File::create(output_path)
-        .context(format!("Failed to create file: {}", output_path.display()))?;
-    
-    file.write_all(json.as_bytes())
-        .context("Failed to write JSON to file")?;
+    Ok(())
+}
+
+/// One line of an NDJSON export
+pub fn write_ndjson(article: &Article, fields: &Fields, out: &mut dyn Write) -> Result<()> {
+    serde_json::to_writer(&mut *out, &Selected { article, fields })
+        .context("Failed to serialize article to JSON")?;
+    out.write_all(b"\n")?;
     
     Ok(())
 }
 
-pub fn import_from_json(json_path: &Path) -> Result<Vec<Article>> {
+pub fn import_from_json(json_path: &Path) -> Result<Vec<Incoming>> {
     let json_content = std::fs::read_to_string(json_path)
         .context(format!("Failed to read file: {}", json_path.display()))?;
     
     let articles: Vec<Article> = serde_json::from_str(&json_content)
         .context("Failed to parse JSON")?;
     
-    Ok(articles)
+    Ok(articles.into_iter().map(incoming).collect())
+}
+
+/// A JSON export carries every date and all of the state
+fn incoming(article: Article) -> Incoming {
+    let saved_at = article.saved_at;
+    Incoming::new(article, Some(saved_at), STATE.to_vec())
+}
+
+
+/// One article per line, as `stash export --format ndjson` writes them
+pub fn import_from_ndjson(path: &Path) -> Result<Vec<Incoming>> {
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read file: {}", path.display()))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .map(incoming)
+                .context(format!("Failed to parse JSON on line {}", idx + 1))
+        })
+        .collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::imported_article;
+
+    fn fields(list: &[&str]) -> Result<Fields> {
+        Fields::parse(&list.iter().map(|f| f.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_ndjson_fields() {
+        let mut article = imported_article("https://example.com/a");
+        article.content_markdown = Some("Long\ntext".to_string());
+        article.tags = vec!["rust".to_string()];
+
+        let mut out = Vec::new();
+        write_ndjson(&article, &fields(&["tags", "url"]).unwrap(), &mut out).unwrap();
+        write_ndjson(&article, &fields(&["-content", "-highlights"]).unwrap(), &mut out).unwrap();
+        write_ndjson(&article, &Fields::All, &mut out).unwrap();
+
+        let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+        // Fields keep their usual order, whatever order they're asked for in
+        assert_eq!(lines[0], r#"{"url":"https://example.com/a","tags":["rust"]}"#);
+        assert!(!lines[1].contains("content_markdown") && lines[1].contains("\"note\":null"));
+        let full: Article = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(full.content_markdown, article.content_markdown);
+
+        assert!(fields(&["url", "-content"]).is_err());
+        assert!(fields(&["bogus"]).is_err());
+    }
+}
@@ -1,46 +1,92 @@
-use anyhow::{Context, Result};
+// Markdown export, one file per article, that imports back losslessly
+//
+// Everything except the content lives in YAML front matter; the body is the
+// article's `content_markdown` as is. Hand-written files can leave out
+// everything but `url`: the hash is derived from it and `saved_at` defaults
+// to the time of import.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
-use crate::db::models::Article;
+use super::{imported_article, reconcile::Incoming};
+use crate::db::models::{Article, Highlight};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FrontMatter {
+    #[serde(default)]
+    id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    url: String,
+    /// Only written when it differs from `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    canonical_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    site: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    favicon_url: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    read: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archived: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    starred: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    saved_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_opened_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snoozed_until: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    highlights: Vec<FrontMatterHighlight>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FrontMatterHighlight {
+    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    position: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+}
 
 pub fn export_to_markdown(articles: &[Article], output_dir: &Path) -> Result<()> {
     // Create output directory if it doesn't exist
-    fs::create_dir_all(output_dir)
-        .context(format!("Failed to create directory: {}", output_dir.display()))?;
-    
+    fs::create_dir_all(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
     for article in articles {
         let filename = sanitize_filename(article);
         let file_path = output_dir.join(filename);
-        
-        let content = format_article_as_markdown(article);
-        
+
+        let content = format_article_as_markdown(article)?;
+
         fs::write(&file_path, content)
             .context(format!("Failed to write file: {}", file_path.display()))?;
     }
-    
+
     Ok(())
 }
 
-#[allow(dead_code)]
-pub fn import_from_markdown(markdown_dir: &Path) -> Result<Vec<Article>> {
-    let mut articles = Vec::new();
-    
-    let entries = fs::read_dir(markdown_dir)
-        .context(format!("Failed to read directory: {}", markdown_dir.display()))?;
-    
-    for entry in entries {
-        let entry = entry.context("Failed to read directory entry")?;
-        let path = entry.path();
-        
-        if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            if let Ok(article) = parse_markdown_file(&path) {
-                articles.push(article);
-            }
-        }
-    }
-    
-    Ok(articles)
+/// The article in an exported `.md` file. Markdown files without front
+/// matter (a README, say) hold none.
+pub fn import_from_markdown(path: &Path) -> Result<Vec<Incoming>> {
+    Ok(parse_markdown_file(path)?.into_iter().collect())
 }
 
 fn sanitize_filename(article: &Article) -> String {
@@ -53,61 +99,195 @@ fn sanitize_filename(article: &Article) -> String {
         })
         .take(100)
         .collect();
-    
+
     format!("{}-{}.md", article.id, safe_title)
 }
 
-fn format_article_as_markdown(article: &Article) -> String {
-    let mut content = String::new();
-    
-    // Title
-    content.push_str(&format!("# {}\n\n", article.title.as_deref().unwrap_or("Untitled")));
-    
-    // Metadata
-    content.push_str("---\n\n");
-    content.push_str(&format!("- **ID**: {}\n", article.id));
-    content.push_str(&format!("- **URL**: {}\n", article.url));
-    
-    if let Some(site) = &article.site {
-        content.push_str(&format!("- **Site**: {}\n", site));
+fn format_article_as_markdown(article: &Article) -> Result<String> {
+    let front_matter = FrontMatter {
+        id: article.id,
+        hash: Some(article.hash.clone()),
+        url: article.url.clone(),
+        canonical_url: Some(article.canonical_url.clone()).filter(|c| *c != article.url),
+        title: article.title.clone(),
+        site: article.site.clone(),
+        description: article.description.clone(),
+        favicon_url: article.favicon_url.clone(),
+        tags: article.tags.clone(),
+        read: Some(article.read),
+        archived: Some(article.archived),
+        starred: Some(article.starred),
+        saved_at: Some(article.saved_at),
+        last_opened_at: article.last_opened_at,
+        snoozed_until: article.snoozed_until,
+        note: article.note.clone(),
+        highlights: article
+            .highlights
+            .iter()
+            .map(|h| FrontMatterHighlight {
+                text: h.text.clone(),
+                comment: h.comment.clone(),
+                position: h.position,
+                created_at: Some(h.created_at),
+            })
+            .collect(),
+    };
+
+    let yaml = serde_yaml::to_string(&front_matter).context("Failed to serialize front matter")?;
+
+    let mut content = format!("---\n{}---\n", yaml);
+    if let Some(markdown_content) = &article.content_markdown {
+        content.push('\n');
+        content.push_str(markdown_content);
+    }
+
+    Ok(content)
+}
+
+/// The YAML between the opening and closing `---` lines, and the body
+fn split_front_matter(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start_matches('\u{feff}');
+    let rest = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
     }
-    
-    content.push_str(&format!("- **Saved**: {}\n", article.saved_at));
-    
-    if !article.tags.is_empty() {
-        content.push_str(&format!("- **Tags**: {}\n", article.tags.join(", ")));
+
+    None
+}
+
+fn parse_markdown(text: &str) -> Result<Option<Incoming>> {
+    let Some((yaml, body)) = split_front_matter(text) else {
+        return Ok(None);
+    };
+    let front_matter: FrontMatter =
+        serde_yaml::from_str(yaml).context("Failed to parse front matter")?;
+    if front_matter.url.trim().is_empty() {
+        bail!("Front matter has no url");
     }
-    
-    content.push_str(&format!("- **Read**: {}\n", if article.read { "Yes" } else { "No" }));
-    content.push_str(&format!("- **Starred**: {}\n", if article.starred { "Yes" } else { "No" }));
-    content.push_str(&format!("- **Archived**: {}\n", if article.archived { "Yes" } else { "No" }));
-    
-    content.push_str("\n---\n\n");
-    
-    // Description
-    if let Some(description) = &article.description {
-        content.push_str(&format!("## Description\n\n{}\n\n", description));
+
+    let mut article = imported_article(&front_matter.url);
+    article.id = front_matter.id;
+    if let Some(hash) = front_matter.hash {
+        article.hash = hash;
     }
-    
-    // Note
-    if let Some(note) = &article.note {
-        content.push_str(&format!("## Note\n\n{}\n\n", note));
+    if let Some(canonical_url) = front_matter.canonical_url {
+        article.canonical_url = canonical_url;
     }
-    
-    // Content
-    if let Some(markdown_content) = &article.content_markdown {
-        content.push_str("## Content\n\n");
-        content.push_str(markdown_content);
-        content.push('\n');
+    article.title = front_matter.title;
+    if front_matter.site.is_some() {
+        article.site = front_matter.site;
     }
-    
-    content
+    article.description = front_matter.description;
+    article.favicon_url = front_matter.favicon_url;
+    article.tags = front_matter.tags;
+    // Hand-written files may leave out any of the state
+    let state = [
+        ("read", front_matter.read),
+        ("archived", front_matter.archived),
+        ("starred", front_matter.starred),
+        ("snoozed_until", front_matter.snoozed_until.map(|_| true)),
+    ];
+    let state: Vec<&'static str> = state
+        .iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(field, _)| *field)
+        .collect();
+    article.read = front_matter.read.unwrap_or(false);
+    article.archived = front_matter.archived.unwrap_or(false);
+    article.starred = front_matter.starred.unwrap_or(false);
+    article.last_opened_at = front_matter.last_opened_at;
+    article.snoozed_until = front_matter.snoozed_until;
+    article.note = front_matter.note;
+    article.highlights = front_matter
+        .highlights
+        .into_iter()
+        .map(|h| Highlight {
+            id: 0,
+            article_id: article.id,
+            text: h.text,
+            position: h.position,
+            comment: h.comment,
+            created_at: h.created_at.unwrap_or_else(Utc::now),
+        })
+        .collect();
+
+    // The blank line after the front matter is ours, not the content's
+    let body = body
+        .strip_prefix('\n')
+        .or_else(|| body.strip_prefix("\r\n"))
+        .unwrap_or(body);
+    article.content_markdown = (!body.is_empty()).then(|| body.to_string());
+
+    Ok(Some(Incoming::new(article, front_matter.saved_at, state)))
 }
 
-#[allow(dead_code)]
-fn parse_markdown_file(_path: &Path) -> Result<Article> {
-    // This is a simplified parser - in production you'd want more robust parsing
-    // For now, we'll return an error as this is complex to implement properly
-    anyhow::bail!("Markdown import not fully implemented - use JSON import instead")
+fn parse_markdown_file(path: &Path) -> Result<Option<Incoming>> {
+    let text =
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+    parse_markdown(&text).context(format!("Failed to import {}", path.display()))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Article {
+        let mut article = imported_article("https://example.com/post?utm=x");
+        article.id = 42;
+        article.canonical_url = "https://example.com/post".to_string();
+        article.title = Some("A \"quoted\": title".to_string());
+        article.description = Some("About things".to_string());
+        article.tags = vec!["lang/rust".to_string(), "cli".to_string()];
+        article.read = true;
+        article.starred = true;
+        article.saved_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        article.last_opened_at = DateTime::from_timestamp(1_700_100_000, 0);
+        article.note = Some("Line one\n\n- line: two\n".to_string());
+        article.content_markdown = Some("# Heading\n\n---\n\nBody text\n".to_string());
+        article.highlights = vec![Highlight {
+            id: 7,
+            article_id: 42,
+            text: "Body text".to_string(),
+            position: Some(15),
+            comment: Some("key".to_string()),
+            created_at: DateTime::from_timestamp(1_700_200_000, 0).unwrap(),
+        }];
+        article
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let original = sample();
+        let parsed = parse_markdown(&format_article_as_markdown(&original).unwrap())
+            .unwrap()
+            .unwrap()
+            .article;
+
+        let mut expected = serde_json::to_value(&original).unwrap();
+        expected["highlights"][0]["id"] = 0.into();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_hand_written() {
+        let incoming = parse_markdown("---\nurl: https://example.com/a\ntags: [rust]\n---\n")
+            .unwrap()
+            .unwrap();
+        assert!(incoming.saved_at.is_none() && incoming.state.is_empty());
+        let parsed = incoming.article;
+        assert_eq!(parsed.hash.len(), 8);
+        assert_eq!(parsed.site.as_deref(), Some("example.com"));
+        assert_eq!(parsed.tags, vec!["rust"]);
+        assert_eq!(parsed.content_markdown, None);
+
+        assert!(parse_markdown("# Just notes\n").unwrap().is_none());
+        assert!(parse_markdown("---\ntitle: no url\n---\n").is_err());
+    }
+}
@@ -1,4 +1,44 @@
+pub mod detect;
+pub mod epub;
+pub mod feed;
+pub mod html;
 pub mod json;
 pub mod markdown;
-pub mod html;
+pub mod netscape;
+pub mod reconcile;
+pub mod render;
+pub mod services;
+pub mod site;
+pub mod table;
+pub mod template;
+pub mod urls;
+
+use chrono::Utc;
+
+use crate::db::models::{Article, NewArticle};
+use crate::fetch::http::extract_site;
 
+/// An unsaved article for `url` with default state, for importers to fill in
+pub(crate) fn imported_article(url: &str) -> Article {
+    Article {
+        id: 0,
+        hash: NewArticle::hash_url(url),
+        url: url.to_string(),
+        canonical_url: url.to_string(),
+        title: None,
+        site: extract_site(url),
+        description: None,
+        favicon_url: None,
+        content_markdown: None,
+        saved_at: Utc::now(),
+        last_opened_at: None,
+        read: false,
+        archived: false,
+        starred: false,
+        note: None,
+        tags: Vec::new(),
+        queue_position: None,
+        snoozed_until: None,
+        highlights: Vec::new(),
+    }
+}
@@ -0,0 +1,331 @@
+// Netscape bookmark files (`bookmarks.html`), as written and read by browsers
+// and most bookmarking services
+//
+// On import, the folder path becomes a nested tag (`Dev > Rust` -> `dev/rust`),
+// alongside any `TAGS` attribute, and `ADD_DATE` becomes `saved_at`. On
+// export, each article goes in the folder of its first tag and carries all
+// of its tags in `TAGS`, so importing the file again gives the same tags.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use super::html::{decode_entities, html_escape, parse_tag};
+use super::{imported_article, reconcile::Incoming};
+use crate::db::{models::Article, tags};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub added_at: Option<DateTime<Utc>>,
+}
+
+pub fn export_to_netscape(articles: &[Article], out: &mut dyn Write) -> Result<()> {
+    out.write_all(generate_bookmarks(articles).as_bytes())
+        .context("Failed to write bookmarks")?;
+
+    Ok(())
+}
+
+pub fn import_from_netscape(path: &Path) -> Result<Vec<Incoming>> {
+    let html =
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+
+    Ok(parse_bookmarks(&html)
+        .into_iter()
+        .map(bookmark_to_article)
+        .collect())
+}
+
+/// Bookmarks have a date but no state
+fn bookmark_to_article(bookmark: Bookmark) -> Incoming {
+    let mut article = imported_article(&bookmark.url);
+    article.title = bookmark.title;
+    article.description = bookmark.description;
+    article.tags = bookmark.tags;
+    Incoming::new(article, bookmark.added_at, Vec::new())
+}
+
+/// What the text between tags belongs to
+enum Capture {
+    None,
+    Folder(HashMap<String, String>),
+    Link(HashMap<String, String>),
+    Description,
+}
+
+/// Parse a bookmark file. The format is loose HTML that's rarely well-formed
+/// (`<DT>` and `<p>` are never closed), so this scans tags rather than
+/// building a tree. Only http(s) links are kept.
+pub fn parse_bookmarks(html: &str) -> Vec<Bookmark> {
+    let mut bookmarks: Vec<Bookmark> = Vec::new();
+    // Tag for each open `<DL>`; None for the root and browser special folders
+    let mut folders: Vec<Option<String>> = Vec::new();
+    let mut pending_folder: Option<Option<String>> = None;
+    let mut capture = Capture::None;
+    let mut text = String::new();
+    let mut describes_last = false;
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let (name, attrs) = parse_tag(&rest[start + 1..start + len]);
+        rest = &rest[start + len + 1..];
+
+        // A description runs until the next tag of any kind
+        if let Capture::Description = capture {
+            if describes_last && let Some(bookmark) = bookmarks.last_mut() {
+                let description = decode_entities(text.trim());
+                bookmark.description = (!description.is_empty()).then_some(description);
+            }
+            capture = Capture::None;
+            describes_last = false;
+        }
+
+        match (
+            name.as_str(),
+            std::mem::replace(&mut capture, Capture::None),
+        ) {
+            ("h3", _) => {
+                capture = Capture::Folder(attrs);
+                describes_last = false;
+                text.clear();
+            }
+            ("/h3", Capture::Folder(attrs)) => {
+                let special = attrs.contains_key("personal_toolbar_folder")
+                    || attrs.contains_key("unfiled_bookmarks_folder");
+                pending_folder = Some(if special {
+                    None
+                } else {
+                    tags::slugify(&decode_entities(text.trim()))
+                });
+            }
+            ("dl", _) => folders.push(pending_folder.take().flatten()),
+            ("/dl", _) => {
+                folders.pop();
+            }
+            ("a", _) => {
+                capture = Capture::Link(attrs);
+                text.clear();
+            }
+            ("/a", Capture::Link(attrs)) => {
+                describes_last = false;
+                if let Some(url) = attrs
+                    .get("href")
+                    .filter(|href| href.starts_with("http://") || href.starts_with("https://"))
+                {
+                    bookmarks.push(Bookmark {
+                        url: decode_entities(url),
+                        title: Some(decode_entities(text.trim())).filter(|t| !t.is_empty()),
+                        description: None,
+                        tags: bookmark_tags(&folders, attrs.get("tags")),
+                        added_at: attrs.get("add_date").and_then(|date| parse_timestamp(date)),
+                    });
+                    describes_last = true;
+                }
+            }
+            ("dd", _) => {
+                capture = Capture::Description;
+                text.clear();
+            }
+            // Formatting inside a title or description
+            (_, previous) => capture = previous,
+        }
+
+        if let Capture::None = capture {
+            text.clear();
+        }
+    }
+
+    bookmarks
+}
+
+/// The folder path as one nested tag, followed by the `TAGS` attribute
+fn bookmark_tags(folders: &[Option<String>], tags_attr: Option<&String>) -> Vec<String> {
+    let mut bookmark_tags: Vec<String> = Vec::new();
+    let path: Vec<&str> = folders.iter().flatten().map(String::as_str).collect();
+    if !path.is_empty() {
+        bookmark_tags.push(path.join(&tags::SEPARATOR.to_string()));
+    }
+
+    for tag in tags_attr
+        .into_iter()
+        .flat_map(|attr| attr.split(','))
+        .filter_map(tags::slugify)
+    {
+        if !bookmark_tags.contains(&tag) {
+            bookmark_tags.push(tag);
+        }
+    }
+
+    bookmark_tags
+}
+
+/// `ADD_DATE` is Unix seconds, though some exporters write milliseconds or
+/// microseconds
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let mut timestamp: i64 = value.trim().parse().ok()?;
+    while timestamp > 100_000_000_000 {
+        timestamp /= 1000;
+    }
+    (timestamp > 0)
+        .then(|| DateTime::from_timestamp(timestamp, 0))
+        .flatten()
+}
+
+/// Articles grouped by their first tag, nested like the tag
+#[derive(Default)]
+struct Folder<'a> {
+    folders: BTreeMap<&'a str, Folder<'a>>,
+    articles: Vec<&'a Article>,
+}
+
+pub fn generate_bookmarks(articles: &[Article]) -> String {
+    let mut root = Folder::default();
+    for article in articles {
+        let mut folder = &mut root;
+        if let Some(tag) = article.tags.first() {
+            for segment in tag.split(tags::SEPARATOR) {
+                folder = folder.folders.entry(segment).or_default();
+            }
+        }
+        folder.articles.push(article);
+    }
+
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+",
+    );
+    write_folder(&mut html, &root, 0);
+    html
+}
+
+fn write_folder(html: &mut String, folder: &Folder, depth: usize) {
+    let indent = "    ".repeat(depth);
+    html.push_str(&format!("{}<DL><p>\n", indent));
+
+    for article in &folder.articles {
+        let mut attrs = format!(
+            "HREF=\"{}\" ADD_DATE=\"{}\"",
+            html_escape(&article.url),
+            article.saved_at.timestamp()
+        );
+        if let Some(opened) = article.last_opened_at {
+            attrs.push_str(&format!(" LAST_VISIT=\"{}\"", opened.timestamp()));
+        }
+        if !article.tags.is_empty() {
+            attrs.push_str(&format!(
+                " TAGS=\"{}\"",
+                html_escape(&article.tags.join(","))
+            ));
+        }
+
+        html.push_str(&format!(
+            "{}    <DT><A {}>{}</A>\n",
+            indent,
+            attrs,
+            html_escape(article.title.as_deref().unwrap_or(&article.url))
+        ));
+        if let Some(description) = &article.description {
+            html.push_str(&format!("{}    <DD>{}\n", indent, html_escape(description)));
+        }
+    }
+
+    for (name, subfolder) in &folder.folders {
+        html.push_str(&format!(
+            "{}    <DT><H3>{}</H3>\n",
+            indent,
+            html_escape(name)
+        ));
+        write_folder(html, subfolder, depth + 1);
+    }
+
+    html.push_str(&format!("{}</DL><p>\n", indent));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks Menu</H1>
+<DL><p>
+    <DT><A HREF="https://example.com/top" ADD_DATE="1700000000">Top &amp; level</A>
+    <DT><H3 ADD_DATE="1600000000" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks Toolbar</H3>
+    <DL><p>
+        <DT><H3>Dev Tools</H3>
+        <DD>Folder description
+        <DL><p>
+            <DT><H3>Rust</H3>
+            <DL><p>
+                <DT><A HREF="https://blog.rust-lang.org/" ADD_DATE="1700000100000" TAGS="Async,lang">Rust Blog</A>
+                <DD>News from the Rust team
+            </DL><p>
+            <DT><A HREF="place:sort=8&amp;maxResults=10">Recent</A>
+            <DT><A HREF="https://github.com/">GitHub</A>
+        </DL><p>
+    </DL><p>
+</DL><p>
+"#;
+
+    #[test]
+    fn test_parse_bookmarks() {
+        let bookmarks = parse_bookmarks(FIREFOX);
+        assert_eq!(bookmarks.len(), 3);
+
+        assert_eq!(bookmarks[0].title.as_deref(), Some("Top & level"));
+        assert!(bookmarks[0].tags.is_empty());
+        assert_eq!(bookmarks[0].added_at.unwrap().timestamp(), 1_700_000_000);
+
+        assert_eq!(bookmarks[1].url, "https://blog.rust-lang.org/");
+        assert_eq!(bookmarks[1].tags, vec!["dev-tools/rust", "async", "lang"]);
+        assert_eq!(bookmarks[1].added_at.unwrap().timestamp(), 1_700_000_100);
+        assert_eq!(
+            bookmarks[1].description.as_deref(),
+            Some("News from the Rust team")
+        );
+
+        assert_eq!(bookmarks[2].tags, vec!["dev-tools"]);
+        assert_eq!(bookmarks[2].description, None);
+        assert_eq!(bookmarks[2].added_at, None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut articles: Vec<Article> = parse_bookmarks(FIREFOX)
+            .into_iter()
+            .map(|bookmark| bookmark_to_article(bookmark).article)
+            .collect();
+        articles[2].title = Some("<GitHub> \"quoted\"".to_string());
+
+        let reparsed = parse_bookmarks(&generate_bookmarks(&articles));
+        assert_eq!(reparsed.len(), articles.len());
+        for article in &articles {
+            let bookmark = reparsed.iter().find(|b| b.url == article.url).unwrap();
+            assert_eq!(bookmark.title, article.title);
+            assert_eq!(bookmark.tags, article.tags);
+            assert_eq!(bookmark.description, article.description);
+            assert_eq!(
+                bookmark.added_at.map(|at| at.timestamp()),
+                Some(article.saved_at.timestamp())
+            );
+        }
+    }
+}
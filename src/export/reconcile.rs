@@ -0,0 +1,314 @@
+// Reconciling imported articles with the library
+//
+// `import --merge` folds an imported copy into the article already stashed
+// under the same hash, field by field:
+//
+// - tags: union
+// - note: both notes, concatenated (unless one already contains the other)
+// - highlights: imported ones are added unless the same text is there
+// - read/archived/starred, snooze, title, description and content: the newer
+//   copy wins, where a copy's age is its last activity (opened, or saved).
+//   An imported copy only counts as newer if its source had a date, and only
+//   brings the state its source tracks.
+// - saved_at keeps the earliest date, last_opened_at the latest
+
+use chrono::{DateTime, Utc};
+
+use crate::db::models::{Article, Highlight};
+
+/// Fields of an article as named in import reports, in report order
+pub const FIELDS: [&str; 12] = [
+    "title",
+    "description",
+    "content",
+    "tags",
+    "note",
+    "read",
+    "archived",
+    "starred",
+    "saved_at",
+    "last_opened_at",
+    "snoozed_until",
+    "highlights",
+];
+
+/// The state fields an import source can track, as named in `FIELDS`
+pub const STATE: [&str; 4] = ["read", "archived", "starred", "snoozed_until"];
+
+/// An article read from an import source, with what the source said about
+/// it beyond the article's own fields
+#[derive(Debug, Clone)]
+pub struct Incoming {
+    pub article: Article,
+    /// When the source says the article was saved, None when it had no date
+    /// (`article.saved_at` is then just the import time)
+    pub saved_at: Option<DateTime<Utc>>,
+    /// Which of `STATE` the source tracks, so that defaults for state it
+    /// doesn't have aren't taken as its own
+    pub state: Vec<&'static str>,
+}
+
+impl Incoming {
+    /// `article` from a source that gave `saved_at` (if anything) and tracks
+    /// `state`. The date, when there is one, also becomes the article's.
+    pub fn new(
+        mut article: Article,
+        saved_at: Option<DateTime<Utc>>,
+        state: Vec<&'static str>,
+    ) -> Self {
+        if let Some(saved_at) = saved_at {
+            article.saved_at = saved_at;
+        }
+        Self {
+            article,
+            saved_at,
+            state,
+        }
+    }
+}
+
+/// Fields an imported article carries beyond their defaults
+pub fn present_fields(incoming: &Incoming) -> Vec<&'static str> {
+    let article = &incoming.article;
+    let present = [
+        article.title.is_some(),
+        article.description.is_some(),
+        article.content_markdown.is_some(),
+        !article.tags.is_empty(),
+        article.note.is_some(),
+        article.read,
+        article.archived,
+        article.starred,
+        incoming.saved_at.is_some(),
+        article.last_opened_at.is_some(),
+        article.snoozed_until.is_some(),
+        !article.highlights.is_empty(),
+    ];
+
+    FIELDS
+        .iter()
+        .zip(present)
+        .filter(|(_, present)| *present)
+        .map(|(field, _)| *field)
+        .collect()
+}
+
+pub struct Merged {
+    /// The existing article with the imported copy folded in
+    pub article: Article,
+    /// Imported highlights the existing article doesn't have yet
+    pub new_highlights: Vec<Highlight>,
+    /// Names (from `FIELDS`) of the fields that changed
+    pub changed: Vec<&'static str>,
+}
+
+/// When a copy was last opened or saved, None for an imported copy whose
+/// source had neither date
+fn last_activity(
+    saved_at: Option<DateTime<Utc>>,
+    last_opened_at: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    saved_at.max(last_opened_at)
+}
+
+fn merge_notes(existing: Option<&str>, incoming: Option<&str>) -> Option<String> {
+    let existing = existing.map(str::trim).filter(|n| !n.is_empty());
+    let incoming = incoming.map(str::trim).filter(|n| !n.is_empty());
+
+    match (existing, incoming) {
+        (Some(a), Some(b)) if a.contains(b) => Some(a.to_string()),
+        (Some(a), Some(b)) if b.contains(a) => Some(b.to_string()),
+        (Some(a), Some(b)) => Some(format!("{}\n\n{}", a, b)),
+        (a, b) => a.or(b).map(str::to_string),
+    }
+}
+
+/// `existing` should have its highlights attached
+pub fn merge(existing: &Article, imported: &Incoming) -> Merged {
+    let incoming = &imported.article;
+    let mut article = existing.clone();
+
+    for tag in &incoming.tags {
+        if !article.tags.contains(tag) {
+            article.tags.push(tag.clone());
+        }
+    }
+    article.note = merge_notes(existing.note.as_deref(), incoming.note.as_deref());
+
+    let incoming_newer = last_activity(imported.saved_at, incoming.last_opened_at)
+        > last_activity(Some(existing.saved_at), existing.last_opened_at);
+
+    if incoming_newer {
+        let tracks = |field: &str| imported.state.contains(&field);
+        if tracks("read") {
+            article.read = incoming.read;
+        }
+        if tracks("archived") {
+            article.archived = incoming.archived;
+        }
+        if tracks("starred") {
+            article.starred = incoming.starred;
+        }
+        if tracks("snoozed_until") {
+            article.snoozed_until = incoming.snoozed_until;
+        }
+        article.title = incoming.title.clone().or(article.title);
+        article.description = incoming.description.clone().or(article.description);
+        article.content_markdown = incoming
+            .content_markdown
+            .clone()
+            .or(article.content_markdown);
+    } else {
+        article.title = article.title.or_else(|| incoming.title.clone());
+        article.description = article.description.or_else(|| incoming.description.clone());
+        article.content_markdown = article
+            .content_markdown
+            .or_else(|| incoming.content_markdown.clone());
+    }
+    article.favicon_url = article.favicon_url.or_else(|| incoming.favicon_url.clone());
+
+    article.saved_at = imported
+        .saved_at
+        .map_or(existing.saved_at, |saved_at| {
+            saved_at.min(existing.saved_at)
+        });
+    article.last_opened_at = existing.last_opened_at.max(incoming.last_opened_at);
+
+    let new_highlights: Vec<Highlight> = incoming
+        .highlights
+        .iter()
+        .filter(|h| !existing.highlights.iter().any(|e| e.text == h.text))
+        .cloned()
+        .collect();
+
+    let changed = [
+        article.title != existing.title,
+        article.description != existing.description,
+        article.content_markdown != existing.content_markdown,
+        article.tags != existing.tags,
+        article.note != existing.note,
+        article.read != existing.read,
+        article.archived != existing.archived,
+        article.starred != existing.starred,
+        article.saved_at != existing.saved_at,
+        article.last_opened_at != existing.last_opened_at,
+        article.snoozed_until != existing.snoozed_until,
+        !new_highlights.is_empty(),
+    ];
+    let changed: Vec<&'static str> = FIELDS
+        .iter()
+        .zip(changed)
+        .filter(|(_, changed)| *changed)
+        .map(|(field, _)| *field)
+        .collect();
+
+    Merged {
+        article,
+        new_highlights,
+        changed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::imported_article;
+
+    fn at(timestamp: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(timestamp, 0).unwrap()
+    }
+
+    fn highlight(text: &str) -> Highlight {
+        Highlight {
+            id: 0,
+            article_id: 0,
+            text: text.to_string(),
+            position: None,
+            comment: None,
+            created_at: at(0),
+        }
+    }
+
+    fn article(tags: &[&str], note: Option<&str>, saved: i64, opened: Option<i64>) -> Incoming {
+        let mut article = imported_article("https://example.com/a");
+        article.tags = tags.iter().map(|t| t.to_string()).collect();
+        article.note = note.map(str::to_string);
+        article.last_opened_at = opened.map(at);
+        Incoming::new(article, Some(at(saved)), STATE.to_vec())
+    }
+
+    #[test]
+    fn test_merge_newer_import_wins_state() {
+        let existing = article(&["rust"], Some("Mine"), 2_000, None).article;
+        let mut incoming = article(&["cli", "rust"], Some("Theirs"), 1_000, Some(3_000));
+        incoming.article.read = true;
+        incoming.article.title = Some("Better title".to_string());
+
+        let merged = merge(&existing, &incoming);
+        assert_eq!(merged.article.tags, vec!["rust", "cli"]);
+        assert_eq!(merged.article.note.as_deref(), Some("Mine\n\nTheirs"));
+        assert!(merged.article.read);
+        assert_eq!(merged.article.title.as_deref(), Some("Better title"));
+        assert_eq!(merged.article.saved_at, at(1_000));
+        assert_eq!(merged.article.last_opened_at, Some(at(3_000)));
+        assert_eq!(
+            merged.changed,
+            vec![
+                "title",
+                "tags",
+                "note",
+                "read",
+                "saved_at",
+                "last_opened_at"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_older_import_only_fills_gaps() {
+        let mut existing = article(&["rust"], Some("Mine, and more"), 2_000, Some(5_000)).article;
+        existing.starred = true;
+        existing.highlights = vec![highlight("Kept")];
+        let mut incoming = article(&["rust"], Some("Mine"), 2_000, Some(4_000));
+        incoming.article.description = Some("From the import".to_string());
+        incoming.article.highlights = vec![highlight("Kept"), highlight("New")];
+
+        let merged = merge(&existing, &incoming);
+        assert!(merged.article.starred);
+        assert_eq!(merged.article.note.as_deref(), Some("Mine, and more"));
+        assert_eq!(
+            merged.article.description.as_deref(),
+            Some("From the import")
+        );
+        assert_eq!(merged.new_highlights.len(), 1);
+        assert_eq!(merged.new_highlights[0].text, "New");
+        assert_eq!(merged.changed, vec!["description", "highlights"]);
+
+        let unchanged = Incoming::new(existing.clone(), Some(existing.saved_at), STATE.to_vec());
+        assert!(merge(&existing, &unchanged).changed.is_empty());
+    }
+
+    #[test]
+    fn test_merge_undated_or_stateless_import_keeps_state() {
+        let mut existing = article(&["rust"], None, 2_000, Some(3_000)).article;
+        existing.read = true;
+        existing.starred = true;
+
+        // A URL list: no date, no state
+        let from_urls = Incoming::new(imported_article("https://example.com/a"), None, Vec::new());
+        let merged = merge(&existing, &from_urls);
+        assert!(merged.article.read && merged.article.starred);
+        assert_eq!(merged.article.saved_at, at(2_000));
+        assert!(merged.changed.is_empty());
+
+        // Pinboard: newer, but only tracks whether it's been read
+        let from_pinboard = Incoming::new(
+            imported_article("https://example.com/a"),
+            Some(at(4_000)),
+            vec!["read"],
+        );
+        let merged = merge(&existing, &from_pinboard);
+        assert!(!merged.article.read && merged.article.starred);
+        assert_eq!(merged.changed, vec!["read"]);
+    }
+}
@@ -0,0 +1,163 @@
+// Markdown to HTML, for exports that show article content
+//
+// Parsing and HTML output are pulldown-cmark's (CommonMark plus pipe
+// tables); this module only filters the events in between. Raw HTML is
+// escaped rather than passed through, and links and images only keep a URL
+// that is http(s), mailto or relative. The output is well-formed XHTML
+// (void elements are self-closed), so it can go into EPUB chapters and
+// feeds as is.
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd, html};
+
+/// Decides where each image points: a new `src`, or `None` to show the
+/// image as a link instead
+pub type ImageSource<'a> = dyn FnMut(&str) -> Option<String> + 'a;
+
+pub fn markdown_to_html(markdown: &str, images: &mut ImageSource) -> String {
+    let mut events = Vec::new();
+    // What to emit at the end of each open link or image
+    let mut closing: Vec<Vec<Event>> = Vec::new();
+    let mut parser = Parser::new_ext(markdown, Options::ENABLE_TABLES).peekable();
+
+    while let Some(event) = parser.next() {
+        match event {
+            Event::Html(raw) | Event::InlineHtml(raw) => events.push(Event::Text(raw)),
+            Event::Start(Tag::Link { dest_url, .. }) if !is_safe_url(&dest_url) => {
+                closing.push(Vec::new());
+            }
+            Event::Start(link @ Tag::Link { .. }) => {
+                events.push(Event::Start(link));
+                closing.push(vec![Event::End(TagEnd::Link)]);
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => match images(&dest_url) {
+                Some(src) if is_safe_url(&src) => {
+                    events.push(Event::Start(Tag::Image {
+                        link_type,
+                        dest_url: src.into(),
+                        title,
+                        id,
+                    }));
+                    closing.push(vec![Event::End(TagEnd::Image)]);
+                }
+                None if is_safe_url(&dest_url) => {
+                    events.push(Event::Start(Tag::Link {
+                        link_type: LinkType::Inline,
+                        dest_url,
+                        title,
+                        id,
+                    }));
+                    events.push(Event::Text("[".into()));
+                    if matches!(parser.peek(), Some(Event::End(TagEnd::Image))) {
+                        events.push(Event::Text("image".into()));
+                    }
+                    closing.push(vec![Event::Text("]".into()), Event::End(TagEnd::Link)]);
+                }
+                // Only the alt text is left
+                _ => closing.push(Vec::new()),
+            },
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                events.extend(closing.pop().unwrap_or_default());
+            }
+            event => events.push(event),
+        }
+    }
+
+    let mut output = String::new();
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+/// Whether a link or image may point at `url`: http(s), mailto, or no
+/// scheme at all (relative). Browsers ignore control characters and
+/// whitespace around and inside a scheme, so they are too.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .trim_matches(|c: char| c.is_ascii_control() || c == ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+
+    match url.find([':', '/', '?', '#']) {
+        Some(idx) if url[idx..].starts_with(':') => matches!(
+            url[..idx].to_ascii_lowercase().as_str(),
+            "http" | "https" | "mailto"
+        ),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(markdown: &str) -> String {
+        markdown_to_html(markdown, &mut |src| Some(src.to_string()))
+    }
+
+    #[test]
+    fn test_xhtml() {
+        assert_eq!(
+            render("a  \nb\n\n---\n\n![Chart](c.png)\n\n| a |\n|---|\n| 1 |\n"),
+            "<p>a<br />\nb</p>\n<hr />\n<p><img src=\"c.png\" alt=\"Chart\" /></p>\n\
+             <table><thead><tr><th>a</th></tr></thead><tbody>\n<tr><td>1</td></tr>\n</tbody></table>\n"
+        );
+    }
+
+    #[test]
+    fn test_unsafe_markup_is_dropped() {
+        assert_eq!(
+            render(
+                "<b>raw</b> [x](javascript:alert(1)) [y](jav&#x61;script:z) ![i](data:a,b)\n\n<script>\n"
+            ),
+            "<p>&lt;b&gt;raw&lt;/b&gt; x y i</p>\n&lt;script&gt;\n"
+        );
+        assert_eq!(
+            render("[a *b*](https://x.com/(y)) <https://x.com>"),
+            "<p><a href=\"https://x.com/(y)\">a <em>b</em></a> <a href=\"https://x.com\">https://x.com</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_image_sources() {
+        let mut drop = |_: &str| None;
+        assert_eq!(
+            markdown_to_html("![Chart](https://x.com/c.png) ![](d.png)", &mut drop),
+            "<p><a href=\"https://x.com/c.png\">[Chart]</a> <a href=\"d.png\">[image]</a></p>\n"
+        );
+        let mut unsafe_src = |_: &str| Some("javascript:x".to_string());
+        assert_eq!(
+            markdown_to_html("![Chart](c.png)", &mut unsafe_src),
+            "<p>Chart</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_is_safe_url() {
+        for url in [
+            "https://x.com/a:b",
+            "HTTP://x.com",
+            "mailto:me@x.com",
+            "images/1.png",
+            "../images/1.png",
+            "/a?b=c:d",
+            "#top",
+        ] {
+            assert!(is_safe_url(url), "{}", url);
+        }
+        for url in [
+            "javascript:alert(1)",
+            "\x01javascript:alert(1)",
+            " java\tscript:alert(1)",
+            "vbscript:x",
+            "data:text/html,x",
+            "file:///etc/passwd",
+        ] {
+            assert!(!is_safe_url(url), "{:?}", url);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Instapaper's CSV export: `URL,Title,Selection,Folder,Timestamp,Tags`.
+// The folder is "Unread", "Archive", "Starred" or one of the user's own
+// folders, which becomes a tag. Tags are a JSON list in newer exports.
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::{non_empty, parse_date, read_keyed, tags_from};
+use crate::export::{imported_article, reconcile::Incoming};
+
+pub fn import(path: &Path) -> Result<Vec<Incoming>> {
+    Ok(read_keyed(path)?
+        .into_iter()
+        .filter_map(|row| {
+            let field = |name: &str| non_empty(row.get(name).map(String::as_str));
+
+            let mut article = imported_article(&field("url")?);
+            article.title = field("title");
+            article.description = field("selection");
+
+            let mut labels: Vec<String> = match field("tags") {
+                Some(tags) if tags.starts_with('[') => {
+                    serde_json::from_str(&tags).unwrap_or_default()
+                }
+                Some(tags) => tags.split(',').map(str::to_string).collect(),
+                None => Vec::new(),
+            };
+            // The folder is the only state Instapaper exports
+            let state = if row.contains_key("folder") {
+                vec!["read", "archived", "starred"]
+            } else {
+                Vec::new()
+            };
+            match field("folder").as_deref() {
+                None | Some("Unread") => {}
+                Some("Archive") => {
+                    article.read = true;
+                    article.archived = true;
+                }
+                Some("Starred") => article.starred = true,
+                Some(folder) => labels.insert(0, folder.to_string()),
+            }
+            article.tags = tags_from(labels.iter().map(String::as_str));
+
+            let saved_at = field("timestamp").and_then(|t| parse_date(&t));
+            Some(Incoming::new(article, saved_at, state))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("instapaper-export.csv");
+        std::fs::write(
+            &path,
+            "URL,Title,Selection,Folder,Timestamp,Tags\n\
+             https://example.com/a,First,,Unread,1700000000,\"[\"\"Rust\"\"]\"\n\
+             https://example.com/b,Second,A quote,Archive,1600000000,[]\n\
+             https://example.com/c,Third,,Starred,1600000000,\n\
+             https://example.com/d,Fourth,,Deep Dives,1600000000,\n",
+        )
+        .unwrap();
+
+        let articles: Vec<_> = import(&path).unwrap().into_iter().map(|i| i.article).collect();
+        assert_eq!(articles.len(), 4);
+        assert_eq!(articles[0].tags, vec!["rust"]);
+        assert_eq!(articles[0].saved_at.timestamp(), 1_700_000_000);
+        assert!(articles[1].read && articles[1].archived);
+        assert_eq!(articles[1].description.as_deref(), Some("A quote"));
+        assert!(articles[2].starred && !articles[2].read);
+        assert_eq!(articles[3].tags, vec!["deep-dives"]);
+    }
+}
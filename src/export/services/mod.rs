@@ -0,0 +1,147 @@
+// Importers for other read-later services' exports
+//
+// Each service module turns its export file into unsaved articles with as
+// much of the original state as the format carries: read/archived/starred,
+// tags (folders become tags too), when it was added, notes and highlights.
+
+pub mod instapaper;
+pub mod omnivore;
+pub mod pinboard;
+pub mod pocket;
+pub mod raindrop;
+pub mod wallabag;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::db::{models::Highlight, tags};
+use crate::export::reconcile::Incoming;
+
+pub const FORMATS: [&str; 6] = [
+    "pocket",
+    "instapaper",
+    "wallabag",
+    "raindrop",
+    "omnivore",
+    "pinboard",
+];
+
+pub fn import(format: &str, path: &Path) -> Result<Vec<Incoming>> {
+    let articles = match format {
+        "pocket" => pocket::import(path)?,
+        "instapaper" => instapaper::import(path)?,
+        "wallabag" => wallabag::import(path)?,
+        "raindrop" => raindrop::import(path)?,
+        "omnivore" => omnivore::import(path)?,
+        "pinboard" => pinboard::import(path)?,
+        _ => bail!("Unknown service: {}", format),
+    };
+
+    // Non-web links (notes, app deep links) can't be stashed
+    Ok(articles
+        .into_iter()
+        .filter(|i| i.article.url.starts_with("http://") || i.article.url.starts_with("https://"))
+        .collect())
+}
+
+/// Every row of a CSV file with a header line, keyed by lowercased column
+/// name
+pub(crate) fn read_keyed(path: &Path) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .context(format!("Failed to open file: {}", path.display()))?;
+    let headers: Vec<String> = reader
+        .headers()
+        .context("Failed to parse CSV")?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.context("Failed to parse CSV")?;
+            Ok(headers
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect())
+        })
+        .collect()
+}
+
+/// Valid tags from a service's labels, without duplicates
+pub(crate) fn tags_from<'a>(labels: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in labels.into_iter().filter_map(tags::slugify) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Unix seconds, RFC 3339, or a plain `YYYY-MM-DD HH:MM:SS` in UTC
+pub(crate) fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return (seconds > 0)
+            .then(|| DateTime::from_timestamp(seconds, 0))
+            .flatten();
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| dt.and_utc())
+        })
+}
+
+pub(crate) fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// An unsaved highlight; the position is worked out on import
+fn highlight(text: &str, comment: Option<&str>) -> Option<Highlight> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| Highlight {
+        id: 0,
+        article_id: 0,
+        text: text.to_string(),
+        position: None,
+        comment: non_empty(comment),
+        created_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        let expected = DateTime::from_timestamp(1_700_000_000, 0);
+        assert_eq!(parse_date("1700000000"), expected);
+        assert_eq!(parse_date("2023-11-14T22:13:20Z"), expected);
+        assert_eq!(parse_date("2023-11-14T23:13:20+01:00"), expected);
+        assert_eq!(parse_date("2023-11-14 22:13:20"), expected);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_tags_from() {
+        assert_eq!(
+            tags_from(["Rust", "rust", "Machine Learning", ""]),
+            vec!["rust", "machine-learning"]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// Omnivore's export: `metadata_*.json` files listing each saved item with
+// its `labels`, `state` ("Archived" for archived items) and
+// `readingProgress` (0-100, fully read at 100). Highlights are included
+// when the export has them.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use super::{highlight, non_empty, parse_date, tags_from};
+use crate::export::{imported_article, reconcile::Incoming};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    url: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    state: Option<String>,
+    #[serde(default)]
+    reading_progress: f64,
+    #[serde(default)]
+    labels: Vec<String>,
+    saved_at: Option<String>,
+    #[serde(default)]
+    highlights: Vec<ItemHighlight>,
+}
+
+#[derive(Deserialize)]
+struct ItemHighlight {
+    quote: Option<String>,
+    annotation: Option<String>,
+}
+
+pub fn import(path: &Path) -> Result<Vec<Incoming>> {
+    let json =
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+    let items: Vec<Item> = serde_json::from_str(&json).context("Failed to parse Omnivore JSON")?;
+
+    Ok(items
+        .into_iter()
+        .filter(|item| item.state.as_deref() != Some("Deleted"))
+        .filter_map(|item| {
+            let mut article = imported_article(&non_empty(item.url.as_deref())?);
+            article.title = non_empty(item.title.as_deref());
+            article.description = non_empty(item.description.as_deref());
+            article.tags = tags_from(item.labels.iter().map(String::as_str));
+            article.archived = item.state.as_deref() == Some("Archived");
+            article.read = item.reading_progress >= 100.0;
+            article.highlights = item
+                .highlights
+                .iter()
+                .filter_map(|h| highlight(h.quote.as_deref()?, h.annotation.as_deref()))
+                .collect();
+            let saved_at = item.saved_at.as_deref().and_then(parse_date);
+            Some(Incoming::new(article, saved_at, vec!["read", "archived"]))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata_0_to_2.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {
+                    "id": "a", "slug": "first", "title": "First", "url": "https://example.com/a",
+                    "state": "Archived", "readingProgress": 100, "labels": ["Newsletter"],
+                    "savedAt": "2023-11-14T22:13:20.000Z",
+                    "highlights": [{"quote": "Key point", "annotation": null}]
+                },
+                {"id": "b", "title": "Second", "url": "https://example.com/b", "state": "Succeeded", "readingProgress": 40, "labels": []},
+                {"id": "c", "title": "Gone", "url": "https://example.com/c", "state": "Deleted", "labels": []}
+            ]"#,
+        )
+        .unwrap();
+
+        let articles: Vec<_> = import(&path).unwrap().into_iter().map(|i| i.article).collect();
+        assert_eq!(articles.len(), 2);
+        assert!(articles[0].read && articles[0].archived);
+        assert_eq!(articles[0].tags, vec!["newsletter"]);
+        assert_eq!(articles[0].saved_at.timestamp(), 1_700_000_000);
+        assert_eq!(articles[0].highlights[0].text, "Key point");
+        assert!(!articles[1].read && !articles[1].archived);
+    }
+}
@@ -0,0 +1,75 @@
+// Pinboard's JSON export: `href`, `description` (the title), `extended`
+// (the user's notes), `time`, `toread` ("yes" while unread) and
+// space-separated `tags`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use super::{non_empty, parse_date, tags_from};
+use crate::export::{imported_article, reconcile::Incoming};
+
+#[derive(Deserialize)]
+struct Pin {
+    href: Option<String>,
+    description: Option<String>,
+    extended: Option<String>,
+    time: Option<String>,
+    toread: Option<String>,
+    #[serde(default)]
+    tags: String,
+}
+
+pub fn import(path: &Path) -> Result<Vec<Incoming>> {
+    let json =
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+    let pins: Vec<Pin> = serde_json::from_str(&json).context("Failed to parse Pinboard JSON")?;
+
+    Ok(pins
+        .into_iter()
+        .filter_map(|pin| {
+            let mut article = imported_article(&non_empty(pin.href.as_deref())?);
+            article.title = non_empty(pin.description.as_deref());
+            article.note = non_empty(pin.extended.as_deref());
+            article.tags = tags_from(pin.tags.split_whitespace());
+            let mut state = Vec::new();
+            if let Some(toread) = pin.toread.as_deref() {
+                article.read = toread != "yes";
+                state.push("read");
+            }
+            let saved_at = pin.time.as_deref().and_then(parse_date);
+            Some(Incoming::new(article, saved_at, state))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pinboard_export.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {"href": "https://example.com/a", "description": "First", "extended": "Worth a reread",
+                 "meta": "x", "hash": "y", "time": "2023-11-14T22:13:20Z", "shared": "no",
+                 "toread": "yes", "tags": "rust Async_IO"},
+                {"href": "https://example.com/b", "description": "Second", "extended": "",
+                 "time": "2023-11-14T22:13:20Z", "toread": "no", "tags": ""}
+            ]"#,
+        )
+        .unwrap();
+
+        let articles: Vec<_> = import(&path).unwrap().into_iter().map(|i| i.article).collect();
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[0].title.as_deref(), Some("First"));
+        assert_eq!(articles[0].note.as_deref(), Some("Worth a reread"));
+        assert_eq!(articles[0].tags, vec!["rust", "async-io"]);
+        assert!(!articles[0].read);
+        assert!(articles[1].read && articles[1].note.is_none());
+    }
+}
@@ -0,0 +1,117 @@
+// Pocket exports come in two shapes: the older `ril_export.html`, with an
+// "Unread" and a "Read Archive" list, and the newer CSV with
+// `title,url,time_added,tags,status` columns and `|`-separated tags.
+// Archived items were read in Pocket, so they come in read and archived.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use super::{non_empty, parse_date, read_keyed, tags_from};
+use crate::export::{
+    html::{decode_entities, parse_tag},
+    imported_article,
+    reconcile::Incoming,
+};
+
+pub fn import(path: &Path) -> Result<Vec<Incoming>> {
+    let text =
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+
+    if text.trim_start().starts_with('<') {
+        Ok(parse_html(&text))
+    } else {
+        Ok(read_keyed(path)?
+            .into_iter()
+            .filter_map(|row| {
+                let url = non_empty(row.get("url").map(String::as_str))?;
+                let mut article = imported_article(&url);
+                article.title = non_empty(row.get("title").map(String::as_str));
+                article.tags = tags_from(row.get("tags").into_iter().flat_map(|t| t.split('|')));
+                let archived = row.get("status").is_some_and(|s| s == "archive");
+                article.read = archived;
+                article.archived = archived;
+                article.starred = row.get("favorite").is_some_and(|f| f == "1" || f == "true");
+                let mut state = vec!["read", "archived"];
+                if row.contains_key("favorite") {
+                    state.push("starred");
+                }
+                let saved_at = row.get("time_added").and_then(|t| parse_date(t));
+                Some(Incoming::new(article, saved_at, state))
+            })
+            .collect())
+    }
+}
+
+fn parse_html(html: &str) -> Vec<Incoming> {
+    let mut articles = Vec::new();
+    let mut archived = false;
+    let mut link = None;
+    let mut text = String::new();
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let (name, attrs) = parse_tag(&rest[start + 1..start + len]);
+        rest = &rest[start + len + 1..];
+
+        match name.as_str() {
+            "h1" | "a" => {
+                text.clear();
+                if name == "a" {
+                    link = Some(attrs);
+                }
+            }
+            "/h1" => archived = text.to_lowercase().contains("archive"),
+            "/a" => {
+                if let Some(attrs) = link.take()
+                    && let Some(href) = attrs.get("href")
+                {
+                    let mut article = imported_article(&decode_entities(href));
+                    article.title = non_empty(Some(&decode_entities(&text)));
+                    article.tags =
+                        tags_from(attrs.get("tags").into_iter().flat_map(|t| t.split(',')));
+                    article.read = archived;
+                    article.archived = archived;
+                    let saved_at = attrs.get("time_added").and_then(|t| parse_date(t));
+                    articles.push(Incoming::new(article, saved_at, vec!["read", "archived"]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    articles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_html() {
+        let html = r#"<!DOCTYPE html><html><body>
+<h1>Unread</h1>
+<ul>
+<li><a href="https://example.com/a?x=1&amp;y=2" time_added="1700000000" tags="Rust,cli">First &amp; best</a></li>
+</ul>
+<h1>Read Archive</h1>
+<ul>
+<li><a href="https://example.com/b" time_added="1600000000" tags="">https://example.com/b</a></li>
+</ul>
+</body></html>"#;
+
+        let articles: Vec<_> = parse_html(html).into_iter().map(|i| i.article).collect();
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[0].url, "https://example.com/a?x=1&y=2");
+        assert_eq!(articles[0].title.as_deref(), Some("First & best"));
+        assert_eq!(articles[0].tags, vec!["rust", "cli"]);
+        assert_eq!(articles[0].saved_at.timestamp(), 1_700_000_000);
+        assert!(!articles[0].read && !articles[0].archived);
+        assert!(articles[1].read && articles[1].archived);
+        assert!(articles[1].tags.is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+// Raindrop.io's CSV export:
+// `id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite`.
+// The collection (folder) becomes a tag unless it's "Unsorted". Highlights
+// are one field of `Highlight: ...` lines, each optionally followed by
+// `Note: ...`. Raindrop has no read state.
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::{highlight, non_empty, parse_date, read_keyed, tags_from};
+use crate::db::models::Highlight;
+use crate::export::{imported_article, reconcile::Incoming};
+
+pub fn import(path: &Path) -> Result<Vec<Incoming>> {
+    Ok(read_keyed(path)?
+        .into_iter()
+        .filter_map(|row| {
+            let field = |name: &str| non_empty(row.get(name).map(String::as_str));
+
+            let mut article = imported_article(&field("url")?);
+            article.title = field("title");
+            article.description = field("excerpt");
+            article.note = field("note");
+            article.starred = field("favorite").is_some_and(|f| f == "true");
+
+            let folder = field("folder").filter(|f| f != "Unsorted");
+            let tags = field("tags").unwrap_or_default();
+            article.tags = tags_from(folder.as_deref().into_iter().chain(tags.split(',')));
+
+            article.highlights = parse_highlights(&field("highlights").unwrap_or_default());
+
+            let saved_at = field("created").and_then(|c| parse_date(&c));
+            let state = if row.contains_key("favorite") {
+                vec!["starred"]
+            } else {
+                Vec::new()
+            };
+            Some(Incoming::new(article, saved_at, state))
+        })
+        .collect())
+}
+
+fn parse_highlights(field: &str) -> Vec<Highlight> {
+    let mut parsed: Vec<(String, Option<String>)> = Vec::new();
+    for line in field.lines() {
+        if let Some(text) = line.strip_prefix("Highlight:") {
+            parsed.push((text.trim().to_string(), None));
+        } else if let Some(note) = line.strip_prefix("Note:")
+            && let Some((_, comment)) = parsed.last_mut()
+        {
+            *comment = Some(note.trim().to_string());
+        } else if let Some((text, comment)) = parsed.last_mut() {
+            // Continuation of a multi-line highlight or note
+            let target = comment.as_mut().unwrap_or(text);
+            if !line.trim().is_empty() {
+                target.push('\n');
+                target.push_str(line.trim());
+            }
+        }
+    }
+
+    parsed
+        .iter()
+        .filter_map(|(text, comment)| highlight(text, comment.as_deref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raindrop.csv");
+        std::fs::write(
+            &path,
+            "id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n\
+             1,First,My note,An excerpt,https://example.com/a,Reading List,\"rust, Async IO\",2023-11-14T22:13:20.000Z,,\"Highlight:One\nNote:why\n\nHighlight:Two\nlines\",true\n\
+             2,Second,,,https://example.com/b,Unsorted,,2023-11-14T22:13:20.000Z,,,false\n",
+        )
+        .unwrap();
+
+        let articles: Vec<_> = import(&path).unwrap().into_iter().map(|i| i.article).collect();
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[0].tags, vec!["reading-list", "rust", "async-io"]);
+        assert_eq!(articles[0].note.as_deref(), Some("My note"));
+        assert_eq!(articles[0].description.as_deref(), Some("An excerpt"));
+        assert_eq!(articles[0].saved_at.timestamp(), 1_700_000_000);
+        assert!(articles[0].starred);
+
+        let highlights = &articles[0].highlights;
+        assert_eq!(highlights.len(), 2);
+        assert_eq!(highlights[0].text, "One");
+        assert_eq!(highlights[0].comment.as_deref(), Some("why"));
+        assert_eq!(highlights[1].text, "Two\nlines");
+
+        assert!(articles[1].tags.is_empty() && !articles[1].starred);
+    }
+}
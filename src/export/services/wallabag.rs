@@ -0,0 +1,115 @@
+// Wallabag's JSON export: one object per entry with `is_archived`,
+// `is_starred`, `tags`, `created_at`, the saved `content` as HTML and
+// `annotations`, which become highlights.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use super::{highlight, non_empty, parse_date, tags_from};
+use crate::export::{imported_article, reconcile::Incoming};
+use crate::fetch::content::convert_html_to_md;
+
+#[derive(Deserialize)]
+struct Entry {
+    url: Option<String>,
+    title: Option<String>,
+    #[serde(default)]
+    is_archived: Value,
+    #[serde(default)]
+    is_starred: Value,
+    /// Plain labels, or `{ "label": ... }` objects in some versions
+    #[serde(default)]
+    tags: Vec<Value>,
+    created_at: Option<String>,
+    content: Option<String>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    quote: Option<String>,
+    text: Option<String>,
+}
+
+/// Flags are 0/1 in most versions and booleans in some
+fn flag(value: &Value) -> bool {
+    value.as_bool().unwrap_or_else(|| value.as_i64() == Some(1))
+}
+
+pub fn import(path: &Path) -> Result<Vec<Incoming>> {
+    let json =
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+    let entries: Vec<Entry> =
+        serde_json::from_str(&json).context("Failed to parse wallabag JSON")?;
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let mut article = imported_article(&non_empty(entry.url.as_deref())?);
+            article.title = non_empty(entry.title.as_deref());
+            article.tags = tags_from(entry.tags.iter().filter_map(|tag| {
+                tag.as_str()
+                    .or_else(|| tag.get("label").and_then(Value::as_str))
+            }));
+            article.archived = flag(&entry.is_archived);
+            article.read = article.archived;
+            article.starred = flag(&entry.is_starred);
+            article.content_markdown = entry.content.as_deref().and_then(convert_html_to_md);
+            article.highlights = entry
+                .annotations
+                .iter()
+                .filter_map(|a| highlight(a.quote.as_deref()?, a.text.as_deref()))
+                .collect();
+            let saved_at = entry.created_at.as_deref().and_then(parse_date);
+            Some(Incoming::new(article, saved_at, vec!["read", "archived", "starred"]))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallabag.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {
+                    "id": 1, "url": "https://example.com/a", "title": "First",
+                    "is_archived": 1, "is_starred": 0, "tags": ["Rust", {"label": "CLI"}],
+                    "created_at": "2023-11-14T23:13:20+01:00",
+                    "content": "<p>Hello <b>there</b></p>",
+                    "annotations": [{"quote": "Hello", "text": "greeting"}, {"quote": "", "text": ""}]
+                },
+                {"id": 2, "url": "https://example.com/b", "is_archived": false, "is_starred": true}
+            ]"#,
+        )
+        .unwrap();
+
+        let articles: Vec<_> = import(&path).unwrap().into_iter().map(|i| i.article).collect();
+        assert_eq!(articles.len(), 2);
+        assert!(articles[0].read && articles[0].archived && !articles[0].starred);
+        assert_eq!(articles[0].tags, vec!["rust", "cli"]);
+        assert_eq!(articles[0].saved_at.timestamp(), 1_700_000_000);
+        assert!(
+            articles[0]
+                .content_markdown
+                .as_deref()
+                .unwrap()
+                .contains("Hello")
+        );
+        assert_eq!(articles[0].highlights.len(), 1);
+        assert_eq!(
+            articles[0].highlights[0].comment.as_deref(),
+            Some("greeting")
+        );
+        assert!(articles[1].starred && !articles[1].archived);
+    }
+}
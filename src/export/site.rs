@@ -0,0 +1,607 @@
+// Static site export, for browsing a stash offline or hosting it on an intranet
+//
+// The site is plain HTML with relative links, so it works from a web server
+// or straight from disk:
+//
+//   index.html            every article, newest first
+//   tags.html, sites.html  tag and site listings with counts
+//   tags/<tag>.html        articles in a tag and its descendants (`/` becomes `--`)
+//   sites/<site>.html      articles from a site
+//   articles/<id>.html     a reader page: details, note, highlights and content
+//   search-index.js        the prebuilt search index, and search.js to query it
+//
+// The index is a script rather than JSON so pages opened from disk can load
+// it; browsers won't `fetch` from `file://`. Local images are copied into
+// `images/`. A `.stash-site` marker is written at the top, and regenerating
+// into a directory only replaces what a previous export wrote there.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use super::epub::Images;
+use super::html::html_escape;
+use super::render::markdown_to_html;
+use crate::db::models::Article;
+use crate::db::tags as tag_tree;
+
+/// Marks a directory as written by this export, so it's safe to regenerate
+const MARKER: &str = ".stash-site";
+
+/// What an export writes, and removes again before regenerating
+const GENERATED_DIRS: [&str; 4] = ["articles", "tags", "sites", "images"];
+
+/// Words longer than this are mostly hashes and URLs, not worth indexing
+const MAX_TERM_LEN: usize = 32;
+
+const STYLE: &str = "* { box-sizing: border-box; }
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; line-height: 1.6; color: #333; background: #f5f5f5; margin: 0; padding: 20px; }
+.container { max-width: 860px; margin: 0 auto; background: white; padding: 30px; border-radius: 8px; box-shadow: 0 2px 4px rgba(0,0,0,0.1); }
+header { display: flex; flex-wrap: wrap; align-items: center; gap: 16px; border-bottom: 3px solid #3498db; padding-bottom: 12px; margin-bottom: 24px; }
+header .home { font-size: 1.5em; font-weight: 600; color: #2c3e50; text-decoration: none; }
+header nav { display: flex; gap: 12px; flex: 1; }
+header input { padding: 6px 10px; border: 1px solid #ccc; border-radius: 4px; min-width: 220px; }
+a { color: #2980b9; }
+h1 { color: #2c3e50; margin-top: 0; }
+.count { color: #7f8c8d; }
+.articles { list-style: none; padding: 0; }
+.articles li { padding: 12px 0; border-bottom: 1px solid #eee; }
+.articles .title { font-size: 1.1em; font-weight: 600; text-decoration: none; }
+.meta { color: #7f8c8d; font-size: 0.9em; }
+.meta a { color: inherit; }
+.description { margin: 4px 0 0; color: #555; }
+.unread .title::after { content: ' •'; color: #e74c3c; }
+.listing { columns: 2; padding-left: 20px; }
+.note, .highlights { border-left: 3px solid #3498db; padding-left: 12px; margin: 20px 0; }
+.highlights blockquote { margin: 8px 0; font-style: italic; }
+.comment { margin: 0 0 8px 16px; font-size: 0.9em; }
+.content img { max-width: 100%; }
+.content pre { background: #f8f8f8; padding: 10px; overflow-x: auto; }
+.content table { border-collapse: collapse; }
+.content th, .content td { border: 1px solid #ccc; padding: 4px 8px; }
+.missing { font-style: italic; color: #7f8c8d; }
+";
+
+const SEARCH_SCRIPT: &str = r#"(function () {
+  var input = document.getElementById("search");
+  var results = document.getElementById("results");
+  var main = document.getElementById("main");
+  var root = document.body.getAttribute("data-root");
+  var index = window.STASH_INDEX;
+  var terms = Object.keys(index.terms);
+
+  function words(text) {
+    return text.toLowerCase().match(/[\p{L}\p{N}]+/gu) || [];
+  }
+
+  // Every word is matched as a prefix, so results show up while typing
+  function matching(word) {
+    var docs = new Set();
+    terms.forEach(function (term) {
+      if (term.startsWith(word)) {
+        index.terms[term].forEach(function (doc) { docs.add(doc); });
+      }
+    });
+    return docs;
+  }
+
+  function escape(text) {
+    var div = document.createElement("div");
+    div.textContent = text;
+    return div.innerHTML;
+  }
+
+  function search() {
+    var query = words(input.value);
+    if (!query.length) {
+      results.hidden = true;
+      main.hidden = false;
+      return;
+    }
+    var found = null;
+    query.forEach(function (word) {
+      var docs = matching(word);
+      found = found === null ? docs : new Set(Array.from(found).filter(function (doc) { return docs.has(doc); }));
+    });
+    // Articles with the words in their title first, then newest first
+    var hits = Array.from(found).map(function (doc) {
+      var title = index.docs[doc][0].toLowerCase();
+      var score = query.filter(function (word) { return title.indexOf(word) !== -1; }).length;
+      return [score, doc];
+    }).sort(function (a, b) { return b[0] - a[0] || a[1] - b[1]; });
+
+    var html = "<h1>" + hits.length + " result(s)</h1><ul class=\"articles\">";
+    hits.forEach(function (hit) {
+      var doc = index.docs[hit[1]];
+      html += "<li><a class=\"title\" href=\"" + root + doc[1] + "\">" + escape(doc[0]) + "</a>" +
+        "<div class=\"meta\">" + escape(doc[2]) + "</div></li>";
+    });
+    results.innerHTML = html + "</ul>";
+    results.hidden = false;
+    main.hidden = true;
+  }
+
+  input.addEventListener("input", search);
+  if (input.value) {
+    search();
+  }
+})();
+"#;
+
+fn local_date(date: DateTime<Utc>) -> String {
+    date.with_timezone(&Local).format("%Y-%m-%d").to_string()
+}
+
+fn article_title(article: &Article) -> &str {
+    article.title.as_deref().unwrap_or(&article.url)
+}
+
+fn article_page(article: &Article) -> String {
+    format!("articles/{}.html", article.id)
+}
+
+fn tag_page(tag: &str) -> String {
+    // Tags are lowercase words joined by single hyphens, so `--` can't clash
+    format!("tags/{}.html", tag.replace(tag_tree::SEPARATOR, "--"))
+}
+
+fn site_page(site: &str) -> String {
+    let name: String = site
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("sites/{}.html", name)
+}
+
+/// A whole page; `root` leads from the page back to the top of the site
+fn page(site_title: &str, title: &str, root: &str, body: &str) -> String {
+    let full_title = if title == site_title {
+        html_escape(title)
+    } else {
+        format!("{} · {}", html_escape(title), html_escape(site_title))
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{full_title}</title>
+<link rel="stylesheet" href="{root}style.css">
+</head>
+<body data-root="{root}">
+<div class="container">
+<header>
+<a class="home" href="{root}index.html">{site_title}</a>
+<nav><a href="{root}index.html">All</a> <a href="{root}tags.html">Tags</a> <a href="{root}sites.html">Sites</a></nav>
+<input id="search" type="search" placeholder="Search" aria-label="Search">
+</header>
+<div id="results" hidden></div>
+<main id="main">
+{body}</main>
+</div>
+<script src="{root}search-index.js"></script>
+<script src="{root}search.js"></script>
+</body>
+</html>
+"#,
+        site_title = html_escape(site_title),
+    )
+}
+
+/// Site, saved date and tags, linked to their pages
+fn meta(article: &Article, root: &str) -> String {
+    let mut details = Vec::new();
+    if let Some(site) = &article.site {
+        details.push(format!(
+            "<a href=\"{}{}\">{}</a>",
+            root,
+            site_page(site),
+            html_escape(site)
+        ));
+    }
+    details.push(format!("saved {}", local_date(article.saved_at)));
+    if article.starred {
+        details.push("★ starred".to_string());
+    }
+    if !article.tags.is_empty() {
+        let tags: Vec<String> = article
+            .tags
+            .iter()
+            .map(|tag| {
+                format!(
+                    "<a href=\"{}{}\">#{}</a>",
+                    root,
+                    tag_page(tag),
+                    html_escape(tag)
+                )
+            })
+            .collect();
+        details.push(tags.join(" "));
+    }
+    format!("<div class=\"meta\">{}</div>", details.join(" · "))
+}
+
+fn article_list(articles: &[&Article], root: &str) -> String {
+    let mut html = String::from("<ul class=\"articles\">\n");
+    for article in articles {
+        html.push_str(&format!(
+            "<li{}><a class=\"title\" href=\"{}{}\">{}</a>\n{}\n",
+            if article.read {
+                ""
+            } else {
+                " class=\"unread\""
+            },
+            root,
+            article_page(article),
+            html_escape(article_title(article)),
+            meta(article, root)
+        ));
+        if let Some(description) = article.description.as_deref().filter(|d| !d.is_empty()) {
+            html.push_str(&format!(
+                "<p class=\"description\">{}</p>\n",
+                html_escape(description)
+            ));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+fn listing_page(heading: &str, articles: &[&Article]) -> String {
+    format!(
+        "<h1>{}</h1>\n<p class=\"count\">{} article(s)</p>\n{}",
+        html_escape(heading),
+        articles.len(),
+        article_list(articles, "../")
+    )
+}
+
+/// Links to every tag or site page, with how many articles each has
+fn directory(heading: &str, entries: &[(String, String, usize)]) -> String {
+    let mut html = format!("<h1>{}</h1>\n<ul class=\"listing\">\n", heading);
+    for (label, href, count) in entries {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <span class=\"count\">({})</span></li>\n",
+            href,
+            html_escape(label),
+            count
+        ));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+fn reader(article: &Article, images: &mut Images) -> String {
+    let mut body = format!(
+        "<article>\n<h1>{}</h1>\n{}\n<p><a href=\"{2}\">{2}</a></p>\n",
+        html_escape(article_title(article)),
+        meta(article, "../"),
+        html_escape(&article.url)
+    );
+
+    if let Some(description) = article.description.as_deref().filter(|d| !d.is_empty()) {
+        body.push_str(&format!(
+            "<p class=\"description\"><em>{}</em></p>\n",
+            html_escape(description)
+        ));
+    }
+    if let Some(note) = article.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        body.push_str("<div class=\"note\">\n<h2>Note</h2>\n");
+        body.push_str(&markdown_to_html(note, &mut |_| None));
+        body.push_str("</div>\n");
+    }
+    if !article.highlights.is_empty() {
+        body.push_str("<div class=\"highlights\">\n<h2>Highlights</h2>\n");
+        for highlight in &article.highlights {
+            body.push_str(&format!(
+                "<blockquote><p>{}</p></blockquote>\n",
+                html_escape(&highlight.text)
+            ));
+            if let Some(comment) = &highlight.comment {
+                body.push_str(&format!(
+                    "<p class=\"comment\">{}</p>\n",
+                    html_escape(comment)
+                ));
+            }
+        }
+        body.push_str("</div>\n");
+    }
+
+    body.push_str("<hr>\n<div class=\"content\">\n");
+    match &article.content_markdown {
+        // Pictures saved on this machine are copied in; the rest stay remote
+        Some(content) => body.push_str(&markdown_to_html(content, &mut |src| {
+            Some(match images.embed(src) {
+                Some(href) => format!("../{}", href),
+                None => src.to_string(),
+            })
+        })),
+        None => body.push_str("<p class=\"missing\">No content was saved for this article.</p>\n"),
+    }
+    body.push_str("</div>\n</article>\n");
+    body
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 2 && word.len() <= MAX_TERM_LEN)
+        .map(str::to_lowercase)
+}
+
+/// The search index as a script: each article's title, page and details, and
+/// for every word the articles it appears in. Titles, tags, sites, notes,
+/// highlights and content are all searched.
+fn search_index(articles: &[Article]) -> String {
+    let mut terms: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+    let mut docs = Vec::with_capacity(articles.len());
+
+    for (doc, article) in articles.iter().enumerate() {
+        let mut text = vec![article_title(article), &article.url];
+        text.extend(article.site.as_deref());
+        text.extend(article.tags.iter().map(String::as_str));
+        text.extend(article.description.as_deref());
+        text.extend(article.note.as_deref());
+        text.extend(article.highlights.iter().map(|h| h.text.as_str()));
+        text.extend(
+            article
+                .highlights
+                .iter()
+                .filter_map(|h| h.comment.as_deref()),
+        );
+        text.extend(article.content_markdown.as_deref());
+        for word in text.into_iter().flat_map(words) {
+            terms.entry(word).or_default().insert(doc);
+        }
+
+        let details = [article.site.clone(), Some(local_date(article.saved_at))];
+        docs.push(json!([
+            article_title(article),
+            article_page(article),
+            details
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" · "),
+        ]));
+    }
+
+    format!(
+        "window.STASH_INDEX = {};\n",
+        json!({ "docs": docs, "terms": terms })
+    )
+}
+
+/// Clear out what a previous export wrote, refusing to touch a directory
+/// that holds anything else
+fn prepare(output_dir: &Path) -> Result<()> {
+    if output_dir.exists() {
+        let mut entries = fs::read_dir(output_dir).context(format!(
+            "Failed to read directory: {}",
+            output_dir.display()
+        ))?;
+        if !output_dir.join(MARKER).exists() && entries.next().is_some() {
+            anyhow::bail!(
+                "{} isn't empty and isn't a stash site; choose a new or empty directory",
+                output_dir.display()
+            );
+        }
+        for name in GENERATED_DIRS {
+            let dir = output_dir.join(name);
+            if dir.exists() {
+                fs::remove_dir_all(&dir).context(format!("Failed to remove {}", dir.display()))?;
+            }
+        }
+    }
+
+    write(
+        output_dir,
+        MARKER,
+        "Written by `stash export --format site`\n",
+    )
+}
+
+/// Write a file of the site, creating the directories it goes in
+fn write(output_dir: &Path, name: &str, content: impl AsRef<[u8]>) -> Result<()> {
+    let path = output_dir.join(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .context(format!("Failed to create directory: {}", parent.display()))?;
+    }
+    fs::write(&path, content).context(format!("Failed to write file: {}", path.display()))
+}
+
+/// Write the site for `articles` (newest first) into `output_dir`
+pub fn export_to_site(articles: &[Article], title: &str, output_dir: &Path) -> Result<()> {
+    prepare(output_dir)?;
+
+    let all: Vec<&Article> = articles.iter().collect();
+    let unread = articles.iter().filter(|a| !a.read).count();
+    write(
+        output_dir,
+        "index.html",
+        page(
+            title,
+            title,
+            "",
+            &format!(
+                "<h1>{}</h1>\n<p class=\"count\">{} article(s), {} unread</p>\n{}",
+                html_escape(title),
+                articles.len(),
+                unread,
+                article_list(&all, "")
+            ),
+        ),
+    )?;
+
+    let mut images = Images::default();
+    for article in articles {
+        write(
+            output_dir,
+            &article_page(article),
+            page(
+                title,
+                article_title(article),
+                "../",
+                &reader(article, &mut images),
+            ),
+        )?;
+    }
+    for (href, _, data) in &images.files {
+        write(output_dir, href, data)?;
+    }
+
+    // A tag's page covers its descendants too, so parents get pages of their own
+    let mut tags: BTreeMap<&str, Vec<&Article>> = BTreeMap::new();
+    let mut sites: BTreeMap<&str, Vec<&Article>> = BTreeMap::new();
+    for article in articles {
+        let ancestors: BTreeSet<&str> = article
+            .tags
+            .iter()
+            .flat_map(|tag| tag_tree::with_ancestors(tag))
+            .collect();
+        for tag in ancestors {
+            tags.entry(tag).or_default().push(article);
+        }
+        if let Some(site) = &article.site {
+            sites.entry(site).or_default().push(article);
+        }
+    }
+
+    for (tag, tagged) in &tags {
+        let heading = format!("#{}", tag);
+        write(
+            output_dir,
+            &tag_page(tag),
+            page(title, &heading, "../", &listing_page(&heading, tagged)),
+        )?;
+    }
+    for (site, from_site) in &sites {
+        write(
+            output_dir,
+            &site_page(site),
+            page(title, site, "../", &listing_page(site, from_site)),
+        )?;
+    }
+
+    let tag_entries: Vec<_> = tags
+        .iter()
+        .map(|(tag, tagged)| (tag.to_string(), tag_page(tag), tagged.len()))
+        .collect();
+    write(
+        output_dir,
+        "tags.html",
+        page(title, "Tags", "", &directory("Tags", &tag_entries)),
+    )?;
+    // Busiest sites first
+    let mut site_entries: Vec<_> = sites
+        .iter()
+        .map(|(site, from_site)| (site.to_string(), site_page(site), from_site.len()))
+        .collect();
+    site_entries.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    write(
+        output_dir,
+        "sites.html",
+        page(title, "Sites", "", &directory("Sites", &site_entries)),
+    )?;
+
+    write(output_dir, "style.css", STYLE)?;
+    write(output_dir, "search.js", SEARCH_SCRIPT)?;
+    write(output_dir, "search-index.js", search_index(articles))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Highlight;
+    use crate::export::imported_article;
+
+    fn article(id: i64, url: &str, title: &str, tags: &[&str]) -> Article {
+        let mut article = imported_article(url);
+        article.id = id;
+        article.title = Some(title.to_string());
+        article.tags = tags.iter().map(|t| t.to_string()).collect();
+        article
+    }
+
+    #[test]
+    fn test_export_to_site_writes_pages() {
+        let mut first = article(1, "https://example.com/a", "Fish & <Chips>", &["lang/rust"]);
+        first.content_markdown = Some("## Heading\n\nSome *body* text".to_string());
+        first.note = Some("A **note**".to_string());
+        first.highlights = vec![Highlight {
+            id: 1,
+            article_id: 1,
+            text: "body text".to_string(),
+            position: None,
+            comment: Some("worth it".to_string()),
+            created_at: Utc::now(),
+        }];
+        let second = article(2, "https://www.other.org/b", "Second", &["lang"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("site");
+        export_to_site(&[first, second], "My Stash", &output).unwrap();
+
+        let index = fs::read_to_string(output.join("index.html")).unwrap();
+        assert!(index.contains("Fish &amp; &lt;Chips&gt;"));
+        assert!(index.contains("href=\"articles/2.html\""));
+
+        let reader = fs::read_to_string(output.join("articles/1.html")).unwrap();
+        assert!(reader.contains("<h2>Heading</h2>"));
+        assert!(reader.contains("<strong>note</strong>"));
+        assert!(reader.contains("<blockquote><p>body text</p></blockquote>"));
+        assert!(reader.contains("href=\"../tags/lang--rust.html\""));
+        assert!(reader.contains("href=\"../style.css\""));
+
+        // A parent tag's page includes its descendants
+        let lang = fs::read_to_string(output.join("tags/lang.html")).unwrap();
+        assert!(lang.contains("articles/1.html") && lang.contains("articles/2.html"));
+        let rust = fs::read_to_string(output.join("tags/lang--rust.html")).unwrap();
+        assert!(!rust.contains("articles/2.html"));
+        assert!(output.join("sites/other.org.html").exists());
+
+        let search = fs::read_to_string(output.join("search-index.js")).unwrap();
+        assert!(search.starts_with("window.STASH_INDEX = "));
+        assert!(search.contains("\"chips\":[0]"));
+        assert!(search.contains("\"lang\":[0,1]"));
+    }
+
+    #[test]
+    fn test_export_to_site_only_replaces_its_own_output() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "mine").unwrap();
+        assert!(export_to_site(&[], "Stash", dir.path()).is_err());
+
+        let output = dir.path().join("site");
+        export_to_site(
+            &[article(1, "https://example.com/a", "A", &[])],
+            "Stash",
+            &output,
+        )
+        .unwrap();
+        assert!(output.join("articles/1.html").exists());
+
+        // Regenerating drops pages for articles that are gone
+        export_to_site(
+            &[article(2, "https://example.com/b", "B", &[])],
+            "Stash",
+            &output,
+        )
+        .unwrap();
+        assert!(!output.join("articles/1.html").exists());
+        assert!(output.join("articles/2.html").exists());
+    }
+}
@@ -0,0 +1,452 @@
+// Articles as rows of a CSV or TSV file, for spreadsheets and other tools
+//
+// Exports write the chosen columns one article at a time. Imports read any
+// table with a URL column: columns named like stash's fields are picked up
+// as they are, and others can be mapped (`--map url=Link,tags=Labels`).
+// Rows are read one at a time, so neither direction holds the whole file.
+
+use anyhow::{Context, Result, bail};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::imported_article;
+use super::reconcile::{Incoming, STATE};
+use super::services::{non_empty, parse_date, tags_from};
+use crate::db::models::Article;
+
+pub const COLUMNS: [&str; 16] = [
+    "id",
+    "hash",
+    "url",
+    "canonical_url",
+    "title",
+    "site",
+    "description",
+    "tags",
+    "note",
+    "saved_at",
+    "last_opened_at",
+    "read",
+    "archived",
+    "starred",
+    "snoozed_until",
+    "content",
+];
+
+pub const DEFAULT_COLUMNS: [&str; 9] = [
+    "id", "title", "url", "site", "tags", "saved_at", "read", "archived", "starred",
+];
+
+/// The field delimiter of a table format (csv or tsv)
+pub fn delimiter(format: &str) -> u8 {
+    if format == "tsv" { b'\t' } else { b',' }
+}
+
+/// Known columns from a `--columns` list, or the default ones if it's empty
+pub fn parse_columns(columns: &[String]) -> Result<Vec<&'static str>> {
+    if columns.is_empty() {
+        return Ok(DEFAULT_COLUMNS.to_vec());
+    }
+    columns.iter().map(|column| field(column)).collect()
+}
+
+fn field(name: &str) -> Result<&'static str> {
+    let name = name.trim().to_lowercase();
+    match COLUMNS.iter().find(|column| **column == name) {
+        Some(column) => Ok(column),
+        None => bail!("Unknown column '{}'. Columns: {}", name, COLUMNS.join(", ")),
+    }
+}
+
+/// How tags and dates are written in cells, and which columns hold which
+/// fields on import
+#[derive(Clone, Default)]
+pub struct Options {
+    pub tag_delimiter: String,
+    /// strftime format in local time; RFC 3339 in UTC when unset
+    pub date_format: Option<String>,
+    /// Fields read from a column with another name
+    pub map: Vec<(&'static str, String)>,
+}
+
+impl Options {
+    pub fn new(tag_delimiter: &str, date_format: Option<String>, map: &[String]) -> Result<Self> {
+        if tag_delimiter.is_empty() {
+            bail!("The tag delimiter can't be empty");
+        }
+        if let Some(format) = &date_format
+            && StrftimeItems::new(format).any(|item| item == Item::Error)
+        {
+            bail!("Invalid date format: {}", format);
+        }
+
+        let map = map
+            .iter()
+            .map(|entry| {
+                let (name, column) = entry
+                    .split_once('=')
+                    .filter(|(_, column)| !column.trim().is_empty())
+                    .context(format!("Expected field=column in --map, got '{}'", entry))?;
+                let name = field(name)?;
+                if name == "hash" {
+                    bail!("The hash comes from the URL and can't be mapped");
+                }
+                Ok((name, column.trim().to_string()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            tag_delimiter: tag_delimiter.to_string(),
+            date_format,
+            map,
+        })
+    }
+
+    fn format_date(&self, date: DateTime<Utc>) -> String {
+        match &self.date_format {
+            Some(format) => date.with_timezone(&Local).format(format).to_string(),
+            None => date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+
+    /// A date in the given format, falling back to the ones services use
+    fn parse_date(&self, value: &str) -> Option<DateTime<Utc>> {
+        let value = value.trim();
+        let parsed = self.date_format.as_deref().and_then(|format| {
+            DateTime::parse_from_str(value, format)
+                .map(|date| date.with_timezone(&Utc))
+                .ok()
+                .or_else(|| {
+                    let naive =
+                        NaiveDateTime::parse_from_str(value, format)
+                            .ok()
+                            .or_else(|| {
+                                NaiveDate::parse_from_str(value, format)
+                                    .ok()
+                                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                            })?;
+                    Local
+                        .from_local_datetime(&naive)
+                        .earliest()
+                        .map(|date| date.with_timezone(&Utc))
+                })
+        });
+        parsed.or_else(|| parse_date(value))
+    }
+}
+
+fn cell(article: &Article, column: &str, options: &Options) -> String {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let date = |value: Option<DateTime<Utc>>| {
+        value
+            .map(|date| options.format_date(date))
+            .unwrap_or_default()
+    };
+
+    match column {
+        "id" => article.id.to_string(),
+        "hash" => article.hash.clone(),
+        "url" => article.url.clone(),
+        "canonical_url" => article.canonical_url.clone(),
+        "title" => text(&article.title),
+        "site" => text(&article.site),
+        "description" => text(&article.description),
+        "tags" => article.tags.join(&options.tag_delimiter),
+        "note" => text(&article.note),
+        "saved_at" => options.format_date(article.saved_at),
+        "last_opened_at" => date(article.last_opened_at),
+        "read" => article.read.to_string(),
+        "archived" => article.archived.to_string(),
+        "starred" => article.starred.to_string(),
+        "snoozed_until" => date(article.snoozed_until),
+        "content" => text(&article.content_markdown),
+        _ => String::new(),
+    }
+}
+
+pub struct TableWriter<W: Write> {
+    writer: csv::Writer<W>,
+    columns: Vec<&'static str>,
+    options: Options,
+}
+
+impl<W: Write> TableWriter<W> {
+    /// Start a table, writing its header line
+    pub fn new(
+        out: W,
+        format: &str,
+        columns: Vec<&'static str>,
+        options: Options,
+    ) -> io::Result<Self> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter(format))
+            .terminator(csv::Terminator::CRLF)
+            .from_writer(out);
+        writer.write_record(&columns)?;
+        Ok(Self {
+            writer,
+            columns,
+            options,
+        })
+    }
+
+    pub fn write(&mut self, article: &Article) -> io::Result<()> {
+        Ok(self.writer.write_record(
+            self.columns
+                .iter()
+                .map(|column| cell(article, column, &self.options)),
+        )?)
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        let mut out = self.writer.into_inner().map_err(|e| e.into_error())?;
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+/// Articles from the rows of a table, read as they're asked for
+pub struct Rows {
+    reader: csv::Reader<File>,
+    fields: Vec<(&'static str, usize)>,
+    options: Options,
+    row: usize,
+}
+
+/// Open a table and work out which column holds each field
+pub fn read(path: &Path, format: &str, options: &Options) -> Result<Rows> {
+    // Rows may be short or long, as spreadsheets leave them
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter(format))
+        .flexible(true)
+        .from_path(path)
+        .context(format!("Failed to open file: {}", path.display()))?;
+    let headers: Vec<String> = reader
+        .headers()
+        .context(format!("Failed to read {}", path.display()))?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let position = |column: &str| headers.iter().position(|h| *h == column.to_lowercase());
+
+    let mut fields = Vec::new();
+    for (name, column) in &options.map {
+        match position(column) {
+            Some(idx) => fields.push((*name, idx)),
+            None => bail!("No column named '{}' in {}", column, path.display()),
+        }
+    }
+    for name in COLUMNS {
+        if name != "hash"
+            && !fields.iter().any(|(mapped, _)| *mapped == name)
+            && let Some(idx) = position(name)
+        {
+            fields.push((name, idx));
+        }
+    }
+    if !fields.iter().any(|(name, _)| *name == "url") {
+        bail!(
+            "No url column in {}. Name the column that holds URLs with --map url=<column>",
+            path.display()
+        );
+    }
+
+    Ok(Rows {
+        reader,
+        fields,
+        options: options.clone(),
+        row: 1,
+    })
+}
+
+impl Rows {
+    fn article(&self, record: &csv::StringRecord) -> Result<Option<Incoming>> {
+        let value = |name: &str| {
+            let idx = self.fields.iter().find(|(field, _)| *field == name)?.1;
+            non_empty(record.get(idx))
+        };
+        let date = |name: &str| -> Result<Option<DateTime<Utc>>> {
+            value(name)
+                .map(|v| {
+                    self.options
+                        .parse_date(&v)
+                        .context(format!("Can't read {} '{}' as a date", name, v))
+                })
+                .transpose()
+        };
+        let flag = |name: &str| -> Result<bool> {
+            match value(name).map(|v| v.to_lowercase()).as_deref() {
+                None | Some("false" | "no" | "n" | "0") => Ok(false),
+                Some("true" | "yes" | "y" | "1" | "x") => Ok(true),
+                Some(other) => bail!("Can't read {} '{}' as true or false", name, other),
+            }
+        };
+
+        // Rows without a URL, like a spreadsheet's totals, are skipped
+        let Some(url) = value("url") else {
+            return Ok(None);
+        };
+
+        let mut article = imported_article(&url);
+        if let Some(id) = value("id") {
+            article.id = id
+                .parse()
+                .context(format!("Can't read id '{}' as a number", id))?;
+        }
+        if let Some(canonical_url) = value("canonical_url") {
+            article.canonical_url = canonical_url;
+        }
+        article.title = value("title");
+        article.site = value("site").or(article.site);
+        article.description = value("description");
+        article.note = value("note");
+        article.content_markdown = value("content");
+        if let Some(tags) = value("tags") {
+            article.tags = tags_from(tags.split(self.options.tag_delimiter.as_str()));
+        }
+        article.last_opened_at = date("last_opened_at")?;
+        article.snoozed_until = date("snoozed_until")?;
+        article.read = flag("read")?;
+        article.archived = flag("archived")?;
+        article.starred = flag("starred")?;
+        // Only the state columns the table has
+        let state = STATE
+            .into_iter()
+            .filter(|state| self.fields.iter().any(|(field, _)| field == state))
+            .collect();
+
+        Ok(Some(Incoming::new(article, date("saved_at")?, state)))
+    }
+}
+
+impl Iterator for Rows {
+    type Item = Result<Incoming>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = csv::StringRecord::new();
+        loop {
+            self.row += 1;
+            match self.reader.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    return Some(Err(
+                        anyhow::Error::new(e).context(format!("Row {}", self.row))
+                    ));
+                }
+            }
+            match self.article(&record) {
+                Ok(Some(article)) => return Some(Ok(article)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e.context(format!("Row {}", self.row)))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_table(path: &Path, format: &str, articles: &[Article], options: &Options) {
+        let columns = COLUMNS.to_vec();
+        let file = File::create(path).unwrap();
+        let mut writer = TableWriter::new(file, format, columns, options.clone()).unwrap();
+        for article in articles {
+            writer.write(article).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut article = imported_article("https://example.com/a");
+        article.id = 7;
+        article.title = Some("Commas, \"quotes\"\nand lines".to_string());
+        article.tags = vec!["rust".to_string(), "lang/async".to_string()];
+        article.saved_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        article.starred = true;
+
+        for (format, options) in [
+            ("csv", Options::new(",", None, &[]).unwrap()),
+            (
+                "tsv",
+                Options::new("; ", Some("%d/%m/%Y %H:%M:%S".to_string()), &[]).unwrap(),
+            ),
+        ] {
+            let path = dir.path().join(format!("export.{}", format));
+            write_table(&path, format, std::slice::from_ref(&article), &options);
+
+            let read: Vec<Article> = read(&path, format, &options)
+                .unwrap()
+                .map(|row| row.map(|incoming| incoming.article))
+                .collect::<Result<_>>()
+                .unwrap();
+            assert_eq!(read.len(), 1);
+            assert_eq!(read[0].id, 7);
+            assert_eq!(read[0].title, article.title);
+            assert_eq!(read[0].tags, article.tags);
+            assert_eq!(read[0].saved_at, article.saved_at);
+            assert!(read[0].starred && !read[0].read);
+        }
+    }
+
+    #[test]
+    fn test_mapped_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("links.csv");
+        std::fs::write(
+            &path,
+            "\u{feff}Link,Name,Labels,Added,Done\n\
+             https://example.com/a,First,Rust|CLI Tools,2024-03-01,yes\n\
+             ,Total,,,\n\
+             https://example.com/b,Second,,,\n",
+        )
+        .unwrap();
+
+        let map = [
+            "url=Link".to_string(),
+            "tags=labels".to_string(),
+            "saved_at=Added".to_string(),
+            "read=Done".to_string(),
+        ];
+        let options = Options::new("|", Some("%Y-%m-%d".to_string()), &map).unwrap();
+        let incoming: Vec<Incoming> = read(&path, "csv", &options)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        // Only the mapped state column counts as tracked
+        assert_eq!(incoming[0].state, vec!["read"]);
+        assert!(incoming[1].saved_at.is_none());
+        let articles: Vec<Article> = incoming.into_iter().map(|i| i.article).collect();
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[0].tags, vec!["rust", "cli-tools"]);
+        assert_eq!(
+            articles[0].saved_at.with_timezone(&Local).date_naive(),
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+        );
+        assert!(articles[0].read && !articles[1].read);
+        // "Name" isn't a field, so titles aren't read without a mapping
+        assert_eq!(articles[0].title, None);
+
+        let options = Options::new("|", None, &[]).unwrap();
+        assert!(read(&path, "csv", &options).is_err());
+        let options = Options::new(
+            "|",
+            None,
+            &["saved_at=Added".to_string(), "url=Link".to_string()],
+        )
+        .unwrap();
+        let error = read(&path, "csv", &options)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("Row 2"));
+        assert!(Options::new(",", None, &["bogus=X".to_string()]).is_err());
+        assert!(Options::new(",", Some("%Q".to_string()), &[]).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+// User templates for Markdown and HTML exports, in Handlebars:
+//
+//   {{title}}  {{article.site}}       a value; escaped in HTML templates
+//   {{{note_html}}}                   a value as it is, for HTML
+//   {{#each articles}} ... {{/each}}  repeat for each article, whose fields
+//                                     can then be used directly; {{@index}}
+//   {{#if note}} ... {{else}} ... {{/if}}, {{#unless read}} ... {{/unless}}
+//   {{join tags ", "}}                a list as text
+//
+// Block tags alone on a line don't leave a blank line behind.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use handlebars::{Handlebars, handlebars_helper, no_escape};
+use serde_json::{Value, json};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use super::render::markdown_to_html;
+use crate::db::models::Article;
+
+handlebars_helper!(join: |items: array, separator: str| {
+    items
+        .iter()
+        .map(|item| item.as_str().map_or_else(|| item.to_string(), str::to_string))
+        .collect::<Vec<_>>()
+        .join(separator)
+});
+
+/// What a template can use: the export's title, date range and count, and
+/// the articles with every exported field plus `saved_date` and `note_html`
+pub fn context(
+    articles: &[Article],
+    title: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Value> {
+    let date = |date: DateTime<Utc>| date.with_timezone(&Local).format("%Y-%m-%d").to_string();
+
+    let articles = articles
+        .iter()
+        .map(|article| {
+            let mut value = serde_json::to_value(article)?;
+            value["saved_date"] = json!(date(article.saved_at));
+            value["note_html"] = json!(
+                article
+                    .note
+                    .as_deref()
+                    .map(|note| { markdown_to_html(note, &mut |src| Some(src.to_string())) })
+            );
+            Ok(value)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(json!({
+        "title": title,
+        "count": articles.len(),
+        "generated_at": date(now),
+        "since": since.map(date),
+        "until": until.map(date),
+        "articles": articles,
+    }))
+}
+
+/// Render the template at `template_path` for the articles
+pub fn export_with_template(
+    template_path: &Path,
+    articles: &[Article],
+    title: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    escape_html: bool,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let source = fs::read_to_string(template_path).context(format!(
+        "Failed to read template: {}",
+        template_path.display()
+    ))?;
+
+    let mut handlebars = Handlebars::new();
+    if !escape_html {
+        handlebars.register_escape_fn(no_escape);
+    }
+    handlebars.register_helper("join", Box::new(join));
+    handlebars
+        .register_template_string("export", source)
+        .context(format!("Invalid template: {}", template_path.display()))?;
+
+    let context = context(articles, title, since, until, Utc::now())?;
+    handlebars
+        .render_to_write("export", &context, out)
+        .context(format!(
+            "Failed to render template: {}",
+            template_path.display()
+        ))?;
+
+    Ok(())
+}
+
+/// Whether a template is for HTML (`.html` or `.htm`) rather than Markdown
+pub fn is_html(template_path: &Path) -> bool {
+    template_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::imported_article;
+
+    fn export(source: &str, name: &str) -> Result<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        fs::write(&path, source).unwrap();
+
+        let mut article = imported_article("https://example.com/a");
+        article.title = Some("Fish & <Chips>".to_string());
+        article.tags = vec!["rust".to_string(), "cli".to_string()];
+        article.note = Some("*Good*".to_string());
+        let mut other = imported_article("https://example.com/b");
+        other.title = Some("Two".to_string());
+
+        let mut out = Vec::new();
+        export_with_template(
+            &path,
+            &[article, other],
+            "Weekly",
+            None,
+            None,
+            is_html(&path),
+            &mut out,
+        )?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_markdown_template() {
+        let source = "# {{title}}\n\
+                      {{#each articles}}\n\
+                      {{@index}}. [{{title}}]({{url}}){{#if tags}} ({{join tags \", \"}}){{/if}}\n\
+                      {{#if note}}\n\
+                      \x20 > {{note}}\n\
+                      {{/if}}\n\
+                      {{/each}}\n";
+        assert_eq!(
+            export(source, "digest.md").unwrap(),
+            "# Weekly\n\
+             0. [Fish & <Chips>](https://example.com/a) (rust, cli)\n\
+             \x20 > *Good*\n\
+             1. [Two](https://example.com/b)\n"
+        );
+    }
+
+    #[test]
+    fn test_html_template_escapes_values() {
+        let source = "{{#each articles}}{{#unless @index}}<h1>{{title}}</h1>{{{note_html}}}{{/unless}}{{/each}}";
+        assert_eq!(
+            export(source, "digest.html").unwrap(),
+            "<h1>Fish &amp; &lt;Chips&gt;</h1><p><em>Good</em></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        let error = export("{{#each articles}}", "broken.md").unwrap_err();
+        assert!(error.to_string().starts_with("Invalid template: "));
+    }
+}
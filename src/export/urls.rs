@@ -0,0 +1,51 @@
+// Plain lists of URLs, one per line, as pasted from a browser or another
+// tool. Blank lines and `#` comments are ignored.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use super::{imported_article, reconcile::Incoming};
+
+fn entries(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn is_url(line: &str) -> bool {
+    (line.starts_with("http://") || line.starts_with("https://"))
+        && !line.contains(char::is_whitespace)
+}
+
+/// Whether every entry is a web URL, and there's at least one
+pub fn is_url_list(text: &str) -> bool {
+    let mut entries = entries(text).peekable();
+    entries.peek().is_some() && entries.all(is_url)
+}
+
+/// Bare URLs carry neither a date nor any state
+pub fn import_from_urls(path: &Path) -> Result<Vec<Incoming>> {
+    let text =
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+
+    Ok(entries(&text)
+        .filter(|line| is_url(line))
+        .map(|url| Incoming::new(imported_article(url), None, Vec::new()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_url_list() {
+        assert!(is_url_list(
+            "# Reading list\nhttps://a.com/1\n\n  http://b.com/2  \n"
+        ));
+        assert!(!is_url_list("# Only a comment\n\n"));
+        assert!(!is_url_list("https://a.com/1\nSee also the other one\n"));
+        assert!(!is_url_list("https://a.com/1 A title\n"));
+    }
+}
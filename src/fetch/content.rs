@@ -5,7 +5,7 @@ pub fn convert_html_to_md(html: &str) -> Option<String> {
         return None;
     }
 
-    let markdown = parse_html(&html);
+    let markdown = parse_html(html);
     
     if markdown.trim().is_empty() {
         None
@@ -30,7 +30,7 @@ pub fn fetch_html(url: &String) -> Result<String> {
 }
 
 pub fn extract_site(url: &str) -> Option<String> {
-    let url = Url::parse(&url).ok()?;
+    let url = Url::parse(url).ok()?;
     let host = url.host_str()?;
     let host = host.strip_prefix("www.").unwrap_or(host);
 
@@ -37,11 +37,11 @@ fn extract_link(document: &Html, attr: &str, value: &str) -> Option<String> {
 }
 
 fn extract_title(document: &Html) -> Option<String> {
-    if let Some(title) = extract_meta_content(&document, "property", "og:title") {
+    if let Some(title) = extract_meta_content(document, "property", "og:title") {
         return Some(title);
     }
 
-    if let Some(title) = extract_meta_content(&document, "name", "twitter:title") {
+    if let Some(title) = extract_meta_content(document, "name", "twitter:title") {
         return Some(title);
     }
 
@@ -53,15 +53,15 @@ fn extract_title(document: &Html) -> Option<String> {
 }
 
 fn extract_description(document: &Html) -> Option<String> {
-    if let Some(description) = extract_meta_content(&document, "property", "og:description") {
+    if let Some(description) = extract_meta_content(document, "property", "og:description") {
         return Some(description);
     }
 
-    if let Some(description) = extract_meta_content(&document, "name", "twitter:description") {
+    if let Some(description) = extract_meta_content(document, "name", "twitter:description") {
         return Some(description);
     }
 
-    extract_meta_content(&document, "name", "description")
+    extract_meta_content(document, "name", "description")
 }
 
 fn extract_meta_content(document: &Html, attr: &str, value: &str) -> Option<String> {
@@ -0,0 +1,211 @@
+// Reading goals and backlog budgets from the `[goals]` config section
+//
+// `reads_per_week` is a target to reach; `max_unread` and
+// `max_unread_age_days` are budgets, and going over one prints a warning
+// from `add` and `list`. Weeks start on Monday, like in `stash stats`.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+
+use crate::{
+    config::{self, schema::Goals},
+    dates,
+    db::{models::Article, queries},
+    stats,
+};
+
+/// Where the library stands against the goals
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub unread: usize,
+    pub oldest_unread: Option<DateTime<Utc>>,
+    pub read_this_week: usize,
+    pub read_last_week: usize,
+}
+
+impl Progress {
+    pub fn load(conn: &Connection, now: DateTime<Utc>) -> Result<Self> {
+        let (unread, oldest_unread) = queries::backlog_summary(conn)?;
+        let this_week = dates::local_midnight(stats::week_start(stats::local_day(now)));
+        let last_week = this_week - Duration::weeks(1);
+
+        Ok(Self {
+            unread,
+            oldest_unread,
+            read_this_week: queries::count_read_between(
+                conn,
+                this_week,
+                now + Duration::seconds(1),
+            )?,
+            read_last_week: queries::count_read_between(conn, last_week, this_week)?,
+        })
+    }
+
+    pub fn oldest_unread_days(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.oldest_unread
+            .map(|saved_at| (now - saved_at).num_days())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalStatus {
+    pub goal: String,
+    pub current: String,
+    pub met: bool,
+    /// How far along a target is, or how full a budget is (0..=1)
+    pub fill: f64,
+}
+
+pub fn evaluate(goals: &Goals, progress: &Progress, now: DateTime<Utc>) -> Vec<GoalStatus> {
+    let mut statuses = Vec::new();
+
+    if let Some(target) = goals.reads_per_week {
+        statuses.push(GoalStatus {
+            goal: format!("Read {} per week", target),
+            current: format!(
+                "{} this week, {} last week",
+                progress.read_this_week, progress.read_last_week
+            ),
+            met: progress.read_this_week >= target as usize,
+            fill: ratio(progress.read_this_week, target as usize),
+        });
+    }
+
+    if let Some(max) = goals.max_unread {
+        statuses.push(GoalStatus {
+            goal: format!("At most {} unread", max),
+            current: format!("{} unread", progress.unread),
+            met: progress.unread <= max as usize,
+            fill: ratio(progress.unread, max as usize),
+        });
+    }
+
+    if let Some(max_days) = goals.max_unread_age_days {
+        let oldest = progress.oldest_unread_days(now).unwrap_or(0);
+        statuses.push(GoalStatus {
+            goal: format!("Nothing unread older than {} days", max_days),
+            current: match progress.oldest_unread {
+                Some(_) => format!("oldest unread is {} days old", oldest),
+                None => "nothing unread".to_string(),
+            },
+            met: oldest <= max_days as i64,
+            fill: ratio(oldest.max(0) as usize, max_days as usize),
+        });
+    }
+
+    statuses
+}
+
+fn ratio(value: usize, target: usize) -> f64 {
+    if target == 0 {
+        return if value == 0 { 0.0 } else { 1.0 };
+    }
+    (value as f64 / target as f64).min(1.0)
+}
+
+/// One-line summary of the budgets that are exceeded, if any
+pub fn budget_warning(goals: &Goals, progress: &Progress, now: DateTime<Utc>) -> Option<String> {
+    let mut over = Vec::new();
+
+    if let Some(max) = goals.max_unread
+        && progress.unread > max as usize
+    {
+        over.push(format!("{} unread (budget {})", progress.unread, max));
+    }
+
+    if let Some(max_days) = goals.max_unread_age_days
+        && let Some(oldest) = progress.oldest_unread_days(now)
+        && oldest > max_days as i64
+    {
+        over.push(format!(
+            "oldest unread is {} days old (max {})",
+            oldest, max_days
+        ));
+    }
+
+    (!over.is_empty()).then(|| format!("Over budget: {}. See `stash goals`", over.join(", ")))
+}
+
+/// Budget warning for the configured goals, checked against the database
+pub fn check_budgets(conn: &Connection) -> Result<Option<String>> {
+    let goals = config::load_config()?.goals;
+    if goals.max_unread.is_none() && goals.max_unread_age_days.is_none() {
+        return Ok(None);
+    }
+
+    let now = Utc::now();
+    Ok(budget_warning(&goals, &Progress::load(conn, now)?, now))
+}
+
+/// Unread articles saved more than `days` ago, leaving out starred ones.
+/// `articles` should come from the default listing, which already skips
+/// snoozed articles.
+pub fn bankruptcy_candidates(
+    articles: Vec<Article>,
+    days: u32,
+    now: DateTime<Utc>,
+) -> Vec<Article> {
+    // No article is older than the earliest representable date
+    let cutoff = now
+        .checked_sub_signed(Duration::days(days as i64))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    articles
+        .into_iter()
+        .filter(|a| !a.read && !a.archived && !a.starred && a.saved_at < cutoff)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(
+        unread: usize,
+        oldest_days: i64,
+        read_this_week: usize,
+        now: DateTime<Utc>,
+    ) -> Progress {
+        Progress {
+            unread,
+            oldest_unread: Some(now - Duration::days(oldest_days)),
+            read_this_week,
+            read_last_week: 0,
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let now = Utc::now();
+        let goals = Goals {
+            reads_per_week: Some(5),
+            max_unread: Some(200),
+            max_unread_age_days: Some(90),
+        };
+
+        let statuses = evaluate(&goals, &progress(150, 120, 5, now), now);
+        let met: Vec<bool> = statuses.iter().map(|s| s.met).collect();
+        assert_eq!(met, vec![true, true, false]);
+        assert_eq!(statuses[1].fill, 0.75);
+
+        assert!(evaluate(&Goals::default(), &progress(150, 120, 5, now), now).is_empty());
+    }
+
+    #[test]
+    fn test_budget_warning() {
+        let now = Utc::now();
+        let goals = Goals {
+            reads_per_week: Some(5),
+            max_unread: Some(100),
+            max_unread_age_days: None,
+        };
+
+        assert_eq!(
+            budget_warning(&goals, &progress(100, 400, 0, now), now),
+            None
+        );
+        let warning = budget_warning(&goals, &progress(101, 400, 0, now), now).unwrap();
+        assert!(warning.contains("101 unread (budget 100)"));
+        assert!(!warning.contains("oldest"));
+    }
+}
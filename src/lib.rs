@@ -2,8 +2,16 @@
 // This allows integration tests to access internal modules
 
 pub mod config;
+pub mod dates;
 pub mod db;
+pub mod dedupe;
 pub mod export;
 pub mod fetch;
+pub mod goals;
+pub mod queue;
+pub mod rules;
+pub mod similarity;
+pub mod stats;
+pub mod sync;
 pub mod ui;
 
@@ -1,8 +1,16 @@
 mod commands;
 mod config;
+mod dates;
 mod db;
+mod dedupe;
 mod export;
 mod fetch;
+mod goals;
+mod queue;
+mod rules;
+mod similarity;
+mod stats;
+mod sync;
 mod ui;
 
 use anyhow::Result;
@@ -30,6 +38,115 @@ enum ConfigAction {
     Path,
 }
 
+#[derive(Subcommand)]
+enum ViewAction {
+    Save {
+        name: String,
+
+        #[arg(required = true, num_args = 1.., allow_hyphen_values = true)]
+        query: Vec<String>,
+
+        #[arg(short, long)]
+        force: bool,
+    },
+    List,
+    #[command(alias = "rm")]
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RulesAction {
+    List,
+    Test {
+        /// Article ID or URL
+        target: String,
+    },
+    Apply {
+        #[arg(value_delimiter = ',')]
+        ids: Vec<i64>,
+
+        #[arg(short, long)]
+        all: bool,
+
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SyncAction {
+    /// Keep a note per article in an Obsidian vault
+    Obsidian {
+        vault: String,
+
+        /// Folder in the vault for the notes
+        #[arg(long, default_value = "Stash")]
+        folder: String,
+
+        /// Save edits to the `## Note` section of notes back to the articles
+        #[arg(long)]
+        pull_notes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum QueueAction {
+    Add {
+        #[arg(value_delimiter = ',', required = true)]
+        ids: Vec<i64>,
+
+        /// Put the articles at the head of the queue
+        #[arg(long, conflicts_with = "after")]
+        top: bool,
+
+        /// Put the articles right after this queued article
+        #[arg(long, value_name = "ID")]
+        after: Option<i64>,
+    },
+    /// Open the head of the queue and advance
+    Next,
+    /// Move a queued article to a new position (1 is the head)
+    Move { id: i64, position: usize },
+    Show {
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+    #[command(alias = "rm")]
+    Remove {
+        #[arg(value_delimiter = ',', required = true)]
+        ids: Vec<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HighlightAction {
+    Add {
+        id: i64,
+
+        /// Quoted text from the article
+        text: String,
+
+        #[arg(short, long)]
+        comment: Option<String>,
+    },
+    List {
+        /// Only show highlights for this article
+        id: Option<i64>,
+
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    #[command(alias = "rm")]
+    Delete {
+        /// Highlight ID (shown in brackets by `highlight list`)
+        id: i64,
+    },
+}
+
+// Parsed once per run, so the size of the largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     Add {
@@ -44,6 +161,10 @@ enum Commands {
         #[arg(long)]
         no_fetch: bool,
 
+        /// Don't apply tagging rules from config
+        #[arg(long)]
+        no_rules: bool,
+
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         extra_args: Vec<String>,
     },
@@ -65,6 +186,10 @@ enum Commands {
         #[arg(long)]
         starred: bool,
 
+        /// Show articles that are still snoozed
+        #[arg(long)]
+        snoozed: bool,
+
         #[arg(short = 'T', long, value_delimiter = ',')]
         tag: Vec<String>,
 
@@ -114,27 +239,133 @@ enum Commands {
     },
 
     Export {
-        #[arg(short, long, default_value = "json")]
-        format: String,
+        /// json, ndjson (one article per line), markdown, html, netscape
+        /// (browser bookmarks), epub, atom, rss, csv, tsv, or site (a
+        /// browsable static site). Defaults to json, or with --template to
+        /// markdown or html by its extension
+        #[arg(short, long)]
+        format: Option<String>,
 
+        /// File or directory to write, or - for stdout
         #[arg(short, long)]
         output: Option<String>,
 
-        #[arg(long, value_delimiter = ',')]
+        /// Where to write, as an alternative to --output
+        #[arg(conflicts_with = "output")]
+        path: Option<String>,
+
+        #[arg(long, value_delimiter = ',', conflicts_with_all = ["tags", "view"])]
         ids: Option<Vec<i64>>,
 
-        #[arg(short, long, value_delimiter = ',')]
+        #[arg(short, long, value_delimiter = ',', conflicts_with = "view")]
         tags: Option<Vec<String>>,
+
+        /// Export the articles a saved view matches
+        #[arg(long)]
+        view: Option<String>,
+
+        #[arg(short, long)]
+        starred: bool,
+
+        /// Only articles in these states: unread, read, archived, snoozed
+        #[arg(long, value_delimiter = ',')]
+        state: Vec<String>,
+
+        /// Only articles from these sites (subdomains included)
+        #[arg(long, value_delimiter = ',')]
+        site: Vec<String>,
+
+        /// Only articles saved since: 2026-10-01, an offset like 7d/12h/4w,
+        /// today or yesterday
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only articles saved before this date, given like --since
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only articles matching a search query, as `stash search` takes it
+        #[arg(short, long)]
+        query: Option<String>,
+
+        /// Only unread articles saved in the past week
+        #[arg(long, conflicts_with_all = ["since", "state"])]
+        digest: bool,
+
+        /// Fill in a Markdown or HTML template of your own (see README)
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Title of an epub, feed or template export
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Columns of a csv or tsv export, in order (default: id, title, url,
+        /// site, tags, saved_at, read, archived, starred). Also available:
+        /// hash, canonical_url, description, note, last_opened_at,
+        /// snoozed_until, content
+        #[arg(long, value_delimiter = ',')]
+        columns: Vec<String>,
+
+        /// What separates tags within a csv or tsv cell
+        #[arg(long, default_value = ",")]
+        tag_delimiter: String,
+
+        /// strftime format for csv or tsv dates, in local time (default: RFC 3339)
+        #[arg(long)]
+        date_format: Option<String>,
+
+        /// Fields of a json or ndjson export: names to keep (`id,url,title`)
+        /// or to drop (`-content,-highlights`)
+        #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+        fields: Vec<String>,
     },
 
     Import {
-        path: String,
+        /// Files or directories; directories are searched recursively
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Read every file as: json, ndjson, markdown, netscape (browser
+        /// bookmarks), urls (one per line), csv, tsv, or another service's export:
+        /// pocket, instapaper, wallabag, raindrop, omnivore, pinboard.
+        /// Detected per file when left out, except for services
+        #[arg(short, long)]
+        format: Option<String>,
 
+        /// Fold articles already stashed together with their imported copy:
+        /// tags are combined, notes joined, and the newer copy's state wins
         #[arg(short, long)]
         merge: bool,
 
+        /// Insert articles under their original IDs where those are free
+        #[arg(long)]
+        keep_ids: bool,
+
         #[arg(long)]
         dry_run: bool,
+
+        #[arg(long)]
+        no_rules: bool,
+
+        /// Queue imported articles without content for `stash fetch`
+        #[arg(long)]
+        fetch: bool,
+
+        /// For csv and tsv: read fields from differently named columns, as
+        /// field=column pairs (`--map url=Link,tags=Labels`). Columns named
+        /// like export columns are read without mapping
+        #[arg(long, value_delimiter = ',')]
+        map: Vec<String>,
+
+        /// What separates tags within a csv or tsv cell
+        #[arg(long, default_value = ",")]
+        tag_delimiter: String,
+
+        /// strftime format of csv or tsv dates, in local time. RFC 3339, Unix
+        /// seconds and `YYYY-MM-DD HH:MM:SS` are always understood
+        #[arg(long)]
+        date_format: Option<String>,
     },
 
     Config {
@@ -179,10 +410,16 @@ enum Commands {
     Tag {
         id: i64,
 
+        #[arg(long)]
+        suggest: bool,
+
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         tags: Vec<String>,
     },
-    Tags,
+    Tags {
+        #[arg(long)]
+        tree: bool,
+    },
     TagRename {
         old_tag: String,
         new_tag: String,
@@ -235,6 +472,110 @@ enum Commands {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         extra_args: Vec<String>,
     },
+    #[command(args_conflicts_with_subcommands = true)]
+    View {
+        #[command(subcommand)]
+        action: Option<ViewAction>,
+
+        name: Option<String>,
+
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: i64,
+
+        #[arg(short = 'f', long, default_value = "table")]
+        format: String,
+
+        #[arg(short = 'b', long)]
+        browser: bool,
+    },
+    Rules {
+        #[command(subcommand)]
+        action: RulesAction,
+    },
+    Highlight {
+        #[command(subcommand)]
+        action: HighlightAction,
+    },
+    Queue {
+        #[command(subcommand)]
+        action: QueueAction,
+    },
+    /// Reading habit statistics
+    Stats {
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Only count activity since this date (2026-01-01, 30d, 12w, ...)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Progress towards the reading goals in the config
+    Goals,
+    /// Exchange changes with your other devices through a shared folder
+    /// (Syncthing, Dropbox, a git checkout...), or sync elsewhere
+    #[command(args_conflicts_with_subcommands = true)]
+    Sync {
+        #[command(subcommand)]
+        action: Option<SyncAction>,
+
+        /// The shared folder
+        dir: Option<String>,
+    },
+    /// Archive all unread articles older than N days, after a preview
+    Bankruptcy {
+        /// Defaults to goals.max_unread_age_days
+        #[arg(short, long)]
+        days: Option<u32>,
+
+        #[arg(short, long)]
+        yes: bool,
+
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Download content for articles imported with `--fetch`, or for the
+    /// given articles
+    Fetch {
+        #[arg(value_delimiter = ',')]
+        ids: Vec<i64>,
+    },
+    /// Hide articles until a later date
+    Snooze {
+        #[arg(value_delimiter = ',', required = true)]
+        ids: Vec<i64>,
+
+        /// 2026-11-01, an offset like 3d/12h/2w, tomorrow or a weekday
+        #[arg(short, long, required_unless_present = "clear")]
+        until: Option<String>,
+
+        /// Wake the articles up now
+        #[arg(long, conflicts_with = "until")]
+        clear: bool,
+    },
+    Related {
+        id: i64,
+
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: usize,
+
+        #[arg(short = 'f', long, default_value = "table")]
+        format: String,
+
+        #[arg(short = 'b', long)]
+        browser: bool,
+    },
+    Dedupe {
+        /// Similarity (0-1) above which articles count as duplicates
+        #[arg(long, default_value_t = crate::dedupe::DEFAULT_THRESHOLD)]
+        threshold: f64,
+
+        /// Keep the suggested article of every cluster without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 fn main() -> Result<()> {
@@ -246,9 +587,10 @@ fn main() -> Result<()> {
             tags,
             title,
             no_fetch,
+            no_rules,
             extra_args,
         } => {
-            commands::add::execute(url, tags, title, no_fetch, extra_args)?;
+            commands::add::execute(url, tags, title, no_fetch, no_rules, extra_args)?;
         }
         Commands::List {
             all,
@@ -256,6 +598,7 @@ fn main() -> Result<()> {
             format,
             limit,
             starred,
+            snoozed,
             tag,
             sort,
             reverse,
@@ -275,14 +618,14 @@ fn main() -> Result<()> {
             
             // Parse +tag syntax from extra_args
             for arg in extra_args {
-                if let Some(tag_name) = arg.strip_prefix('+') {
-                    if !tag_name.is_empty() {
-                        tags.push(tag_name.to_string());
-                    }
+                if let Some(tag_name) = arg.strip_prefix('+')
+                    && !tag_name.is_empty()
+                {
+                    tags.push(tag_name.to_string());
                 }
             }
             
-            commands::list::execute(all, archived, format, limit, starred, tags, sort, reverse, browser)?;
+            commands::list::execute(all, archived, format, limit, starred, snoozed, tags, sort, reverse, browser)?;
         }
         Commands::Remove { ids, force } => {
             commands::remove::execute(&ids, force)?;
@@ -296,11 +639,72 @@ fn main() -> Result<()> {
         Commands::Note { id, text, append, clear } => {
             commands::note::execute(&id, text, append, clear)?;
         }
-        Commands::Export { format, output, ids, tags } => {
-            commands::export::execute(format, output, ids, tags)?;
+        Commands::Export {
+            format,
+            output,
+            path,
+            ids,
+            tags,
+            view,
+            starred,
+            state,
+            site,
+            since,
+            until,
+            query,
+            digest,
+            template,
+            title,
+            columns,
+            tag_delimiter,
+            date_format,
+            fields,
+        } => {
+            commands::export::execute(
+                format,
+                output.or(path),
+                ids,
+                tags,
+                view,
+                starred,
+                state,
+                site,
+                since,
+                until,
+                query,
+                digest,
+                template,
+                title,
+                columns,
+                tag_delimiter,
+                date_format,
+                fields,
+            )?;
         }
-        Commands::Import { path, merge, dry_run } => {
-            commands::import::execute(path, merge, dry_run)?;
+        Commands::Import {
+            paths,
+            format,
+            merge,
+            keep_ids,
+            dry_run,
+            no_rules,
+            fetch,
+            map,
+            tag_delimiter,
+            date_format,
+        } => {
+            commands::import::execute(
+                paths,
+                format,
+                merge,
+                keep_ids,
+                dry_run,
+                no_rules,
+                fetch,
+                map,
+                tag_delimiter,
+                date_format,
+            )?;
         }
         Commands::Config { action } => {
             match action {
@@ -343,11 +747,11 @@ fn main() -> Result<()> {
         Commands::Pick => {
             commands::pick::execute()?;
         }
-        Commands::Tag { id, tags } => {
-            commands::tag::execute(&id, &tags)?;
+        Commands::Tag { id, tags, suggest } => {
+            commands::tag::execute(&id, &tags, suggest)?;
         }
-        Commands::Tags => {
-            commands::list_tags::execute()?;
+        Commands::Tags { tree } => {
+            commands::list_tags::execute(tree)?;
         }
         Commands::TagRename { old_tag, new_tag } => {
             commands::tag_rename::execute(old_tag, new_tag)?;
@@ -385,15 +789,122 @@ fn main() -> Result<()> {
             
             // Parse +tag syntax from extra_args
             for arg in extra_args {
-                if let Some(tag_name) = arg.strip_prefix('+') {
-                    if !tag_name.is_empty() {
-                        tags.push(tag_name.to_string());
-                    }
+                if let Some(tag_name) = arg.strip_prefix('+')
+                    && !tag_name.is_empty()
+                {
+                    tags.push(tag_name.to_string());
                 }
             }
             
             commands::search::execute(query, all, archived, format, limit, starred, tags, browser)?;
         }
+        Commands::View {
+            action,
+            name,
+            limit,
+            format,
+            browser,
+        } => match (action, name) {
+            (Some(ViewAction::Save { name, query, force }), _) => {
+                commands::view::execute_save(name, query, force)?;
+            }
+            (Some(ViewAction::List), _) | (None, None) => {
+                commands::view::execute_list()?;
+            }
+            (Some(ViewAction::Delete { name }), _) => {
+                commands::view::execute_delete(name)?;
+            }
+            (None, Some(name)) => {
+                commands::view::execute_show(name, format, limit, browser)?;
+            }
+        },
+        Commands::Rules { action } => match action {
+            RulesAction::List => {
+                commands::rules::execute_list()?;
+            }
+            RulesAction::Test { target } => {
+                commands::rules::execute_test(target)?;
+            }
+            RulesAction::Apply { ids, all, dry_run } => {
+                commands::rules::execute_apply(&ids, all, dry_run)?;
+            }
+        },
+        Commands::Highlight { action } => match action {
+            HighlightAction::Add { id, text, comment } => {
+                commands::highlight::execute_add(id, text, comment)?;
+            }
+            HighlightAction::List { id, format } => {
+                commands::highlight::execute_list(id, format)?;
+            }
+            HighlightAction::Delete { id } => {
+                commands::highlight::execute_delete(id)?;
+            }
+        },
+        Commands::Queue { action } => match action {
+            QueueAction::Add { ids, top, after } => {
+                commands::queue::execute_add(&ids, top, after)?;
+            }
+            QueueAction::Next => {
+                commands::queue::execute_next()?;
+            }
+            QueueAction::Move { id, position } => {
+                commands::queue::execute_move(id, position)?;
+            }
+            QueueAction::Show { format } => {
+                commands::queue::execute_show(format)?;
+            }
+            QueueAction::Remove { ids } => {
+                commands::queue::execute_remove(&ids)?;
+            }
+        },
+        Commands::Stats { format, since } => {
+            commands::stats::execute(format, since)?;
+        }
+        Commands::Goals => {
+            commands::goals::execute()?;
+        }
+        Commands::Sync { action, dir } => match (action, dir) {
+            (
+                Some(SyncAction::Obsidian {
+                    vault,
+                    folder,
+                    pull_notes,
+                }),
+                _,
+            ) => {
+                commands::sync::execute_obsidian(vault, folder, pull_notes)?;
+            }
+            (None, Some(dir)) => {
+                commands::sync::execute(dir)?;
+            }
+            (None, None) => {
+                commands::sync::execute_status()?;
+            }
+        },
+        Commands::Bankruptcy { days, yes, dry_run } => {
+            commands::goals::execute_bankruptcy(days, yes, dry_run)?;
+        }
+        Commands::Fetch { ids } => {
+            commands::fetch::execute(&ids)?;
+        }
+        Commands::Snooze { ids, until, clear } => {
+            commands::snooze::execute(&ids, until, clear)?;
+        }
+        Commands::Related {
+            id,
+            limit,
+            format,
+            browser,
+        } => {
+            commands::related::execute(id, limit, format, browser)?;
+        }
+        Commands::Dedupe {
+            threshold,
+            yes,
+            dry_run,
+        } => {
+            commands::dedupe::execute(threshold, yes, dry_run)?;
+        }
     }
     Ok(())
 }
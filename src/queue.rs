@@ -0,0 +1,169 @@
+// Reading queue
+//
+// Articles lined up to read next, in an order the user picks instead of by
+// save date. Articles leave the queue once they're read or archived (the
+// queries that set those flags also clear `queue_position`).
+
+use anyhow::{Result, bail};
+use rusqlite::Connection;
+
+use crate::db::{models::Article, queries};
+
+/// Where to put articles in the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Bottom,
+    Top,
+    /// Right behind the article with this ID
+    After(i64),
+    /// At this 1-based position; past the end means the bottom
+    Position(usize),
+}
+
+/// New queue order with `ids` (re)inserted at `placement`. Articles already
+/// queued are moved rather than duplicated, and keep the order given in `ids`.
+pub fn place(queue: &[i64], ids: &[i64], placement: Placement) -> Result<Vec<i64>> {
+    let mut moving: Vec<i64> = Vec::new();
+    for &id in ids {
+        if !moving.contains(&id) {
+            moving.push(id);
+        }
+    }
+
+    let mut rest: Vec<i64> = queue
+        .iter()
+        .copied()
+        .filter(|id| !moving.contains(id))
+        .collect();
+
+    let index = match placement {
+        Placement::Bottom => rest.len(),
+        Placement::Top => 0,
+        Placement::Position(position) => position.saturating_sub(1).min(rest.len()),
+        Placement::After(anchor) => {
+            if moving.contains(&anchor) {
+                bail!("Can't place article {} after itself", anchor);
+            }
+            match rest.iter().position(|&id| id == anchor) {
+                Some(idx) => idx + 1,
+                None => bail!("Article {} is not in the queue", anchor),
+            }
+        }
+    };
+
+    rest.splice(index..index, moving);
+    Ok(rest)
+}
+
+fn queue_ids(conn: &Connection) -> Result<Vec<i64>> {
+    Ok(queries::list_queue(conn)?
+        .into_iter()
+        .map(|a| a.id)
+        .collect())
+}
+
+/// Add (or move) articles in the queue. Returns the queue after the change.
+pub fn enqueue(conn: &Connection, ids: &[i64], placement: Placement) -> Result<Vec<Article>> {
+    let articles = queries::find_by_ids(conn, ids)?;
+    for id in ids {
+        match articles.iter().find(|a| a.id == *id) {
+            None => bail!("Article with ID {} not found", id),
+            Some(article) if article.archived => {
+                bail!("Article {} is archived; restore it before queueing", id)
+            }
+            Some(article) if article.is_snoozed() => {
+                bail!("Article {} is snoozed; clear the snooze before queueing", id)
+            }
+            Some(_) => {}
+        }
+    }
+
+    let order = place(&queue_ids(conn)?, ids, placement)?;
+    queries::set_queue(conn, &order)?;
+    queries::list_queue(conn)
+}
+
+/// Take articles out of the queue. Returns how many were queued.
+pub fn dequeue(conn: &Connection, ids: &[i64]) -> Result<usize> {
+    let queue = queue_ids(conn)?;
+    let remaining: Vec<i64> = queue
+        .iter()
+        .copied()
+        .filter(|id| !ids.contains(id))
+        .collect();
+    queries::set_queue(conn, &remaining)?;
+    Ok(queue.len() - remaining.len())
+}
+
+/// Move a queued article `offset` places towards the bottom (negative moves
+/// it up). Returns false if the article isn't queued or is already at the end.
+pub fn shift(conn: &Connection, id: i64, offset: isize) -> Result<bool> {
+    let queue = queue_ids(conn)?;
+    let Some(current) = queue.iter().position(|&q| q == id) else {
+        return Ok(false);
+    };
+
+    let target = current.saturating_add_signed(offset).min(queue.len() - 1);
+    if target == current {
+        return Ok(false);
+    }
+
+    let order = place(&queue, &[id], Placement::Position(target + 1))?;
+    queries::set_queue(conn, &order)?;
+    Ok(true)
+}
+
+pub fn is_queued(conn: &Connection, id: i64) -> Result<bool> {
+    Ok(queue_ids(conn)?.contains(&id))
+}
+
+/// The article to read next, if anything is queued
+pub fn head(conn: &Connection) -> Result<Option<Article>> {
+    Ok(queries::list_queue(conn)?.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_place() {
+        let queue = [1, 2, 3];
+        assert_eq!(
+            place(&queue, &[4], Placement::Bottom).unwrap(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            place(&queue, &[4, 5], Placement::Top).unwrap(),
+            vec![4, 5, 1, 2, 3]
+        );
+        assert_eq!(
+            place(&queue, &[4], Placement::After(1)).unwrap(),
+            vec![1, 4, 2, 3]
+        );
+        assert_eq!(
+            place(&queue, &[4], Placement::Position(9)).unwrap(),
+            vec![1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_place_moves_queued_articles() {
+        let queue = [1, 2, 3];
+        assert_eq!(place(&queue, &[3], Placement::Top).unwrap(), vec![3, 1, 2]);
+        assert_eq!(
+            place(&queue, &[1], Placement::Position(2)).unwrap(),
+            vec![2, 1, 3]
+        );
+        assert_eq!(
+            place(&queue, &[1, 1], Placement::After(3)).unwrap(),
+            vec![2, 3, 1]
+        );
+    }
+
+    #[test]
+    fn test_place_after_invalid_anchor() {
+        assert!(place(&[1, 2], &[3], Placement::After(9)).is_err());
+        assert!(place(&[1, 2], &[2], Placement::After(2)).is_err());
+    }
+}
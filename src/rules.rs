@@ -0,0 +1,456 @@
+// Automatic tagging rules
+//
+// Rules are `[[rules]]` tables in config.toml. They are evaluated when an
+// article is added or imported, and can be backfilled with `stash rules apply`.
+// A rule never removes anything: tags are only added, starring only turns the
+// star on, and a note is only written when the article has none.
+
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use regex::Regex;
+use rusqlite::Connection;
+use url::Url;
+
+use crate::{
+    config::schema::Rule,
+    db::{models::Article, queries, tags as tag_tree},
+    fetch::http::extract_site,
+};
+
+pub const CONTENT_TYPES: [&str; 5] = ["article", "pdf", "video", "image", "link"];
+
+const VIDEO_SITES: [&str; 4] = ["youtube.com", "youtu.be", "vimeo.com", "twitch.tv"];
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "svg"];
+
+/// The parts of an article that rules look at
+pub struct Candidate<'a> {
+    pub url: &'a str,
+    pub site: Option<String>,
+    pub title: Option<&'a str>,
+    pub content: Option<&'a str>,
+}
+
+impl<'a> Candidate<'a> {
+    pub fn new(url: &'a str, title: Option<&'a str>, content: Option<&'a str>) -> Self {
+        Self {
+            url,
+            site: extract_site(url),
+            title,
+            content,
+        }
+    }
+
+    pub fn from_article(article: &'a Article) -> Self {
+        Self {
+            url: &article.url,
+            site: article.site.clone().or_else(|| extract_site(&article.url)),
+            title: article.title.as_deref(),
+            content: article.content_markdown.as_deref(),
+        }
+    }
+}
+
+/// A rule with its regex compiled and keywords normalized
+pub struct CompiledRule {
+    pub label: String,
+    rule: Rule,
+    url_regex: Option<Regex>,
+    keywords: Vec<String>,
+}
+
+/// Combined result of every rule that matched
+#[derive(Debug, Default, PartialEq)]
+pub struct RuleOutcome {
+    pub matched: Vec<String>,
+    pub tags: Vec<String>,
+    pub star: bool,
+    pub note: Option<String>,
+}
+
+/// What applying an outcome would actually change on a given article
+#[derive(Debug, Default, PartialEq)]
+pub struct RuleChanges {
+    pub add_tags: Vec<String>,
+    pub star: bool,
+    pub note: Option<String>,
+}
+
+impl RuleChanges {
+    pub fn is_empty(&self) -> bool {
+        self.add_tags.is_empty() && !self.star && self.note.is_none()
+    }
+
+    /// Short summary such as `+rust +lang/go, star, note`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.add_tags.is_empty() {
+            parts.push(
+                self.add_tags
+                    .iter()
+                    .map(|t| format!("+{}", t))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        }
+        if self.star {
+            parts.push("star".to_string());
+        }
+        if self.note.is_some() {
+            parts.push("note".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+pub fn compile(rules: &[Rule]) -> Result<Vec<CompiledRule>> {
+    rules
+        .iter()
+        .enumerate()
+        .map(|(idx, rule)| {
+            let label = rule
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("rule #{}", idx + 1));
+
+            if rule.site.is_none()
+                && rule.url_pattern.is_none()
+                && rule.keywords.is_empty()
+                && rule.content_type.is_none()
+            {
+                bail!(
+                    "Rule '{}' has no conditions. Set site, url_pattern, keywords or content_type.",
+                    label
+                );
+            }
+
+            if let Some(content_type) = &rule.content_type
+                && !CONTENT_TYPES.contains(&content_type.as_str())
+            {
+                bail!(
+                    "Rule '{}' has invalid content_type '{}'. Use one of: {}",
+                    label,
+                    content_type,
+                    CONTENT_TYPES.join(", ")
+                );
+            }
+
+            let url_regex = rule
+                .url_pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .with_context(|| format!("Rule '{}' has an invalid url_pattern", label))?;
+
+            let mut rule = rule.clone();
+            rule.tags = rule
+                .tags
+                .iter()
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect();
+            if let Some(invalid) = rule.tags.iter().find(|t| !tag_tree::is_valid(t)) {
+                bail!(
+                    "Rule '{}' has invalid tag '{}'. Tags must be lowercase alphanumeric with hyphens, nested with '/'.",
+                    label,
+                    invalid
+                );
+            }
+
+            let keywords = rule
+                .keywords
+                .iter()
+                .map(|k| k.trim().to_lowercase())
+                .filter(|k| !k.is_empty())
+                .collect();
+
+            Ok(CompiledRule {
+                label,
+                rule,
+                url_regex,
+                keywords,
+            })
+        })
+        .collect()
+}
+
+/// Compile the rules from the user's config
+pub fn load() -> Result<Vec<CompiledRule>> {
+    let config = crate::config::load_config()?;
+    compile(&config.rules)
+}
+
+pub fn evaluate(rules: &[CompiledRule], candidate: &Candidate) -> RuleOutcome {
+    let mut outcome = RuleOutcome::default();
+
+    // Lowercased once up front; content can be large
+    let haystack = format!(
+        "{}\n{}",
+        candidate.title.unwrap_or_default(),
+        candidate.content.unwrap_or_default()
+    )
+    .to_lowercase();
+    let kind = content_type(candidate.url, candidate.content.is_some());
+
+    for compiled in rules {
+        if !compiled.matches(candidate, &haystack, kind) {
+            continue;
+        }
+
+        outcome.matched.push(compiled.label.clone());
+        for tag in &compiled.rule.tags {
+            if !outcome.tags.contains(tag) {
+                outcome.tags.push(tag.clone());
+            }
+        }
+        outcome.star |= compiled.rule.star;
+        if outcome.note.is_none()
+            && let Some(template) = &compiled.rule.note
+        {
+            outcome.note = Some(render_note(template, candidate));
+        }
+    }
+
+    outcome
+}
+
+impl CompiledRule {
+    fn matches(&self, candidate: &Candidate, haystack: &str, kind: &str) -> bool {
+        if let Some(site) = &self.rule.site {
+            match &candidate.site {
+                Some(candidate_site) if site_matches(candidate_site, site) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(regex) = &self.url_regex
+            && !regex.is_match(candidate.url)
+        {
+            return false;
+        }
+
+        if !self.keywords.is_empty() && !self.keywords.iter().any(|k| contains_word(haystack, k)) {
+            return false;
+        }
+
+        if let Some(content_type) = &self.rule.content_type
+            && content_type != kind
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Changes needed to bring `article` in line with `outcome`
+pub fn changes_for(article: &Article, outcome: &RuleOutcome) -> RuleChanges {
+    RuleChanges {
+        add_tags: outcome
+            .tags
+            .iter()
+            .filter(|t| !article.tags.contains(t))
+            .cloned()
+            .collect(),
+        star: outcome.star && !article.starred,
+        note: if article.note.as_deref().unwrap_or_default().is_empty() {
+            outcome.note.clone()
+        } else {
+            None
+        },
+    }
+}
+
+pub fn apply_changes(conn: &Connection, article: &Article, changes: &RuleChanges) -> Result<()> {
+    if !changes.add_tags.is_empty() {
+        let mut tags = article.tags.clone();
+        tags.extend(changes.add_tags.iter().cloned());
+        tags.sort();
+        queries::update_tags(conn, article.id, tags)?;
+    }
+
+    if changes.star {
+        queries::set_starred_by_ids(conn, &[article.id], true)?;
+    }
+
+    if let Some(note) = &changes.note {
+        queries::update_note(conn, article.id, Some(note.clone()))?;
+    }
+
+    Ok(())
+}
+
+/// Evaluate `rules` against a freshly saved article and apply the result.
+/// Returns the labels of the rules that matched.
+pub fn apply_to_new_article(
+    conn: &Connection,
+    rules: &[CompiledRule],
+    article: &Article,
+) -> Result<Vec<String>> {
+    let outcome = evaluate(rules, &Candidate::from_article(article));
+    let changes = changes_for(article, &outcome);
+    apply_changes(conn, article, &changes)?;
+    Ok(outcome.matched)
+}
+
+/// Rough kind of a link, inferred from its URL and whether content was extracted
+pub fn content_type(url: &str, has_content: bool) -> &'static str {
+    let path = Url::parse(url)
+        .map(|u| u.path().to_lowercase())
+        .unwrap_or_default();
+    let extension = path.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+
+    if extension == "pdf" {
+        "pdf"
+    } else if IMAGE_EXTENSIONS.contains(&extension) {
+        "image"
+    } else if extract_site(url).is_some_and(|site| VIDEO_SITES.iter().any(|v| site_matches(&site, v))) {
+        "video"
+    } else if has_content {
+        "article"
+    } else {
+        "link"
+    }
+}
+
+/// Whether `word` occurs in `haystack` on word boundaries, so `ai` matches
+/// "an ai model" but not "detail"
+fn contains_word(haystack: &str, word: &str) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    haystack.match_indices(word).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + word.len()..].chars().next();
+        !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+    })
+}
+
+fn site_matches(site: &str, rule_site: &str) -> bool {
+    let site = site.to_lowercase();
+    let rule_site = rule_site.trim_start_matches("www.").to_lowercase();
+    site == rule_site || site.ends_with(&format!(".{}", rule_site))
+}
+
+fn render_note(template: &str, candidate: &Candidate) -> String {
+    template
+        .replace("{title}", candidate.title.unwrap_or_default())
+        .replace("{site}", candidate.site.as_deref().unwrap_or_default())
+        .replace("{url}", candidate.url)
+        .replace("{date}", &Local::now().format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules_from_toml(toml_str: &str) -> Vec<CompiledRule> {
+        let config: crate::config::schema::Config = toml::from_str(toml_str).unwrap();
+        compile(&config.rules).unwrap()
+    }
+
+    #[test]
+    fn test_site_and_keyword_rules() {
+        let rules = rules_from_toml(
+            r#"
+            [[rules]]
+            name = "github"
+            site = "github.com"
+            tags = ["code"]
+
+            [[rules]]
+            keywords = ["Rust", "cargo"]
+            tags = ["lang/rust", "code"]
+            star = true
+            "#,
+        );
+
+        let outcome = evaluate(
+            &rules,
+            &Candidate::new(
+                "https://gist.github.com/abc",
+                Some("Rust snippets"),
+                None,
+            ),
+        );
+        assert_eq!(outcome.matched, vec!["github", "rule #2"]);
+        assert_eq!(outcome.tags, vec!["code", "lang/rust"]);
+        assert!(outcome.star);
+
+        let outcome = evaluate(
+            &rules,
+            &Candidate::new("https://notgithub.com/x", Some("Go tips"), None),
+        );
+        assert!(outcome.matched.is_empty());
+    }
+
+    #[test]
+    fn test_all_conditions_must_match() {
+        let rules = rules_from_toml(
+            r#"
+            [[rules]]
+            url_pattern = "/papers/.*"
+            content_type = "pdf"
+            tags = ["papers"]
+            note = "From {site}: {title}"
+            "#,
+        );
+
+        let outcome = evaluate(
+            &rules,
+            &Candidate::new("https://arxiv.org/papers/1234.pdf", Some("Attention"), None),
+        );
+        assert_eq!(outcome.tags, vec!["papers"]);
+        assert_eq!(outcome.note.as_deref(), Some("From arxiv.org: Attention"));
+
+        let outcome = evaluate(
+            &rules,
+            &Candidate::new("https://arxiv.org/papers/1234", Some("Attention"), None),
+        );
+        assert!(outcome.matched.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let config: crate::config::schema::Config =
+            toml::from_str("[[rules]]\ntags = [\"x\"]").unwrap();
+        assert!(compile(&config.rules).is_err());
+
+        let config: crate::config::schema::Config =
+            toml::from_str("[[rules]]\nurl_pattern = \"(\"").unwrap();
+        assert!(compile(&config.rules).is_err());
+
+        let config: crate::config::schema::Config =
+            toml::from_str("[[rules]]\ncontent_type = \"podcast\"").unwrap();
+        assert!(compile(&config.rules).is_err());
+
+        let config: crate::config::schema::Config =
+            toml::from_str("[[rules]]\nsite = \"a.com\"\ntags = [\"rust_lang\"]").unwrap();
+        let err = compile(&config.rules).err().unwrap().to_string();
+        assert!(err.contains("invalid tag 'rust_lang'"), "{}", err);
+    }
+
+    #[test]
+    fn test_keywords_match_whole_words() {
+        let rules = rules_from_toml(
+            r#"
+            [[rules]]
+            keywords = ["ai", "machine learning"]
+            tags = ["ai"]
+            "#,
+        );
+        let matches = |title| !evaluate(&rules, &Candidate::new("https://a.com", Some(title), None)).matched.is_empty();
+
+        assert!(matches("AI in 2025"));
+        assert!(matches("Notes on (machine learning)."));
+        assert!(!matches("The devil is in the detail"));
+        assert!(!matches("Rainy days"));
+        assert!(!matches("Machine learnings"));
+    }
+
+    #[test]
+    fn test_content_type() {
+        assert_eq!(content_type("https://example.com/a.PDF", true), "pdf");
+        assert_eq!(content_type("https://www.youtube.com/watch?v=1", true), "video");
+        assert_eq!(content_type("https://example.com/cat.png", false), "image");
+        assert_eq!(content_type("https://example.com/post", true), "article");
+        assert_eq!(content_type("https://example.com/post", false), "link");
+    }
+}
@@ -0,0 +1,389 @@
+// Term statistics over the local library
+//
+// Articles are turned into TF-IDF vectors built from their title and
+// `content_markdown`. Tag suggestions compare a new article against the
+// average vector ("centroid") of every existing tag; related articles are
+// ranked by cosine similarity, with tags mixed in as extra terms.
+//
+// Term frequencies are cached in the `term_vectors` table; IDF weights depend
+// on the whole library and are recomputed on every lookup, which is cheap.
+// Everything is computed locally; nothing leaves the machine.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::db::{
+    models::{Article, ArticleTags},
+    queries, tags as tag_tree,
+};
+
+pub type TermVector = HashMap<String, f64>;
+
+/// Title words count this many times more than body words
+const TITLE_WEIGHT: f64 = 3.0;
+
+/// Long articles are cut off here; the opening is usually the most telling part
+const MAX_CONTENT_TOKENS: usize = 5000;
+
+/// Only the most frequent terms of an article are kept (and cached)
+const MAX_TERMS: usize = 300;
+
+/// Articles missing from the cache are loaded this many at a time
+const LOAD_BATCH: usize = 500;
+
+/// Weight of each tag (and its ancestors) when comparing articles
+const TAG_WEIGHT: f64 = 2.0;
+
+/// Suggestions and related articles scoring below this are noise
+const MIN_SUGGESTION_SCORE: f64 = 0.05;
+const MIN_RELATED_SCORE: f64 = 0.05;
+
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "all", "also", "and", "any", "are", "because", "been", "before",
+    "being", "between", "both", "but", "can", "com", "could", "did", "does", "doing", "down",
+    "each", "few", "for", "from", "further", "had", "has", "have", "having", "her", "here",
+    "hers", "him", "his", "how", "html", "http", "https", "into", "its", "itself", "just", "more",
+    "most", "not", "now", "off", "once", "only", "other", "our", "ours", "out", "over", "own",
+    "same", "she", "should", "some", "such", "than", "that", "the", "their", "them", "then",
+    "there", "these", "they", "this", "those", "through", "too", "under", "until", "use",
+    "used", "using", "very", "was", "were", "what", "when", "where", "which", "while", "who",
+    "whom", "why", "will", "with", "would", "www", "you", "your", "yours",
+];
+
+/// Title and content of something to compare against the library
+pub struct Document<'a> {
+    /// ID of the article itself, so it isn't compared with itself
+    pub id: Option<i64>,
+    pub title: Option<&'a str>,
+    pub content: Option<&'a str>,
+    /// Tags it already has, which are never suggested
+    pub tags: &'a [String],
+}
+
+impl<'a> Document<'a> {
+    pub fn from_article(article: &'a Article) -> Self {
+        Self {
+            id: Some(article.id),
+            title: article.title.as_deref(),
+            content: article.content_markdown.as_deref(),
+            tags: &article.tags,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagSuggestion {
+    pub tag: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RelatedArticle {
+    pub article: Article,
+    pub score: f64,
+}
+
+/// Lowercased words of at least three characters, minus stopwords and numbers
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(|word| word.to_lowercase())
+        .filter(|word| !word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+/// Sublinear term frequencies (`1 + ln(count)`) with title words boosted
+pub fn term_frequencies(title: Option<&str>, content: Option<&str>) -> TermVector {
+    let mut counts: HashMap<String, f64> = HashMap::new();
+
+    for word in tokenize(title.unwrap_or_default()) {
+        *counts.entry(word).or_default() += TITLE_WEIGHT;
+    }
+    for word in tokenize(content.unwrap_or_default()).take(MAX_CONTENT_TOKENS) {
+        *counts.entry(word).or_default() += 1.0;
+    }
+
+    let mut terms: Vec<(String, f64)> = counts
+        .into_iter()
+        .map(|(term, count)| (term, 1.0 + count.ln()))
+        .collect();
+
+    if terms.len() > MAX_TERMS {
+        terms.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        terms.truncate(MAX_TERMS);
+    }
+
+    terms.into_iter().collect()
+}
+
+/// Term frequencies for each of `ids`, read from the cache where possible.
+/// Only articles whose entry is missing (or was invalidated by an edit) are
+/// loaded; their frequencies are computed and written back.
+pub fn cached_term_frequencies(conn: &Connection, ids: &[i64]) -> Result<Vec<TermVector>> {
+    let mut cache = queries::get_term_vectors(conn)?;
+
+    let missing: Vec<i64> = ids.iter().copied().filter(|id| !cache.contains_key(id)).collect();
+    let mut computed = Vec::new();
+    for batch in missing.chunks(LOAD_BATCH) {
+        for article in queries::find_by_ids(conn, batch)? {
+            let terms =
+                term_frequencies(article.title.as_deref(), article.content_markdown.as_deref());
+            computed.push((article.id, terms));
+        }
+    }
+
+    let to_save: Vec<(i64, &TermVector)> = computed.iter().map(|(id, terms)| (*id, terms)).collect();
+    queries::save_term_vectors(conn, &to_save)?;
+    cache.extend(computed);
+
+    // IDs deleted in the meantime simply have no terms
+    Ok(ids.iter().map(|id| cache.remove(id).unwrap_or_default()).collect())
+}
+
+/// Term frequencies plus one `#tag` term per tag and ancestor
+fn with_tag_terms(frequencies: &TermVector, tags: &[String]) -> TermVector {
+    let mut terms = frequencies.clone();
+    for tag in tags {
+        for ancestor in tag_tree::with_ancestors(tag) {
+            terms.insert(format!("#{}", ancestor), TAG_WEIGHT);
+        }
+    }
+    terms
+}
+
+/// Document frequencies for a set of term-frequency vectors
+pub struct Corpus {
+    doc_count: usize,
+    document_frequency: HashMap<String, usize>,
+}
+
+impl Corpus {
+    pub fn new<'a>(documents: impl IntoIterator<Item = &'a TermVector>) -> Self {
+        let mut doc_count = 0;
+        let mut document_frequency: HashMap<String, usize> = HashMap::new();
+
+        for document in documents {
+            doc_count += 1;
+            for term in document.keys() {
+                *document_frequency.entry(term.clone()).or_default() += 1;
+            }
+        }
+
+        Self {
+            doc_count,
+            document_frequency,
+        }
+    }
+
+    /// Smoothed inverse document frequency; unseen terms get the highest weight
+    pub fn idf(&self, term: &str) -> f64 {
+        let df = self.document_frequency.get(term).copied().unwrap_or(0);
+        ((1.0 + self.doc_count as f64) / (1.0 + df as f64)).ln() + 1.0
+    }
+
+    /// Unit-length TF-IDF vector for a term-frequency vector
+    pub fn weigh(&self, frequencies: &TermVector) -> TermVector {
+        let mut vector: TermVector = frequencies
+            .iter()
+            .map(|(term, tf)| (term.clone(), tf * self.idf(term)))
+            .collect();
+        normalize(&mut vector);
+        vector
+    }
+}
+
+pub fn normalize(vector: &mut TermVector) {
+    let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        for weight in vector.values_mut() {
+            *weight /= norm;
+        }
+    }
+}
+
+/// Cosine similarity of two unit-length vectors
+pub fn cosine(a: &TermVector, b: &TermVector) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(term, weight)| large.get(term).map(|other| weight * other))
+        .sum()
+}
+
+/// Rank the library's existing tags by how well they fit `document`.
+/// `frequencies[i]` are the term frequencies of `library[i]`.
+pub fn suggest_tags_from(
+    library: &[ArticleTags],
+    frequencies: Vec<TermVector>,
+    document: &Document,
+    limit: usize,
+) -> Vec<TagSuggestion> {
+    let library: Vec<(&ArticleTags, TermVector)> = library
+        .iter()
+        .zip(frequencies)
+        .filter(|(article, _)| Some(article.id) != document.id)
+        .collect();
+
+    let corpus = Corpus::new(library.iter().map(|(_, tf)| tf));
+
+    let mut centroids: BTreeMap<&str, TermVector> = BTreeMap::new();
+    for (article, frequencies) in &library {
+        if article.tags.is_empty() {
+            continue;
+        }
+        let vector = corpus.weigh(frequencies);
+        for tag in &article.tags {
+            let centroid = centroids.entry(tag.as_str()).or_default();
+            for (term, weight) in &vector {
+                *centroid.entry(term.clone()).or_default() += weight;
+            }
+        }
+    }
+
+    let target = corpus.weigh(&term_frequencies(document.title, document.content));
+    if target.is_empty() {
+        return Vec::new();
+    }
+
+    let mut suggestions: Vec<TagSuggestion> = centroids
+        .into_iter()
+        .filter(|(tag, _)| !document.tags.iter().any(|t| t == tag))
+        .map(|(tag, mut centroid)| {
+            normalize(&mut centroid);
+            TagSuggestion {
+                tag: tag.to_string(),
+                score: cosine(&target, &centroid),
+            }
+        })
+        .filter(|suggestion| suggestion.score >= MIN_SUGGESTION_SCORE)
+        .collect();
+
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.tag.cmp(&b.tag)));
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// Suggest tags for `document` using every article in the database
+pub fn suggest_tags(
+    conn: &Connection,
+    document: &Document,
+    limit: usize,
+) -> Result<Vec<TagSuggestion>> {
+    let library = queries::list_article_tags(conn, true)?;
+    let ids: Vec<i64> = library.iter().map(|article| article.id).collect();
+    let frequencies = cached_term_frequencies(conn, &ids)?;
+    Ok(suggest_tags_from(&library, frequencies, document, limit))
+}
+
+/// Rank `library` by similarity to `target`, which is skipped if present.
+/// `frequencies[i]` are the term frequencies of `library[i]`. Returns the IDs
+/// of the best matches with their scores, ties going to the newest.
+pub fn related_articles_from(
+    library: &[ArticleTags],
+    frequencies: Vec<TermVector>,
+    target: &Article,
+    limit: usize,
+) -> Vec<(i64, f64)> {
+    let library: Vec<(&ArticleTags, TermVector)> = library
+        .iter()
+        .zip(frequencies)
+        .map(|(article, tf)| {
+            let terms = with_tag_terms(&tf, &article.tags);
+            (article, terms)
+        })
+        .collect();
+
+    let target_terms = match library.iter().find(|(article, _)| article.id == target.id) {
+        Some((_, terms)) => terms.clone(),
+        None => with_tag_terms(
+            &term_frequencies(target.title.as_deref(), target.content_markdown.as_deref()),
+            &target.tags,
+        ),
+    };
+
+    let corpus = Corpus::new(library.iter().map(|(_, terms)| terms));
+    let target_vector = corpus.weigh(&target_terms);
+    if target_vector.is_empty() {
+        return Vec::new();
+    }
+
+    let mut related: Vec<(i64, f64)> = library
+        .iter()
+        .filter(|(article, _)| article.id != target.id)
+        .map(|(article, terms)| (article.id, cosine(&target_vector, &corpus.weigh(terms))))
+        .filter(|(_, score)| *score >= MIN_RELATED_SCORE)
+        .collect();
+
+    related.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+    related.truncate(limit);
+    related
+}
+
+/// Articles most similar to `target`, ignoring archived ones. Only the
+/// matches themselves are loaded; the rest of the library is compared
+/// through its cached term frequencies.
+pub fn related_articles(
+    conn: &Connection,
+    target: &Article,
+    limit: usize,
+) -> Result<Vec<RelatedArticle>> {
+    let mut library = queries::list_article_tags(conn, false)?;
+    if target.archived {
+        library.push(ArticleTags {
+            id: target.id,
+            tags: target.tags.clone(),
+        });
+    }
+    let ids: Vec<i64> = library.iter().map(|article| article.id).collect();
+    let frequencies = cached_term_frequencies(conn, &ids)?;
+    let ranked = related_articles_from(&library, frequencies, target, limit);
+
+    let ranked_ids: Vec<i64> = ranked.iter().map(|(id, _)| *id).collect();
+    let mut articles: HashMap<i64, Article> = queries::find_by_ids(conn, &ranked_ids)?
+        .into_iter()
+        .map(|article| (article.id, article))
+        .collect();
+
+    Ok(ranked
+        .into_iter()
+        .filter_map(|(id, score)| {
+            articles
+                .remove(&id)
+                .map(|article| RelatedArticle { article, score })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens: Vec<String> =
+            tokenize("The Rust borrow-checker, in 2024: see https://www.rust-lang.org").collect();
+        assert_eq!(
+            tokens,
+            vec!["rust", "borrow", "checker", "see", "rust", "lang", "org"]
+        );
+    }
+
+    #[test]
+    fn test_idf_prefers_rare_terms() {
+        let a = term_frequencies(Some("rust tokio"), None);
+        let b = term_frequencies(Some("rust serde"), None);
+        let corpus = Corpus::new([&a, &b]);
+        assert!(corpus.idf("tokio") > corpus.idf("rust"));
+        assert!(corpus.idf("unseen") > corpus.idf("tokio"));
+    }
+
+    #[test]
+    fn test_cosine_of_unit_vectors() {
+        let a = term_frequencies(Some("rust async runtime"), None);
+        let corpus = Corpus::new([&a]);
+        let v = corpus.weigh(&a);
+        assert!((cosine(&v, &v) - 1.0).abs() < 1e-9);
+        assert_eq!(cosine(&v, &corpus.weigh(&term_frequencies(Some("gardening"), None))), 0.0);
+    }
+}
@@ -0,0 +1,261 @@
+// Reading statistics for `stash stats`
+//
+// Computed in memory from the article list: saving is dated by `saved_at`
+// and reading by `last_opened_at`, which `open` and `mark-read` set. Days
+// and weeks follow the local calendar, weeks start on Monday.
+
+use chrono::{DateTime, Datelike, Days, Duration, Local, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::db::models::Article;
+
+/// Weeks shown when no `--since` window is given
+pub const DEFAULT_WEEKS: i64 = 12;
+
+/// Sites and tags need this many saved articles to be ranked
+const MIN_RANKED: usize = 3;
+const TOP_LIMIT: usize = 5;
+
+/// Upper bounds (in days) of the backlog age buckets
+const AGE_BUCKETS: [(&str, i64); 5] = [
+    ("< 1 week", 7),
+    ("1-4 weeks", 28),
+    ("1-3 months", 91),
+    ("3-12 months", 365),
+    ("> 1 year", i64::MAX),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub since: Option<DateTime<Utc>>,
+    pub saved: usize,
+    pub read: usize,
+    /// Unread, unarchived articles
+    pub backlog: usize,
+    pub avg_days_to_read: Option<f64>,
+    pub median_days_to_read: Option<f64>,
+    pub weeks: Vec<WeekStats>,
+    pub backlog_age: Vec<AgeBucket>,
+    pub top_sites: Vec<ReadRatio>,
+    pub top_tags: Vec<ReadRatio>,
+    pub streak: Streak,
+    /// Articles read per day, for the heatmap
+    pub daily_reads: BTreeMap<NaiveDate, usize>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct WeekStats {
+    /// Monday of the week
+    pub week: NaiveDate,
+    pub saved: usize,
+    pub read: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AgeBucket {
+    pub label: &'static str,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ReadRatio {
+    pub name: String,
+    pub saved: usize,
+    pub read: usize,
+    pub ratio: f64,
+}
+
+/// Consecutive days with at least one article read
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Streak {
+    pub current: usize,
+    pub longest: usize,
+}
+
+pub fn local_day(dt: DateTime<Utc>) -> NaiveDate {
+    dt.with_timezone(&Local).date_naive()
+}
+
+/// Monday of the week `day` is in, or the first day chrono can represent
+/// for the week it starts in
+pub fn week_start(day: NaiveDate) -> NaiveDate {
+    day.checked_sub_days(Days::new(day.weekday().num_days_from_monday() as u64))
+        .unwrap_or(NaiveDate::MIN)
+}
+
+fn read_at(article: &Article) -> Option<DateTime<Utc>> {
+    article.last_opened_at.filter(|_| article.read)
+}
+
+fn days_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_seconds().max(0) as f64 / 86_400.0
+}
+
+fn ranked<'a>(groups: impl Iterator<Item = (&'a str, bool)>) -> Vec<ReadRatio> {
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (name, read) in groups {
+        let entry = counts.entry(name).or_default();
+        entry.0 += 1;
+        entry.1 += read as usize;
+    }
+
+    let mut ratios: Vec<ReadRatio> = counts
+        .into_iter()
+        .filter(|(_, (saved, _))| *saved >= MIN_RANKED)
+        .map(|(name, (saved, read))| ReadRatio {
+            name: name.to_string(),
+            saved,
+            read,
+            ratio: read as f64 / saved as f64,
+        })
+        .collect();
+
+    ratios.sort_by(|a, b| {
+        b.ratio
+            .total_cmp(&a.ratio)
+            .then(b.saved.cmp(&a.saved))
+            .then(a.name.cmp(&b.name))
+    });
+    ratios.truncate(TOP_LIMIT);
+    ratios
+}
+
+fn streak(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> Streak {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        run = match previous {
+            Some(prev) if prev.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    // Today doesn't break the streak until it's over
+    let mut day = if days.contains(&today) {
+        Some(today)
+    } else {
+        today.pred_opt()
+    };
+    let mut current = 0;
+    while let Some(current_day) = day.filter(|d| days.contains(d)) {
+        current += 1;
+        day = current_day.pred_opt();
+    }
+
+    Streak { current, longest }
+}
+
+/// Statistics over articles saved (or read) since `since`. The streak always
+/// looks at the whole history.
+pub fn compute(articles: &[Article], since: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Stats {
+    let in_window = |dt: DateTime<Utc>| since.is_none_or(|since| dt >= since);
+    let today = local_day(now);
+
+    let saved: Vec<&Article> = articles.iter().filter(|a| in_window(a.saved_at)).collect();
+    let read: Vec<(&Article, DateTime<Utc>)> = articles
+        .iter()
+        .filter_map(|a| read_at(a).map(|at| (a, at)))
+        .filter(|(_, at)| in_window(*at))
+        .collect();
+
+    // Time from saving to reading
+    let mut days_to_read: Vec<f64> = read
+        .iter()
+        .map(|(a, at)| days_between(a.saved_at, *at))
+        .collect();
+    days_to_read.sort_by(f64::total_cmp);
+    let avg_days_to_read = (!days_to_read.is_empty())
+        .then(|| days_to_read.iter().sum::<f64>() / days_to_read.len() as f64);
+    let median_days_to_read = (!days_to_read.is_empty()).then(|| {
+        let mid = days_to_read.len() / 2;
+        if days_to_read.len().is_multiple_of(2) {
+            (days_to_read[mid - 1] + days_to_read[mid]) / 2.0
+        } else {
+            days_to_read[mid]
+        }
+    });
+
+    // Saved vs read per week, including empty weeks. A window reaching back
+    // before anything was saved starts at the first activity instead.
+    let first_week = match since {
+        Some(since) => {
+            let first_activity = articles
+                .iter()
+                .flat_map(|a| [Some(a.saved_at), read_at(a)])
+                .flatten()
+                .min()
+                .map_or(today, local_day);
+            week_start(local_day(since).max(first_activity.min(today)))
+        }
+        None => week_start(today) - Duration::weeks(DEFAULT_WEEKS - 1),
+    };
+    let mut weeks: BTreeMap<NaiveDate, (usize, usize)> = BTreeMap::new();
+    let mut week = Some(first_week);
+    while let Some(monday) = week.filter(|monday| *monday <= today) {
+        weeks.insert(monday, (0, 0));
+        week = monday.checked_add_days(Days::new(7));
+    }
+    for article in &saved {
+        if let Some(entry) = weeks.get_mut(&week_start(local_day(article.saved_at))) {
+            entry.0 += 1;
+        }
+    }
+    for (_, at) in &read {
+        if let Some(entry) = weeks.get_mut(&week_start(local_day(*at))) {
+            entry.1 += 1;
+        }
+    }
+
+    // How long unread articles have been waiting
+    let backlog: Vec<&&Article> = saved.iter().filter(|a| !a.read && !a.archived).collect();
+    let mut backlog_age: Vec<AgeBucket> = AGE_BUCKETS
+        .iter()
+        .map(|(label, _)| AgeBucket { label, count: 0 })
+        .collect();
+    for article in &backlog {
+        let age = (now - article.saved_at).num_days();
+        let idx = AGE_BUCKETS
+            .iter()
+            .position(|(_, max)| age < *max)
+            .unwrap_or(AGE_BUCKETS.len() - 1);
+        backlog_age[idx].count += 1;
+    }
+
+    let mut daily_reads: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for (_, at) in &read {
+        *daily_reads.entry(local_day(*at)).or_default() += 1;
+    }
+
+    let all_read_days: BTreeSet<NaiveDate> =
+        articles.iter().filter_map(read_at).map(local_day).collect();
+
+    Stats {
+        since,
+        saved: saved.len(),
+        read: read.len(),
+        backlog: backlog.len(),
+        avg_days_to_read,
+        median_days_to_read,
+        weeks: weeks
+            .into_iter()
+            .map(|(week, (saved, read))| WeekStats { week, saved, read })
+            .collect(),
+        backlog_age,
+        top_sites: ranked(
+            saved
+                .iter()
+                .filter_map(|a| a.site.as_deref().map(|site| (site, a.read))),
+        ),
+        top_tags: ranked(
+            saved
+                .iter()
+                .flat_map(|a| a.tags.iter().map(|tag| (tag.as_str(), a.read))),
+        ),
+        streak: streak(&all_read_days, today),
+        daily_reads,
+    }
+}
@@ -0,0 +1,646 @@
+// Sync between devices through a shared folder (Syncthing, Dropbox, git...)
+//
+// Each device appends the changes made on it to its own `<device>.ndjson`
+// in the folder, so no two devices ever write the same file. Changes are
+// logged by triggers (see the V9 migration) field by field: every synced
+// column, each tag and each highlight is merged on its own, and the latest
+// change to it wins. Tags added on different devices therefore add up, and
+// deleting an article leaves a tombstone instead of the other devices
+// syncing it back. The queue and the fetch queue stay with each device.
+//
+// The first sync logs the whole library as it stands, dated at zero so any
+// real change wins over it. Devices that started out as copies of the same
+// database merge cleanly: tags and highlights are combined, and a field set
+// on one copy wins over a default on the other.
+
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use rusqlite::{Connection, OptionalExtension, params, types::Value as SqlValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::db::{models::NewArticle, queries};
+
+const LOG_EXTENSION: &str = "ndjson";
+
+/// Columns synced as they are, by the name used in the logs
+const COLUMNS: [&str; 12] = [
+    "url",
+    "title",
+    "description",
+    "favicon_url",
+    "content_markdown",
+    "saved_at",
+    "read",
+    "archived",
+    "starred",
+    "note",
+    "last_opened_at",
+    "snoozed_until",
+];
+
+/// The library as it stands, logged by the first sync. Mirrors the insert
+/// triggers, with time zero.
+const BASELINE: &str = "
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT hash, 'article', json_object(
+    'url', url,
+    'canonical_url', canonical_url,
+    'title', title,
+    'site', site,
+    'description', description,
+    'favicon_url', favicon_url,
+    'content_markdown', content_markdown,
+    'saved_at', saved_at
+), 0 FROM articles;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT hash, 'read', 'true', 0 FROM articles WHERE read;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT hash, 'archived', 'true', 0 FROM articles WHERE archived;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT hash, 'starred', 'true', 0 FROM articles WHERE starred;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT hash, 'note', json_quote(note), 0 FROM articles WHERE note IS NOT NULL;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT hash, 'last_opened_at', json_quote(last_opened_at), 0 FROM articles
+WHERE last_opened_at IS NOT NULL;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT hash, 'snoozed_until', json_quote(snoozed_until), 0 FROM articles
+WHERE snoozed_until IS NOT NULL;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT articles.hash, 'tag:' || tag.value, 'true', 0 FROM articles, json_each(articles.tags) AS tag;
+INSERT INTO sync_changes (hash, field, value, changed_at)
+SELECT articles.hash, 'highlight:' || highlights.created_at || ':' || highlights.text, json_object(
+    'text', highlights.text,
+    'position', highlights.position,
+    'comment', highlights.comment,
+    'created_at', highlights.created_at
+), 0 FROM highlights JOIN articles ON articles.id = highlights.article_id;
+";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    /// This device, which names its log in the folder
+    pub device: String,
+    /// Changes made here, written to this device's log
+    pub written: usize,
+    /// Changes from other devices merged into the library
+    pub applied: usize,
+    /// Other devices with a log in the folder
+    pub devices: usize,
+    /// Changes to articles that haven't arrived from their device yet
+    pub pending: usize,
+    /// Log lines that couldn't be read, and were skipped
+    pub malformed: usize,
+}
+
+/// A change as it's written to a device's log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Change {
+    /// Position in the device's log
+    seq: i64,
+    /// Unix milliseconds
+    at: i64,
+    hash: String,
+    field: String,
+    value: Value,
+}
+
+/// Orders changes to the same field: by time, then device, then log order,
+/// so every device picks the same winner
+type Stamp = (i64, String, i64);
+
+/// What merging a change from another device did
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Applied,
+    /// A later change to the field (or to the whole article) is already here
+    Outdated,
+    /// The article hasn't been added here yet
+    Pending,
+}
+
+/// The details an article is added with
+#[derive(Deserialize)]
+struct Details {
+    url: String,
+    canonical_url: String,
+    title: Option<String>,
+    site: Option<String>,
+    description: Option<String>,
+    favicon_url: Option<String>,
+    content_markdown: Option<String>,
+    saved_at: i64,
+}
+
+#[derive(Deserialize)]
+struct SyncedHighlight {
+    text: String,
+    position: Option<i64>,
+    comment: Option<String>,
+    created_at: i64,
+}
+
+/// This database's device, None until the first sync
+pub fn device_id(conn: &Connection) -> Result<Option<String>> {
+    conn.query_row("SELECT id FROM sync_device", [], |row| row.get(0))
+        .optional()
+        .context("Failed to read the sync device")
+}
+
+/// Changes made here since the last sync
+pub fn unsent_changes(conn: &Connection) -> Result<usize> {
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM sync_changes", [], |row| row.get(0))
+        .context("Failed to count changes to sync")?;
+    Ok(count as usize)
+}
+
+/// A new device ID: the host name, to tell the logs apart, and a random part
+fn new_device_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .unwrap_or_default();
+    let host: String = host
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+
+    let seed = format!("{:?} {} {}", SystemTime::now(), std::process::id(), host);
+    let random: String = blake3::hash(seed.as_bytes())
+        .to_hex()
+        .chars()
+        .take(8)
+        .collect();
+
+    match host.trim_matches('-') {
+        "" => random,
+        host => format!("{}-{}", host, random),
+    }
+}
+
+/// Start logging changes, beginning with the library as it stands
+fn register(conn: &Connection) -> Result<String> {
+    let id = new_device_id();
+    conn.execute("INSERT INTO sync_device (id) VALUES (?1)", params![id])?;
+    conn.execute_batch(BASELINE)
+        .context("Failed to log the library for syncing")?;
+    Ok(id)
+}
+
+fn stamp(change: &Change, device: &str) -> Stamp {
+    (change.at, device.to_string(), change.seq)
+}
+
+fn clock(conn: &Connection, hash: &str, field: &str) -> Result<Option<Stamp>> {
+    conn.query_row(
+        "SELECT changed_at, device, seq FROM sync_clock WHERE hash = ?1 AND field = ?2",
+        params![hash, field],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+    .optional()
+    .context("Failed to read the sync clock")
+}
+
+fn set_clock(conn: &Connection, change: &Change, device: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_clock (hash, field, changed_at, device, seq)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![change.hash, change.field, change.at, device, change.seq],
+    )?;
+    Ok(())
+}
+
+/// The latest change a field holds: its own, or the article being added or
+/// deleted since
+fn latest(conn: &Connection, hash: &str, field: &str) -> Result<Option<Stamp>> {
+    let article = clock(conn, hash, "article")?;
+    if field == "article" {
+        return Ok(article);
+    }
+    Ok(clock(conn, hash, field)?.max(article))
+}
+
+fn log_path(dir: &Path, device: &str) -> std::path::PathBuf {
+    dir.join(format!("{}.{}", device, LOG_EXTENSION))
+}
+
+fn read_log(path: &Path) -> Result<(Vec<Change>, usize)> {
+    let text = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    Ok(parse_log(&text))
+}
+
+/// The complete lines of a log, and how many couldn't be read. A last line
+/// without its newline may still be on its way from another device, so
+/// it's left for next time.
+fn parse_log(text: &str) -> (Vec<Change>, usize) {
+    let mut changes = Vec::new();
+    let mut malformed = 0;
+    for line in text
+        .split_inclusive('\n')
+        .filter(|line| line.ends_with('\n'))
+    {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(change) => changes.push(change),
+            Err(_) => malformed += 1,
+        }
+    }
+    (changes, malformed)
+}
+
+/// Write the changes made here to this device's log. Each is dated after
+/// the change it replaces, so a device whose clock is behind still has its
+/// latest edits win.
+fn write_local(conn: &Connection, device: &str, path: &Path) -> Result<usize> {
+    let mut stmt =
+        conn.prepare("SELECT hash, field, value, changed_at FROM sync_changes ORDER BY seq")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read changes to sync")?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    // Carry on from the log rather than the database, so a sync that failed
+    // after writing can't reuse positions other devices have already seen
+    let existing = if path.exists() {
+        fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let (logged, _) = parse_log(&existing);
+    let last = logged.iter().map(|c| c.seq).max().unwrap_or(0);
+
+    let mut lines = String::new();
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        lines.push('\n');
+    }
+    for (seq, (hash, field, value, changed_at)) in (last + 1..).zip(rows) {
+        let at = match latest(conn, &hash, &field)? {
+            Some((at, _, _)) if at >= changed_at => at + 1,
+            _ => changed_at,
+        };
+        let change = Change {
+            seq,
+            at,
+            value: serde_json::from_str(&value)
+                .context(format!("Malformed change to {} of {}", field, hash))?,
+            hash,
+            field,
+        };
+        set_clock(conn, &change, device)?;
+        lines.push_str(&serde_json::to_string(&change)?);
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Failed to open {}", path.display()))?;
+    file.write_all(lines.as_bytes())
+        .and_then(|_| file.sync_all())
+        .context(format!("Failed to write {}", path.display()))?;
+
+    let written = conn.execute("DELETE FROM sync_changes", [])?;
+    Ok(written)
+}
+
+fn article_id(conn: &Connection, hash: &str) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM articles WHERE hash = ?1",
+        params![hash],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to look up article")
+}
+
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => n.as_i64().map(SqlValue::Integer).unwrap_or(SqlValue::Null),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn add_article(conn: &Connection, hash: &str, details: &Value) -> Result<()> {
+    let details: Details = serde_json::from_value(details.clone())
+        .context(format!("Malformed article {} in the sync folder", hash))?;
+    queries::insert_article(
+        conn,
+        NewArticle {
+            id: None,
+            hash: hash.to_string(),
+            url: details.url,
+            canonical_url: details.canonical_url,
+            title: details.title,
+            site: details.site,
+            description: details.description,
+            favicon_url: details.favicon_url,
+            content_markdown: details.content_markdown,
+            tags: Vec::new(),
+            saved_at: DateTime::from_timestamp(details.saved_at, 0),
+        },
+    )
+    .context(format!("Failed to add {} from the sync folder", hash))?;
+    Ok(())
+}
+
+fn set_tag(conn: &Connection, id: i64, tag: &str, present: bool) -> Result<()> {
+    let tags_json: String = conn.query_row(
+        "SELECT tags FROM articles WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )?;
+    let mut tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    let had = tags.iter().any(|t| t == tag);
+    match (present, had) {
+        (true, false) => tags.push(tag.to_string()),
+        (false, true) => tags.retain(|t| t != tag),
+        _ => return Ok(()),
+    }
+    conn.execute(
+        "UPDATE articles SET tags = ?1 WHERE id = ?2",
+        params![serde_json::to_string(&tags)?, id],
+    )?;
+    Ok(())
+}
+
+fn set_highlight(conn: &Connection, id: i64, key: &str, value: &Value) -> Result<()> {
+    if value.is_null() {
+        // The key is `<created_at>:<text>`
+        let Some((created_at, text)) = key.split_once(':') else {
+            return Ok(());
+        };
+        conn.execute(
+            "DELETE FROM highlights WHERE article_id = ?1 AND created_at = ?2 AND text = ?3",
+            params![id, created_at.parse::<i64>().unwrap_or_default(), text],
+        )?;
+        return Ok(());
+    }
+
+    let highlight: SyncedHighlight =
+        serde_json::from_value(value.clone()).context("Malformed highlight in the sync folder")?;
+    let updated = conn.execute(
+        "UPDATE highlights SET position = ?1, comment = ?2
+         WHERE article_id = ?3 AND created_at = ?4 AND text = ?5",
+        params![
+            highlight.position,
+            highlight.comment,
+            id,
+            highlight.created_at,
+            highlight.text
+        ],
+    )?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO highlights (article_id, text, position, comment, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                id,
+                highlight.text,
+                highlight.position,
+                highlight.comment,
+                highlight.created_at
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Merge a change from another device, if it's later than what's here
+fn merge(conn: &Connection, change: &Change, device: &str) -> Result<Outcome> {
+    let stamp = stamp(change, device);
+    let id = article_id(conn, &change.hash)?;
+    let article = clock(conn, &change.hash, "article")?;
+
+    if change.field == "article" {
+        if article.is_some_and(|current| current >= stamp) {
+            return Ok(Outcome::Outdated);
+        }
+        match (&change.value, id) {
+            (Value::Null, Some(id)) => {
+                queries::delete_by_ids(conn, &[id])?;
+            }
+            (Value::Null, None) => {}
+            (details, None) => add_article(conn, &change.hash, details)?,
+            // Added on both devices
+            (_, Some(_)) => {}
+        }
+        set_clock(conn, change, device)?;
+        return Ok(Outcome::Applied);
+    }
+
+    // Changes from before the article was last added or deleted
+    if article.as_ref().is_some_and(|current| *current > stamp) {
+        return Ok(Outcome::Outdated);
+    }
+    let Some(id) = id else {
+        // Known but gone means deleted here, and deletes win
+        return Ok(if article.is_some() {
+            Outcome::Outdated
+        } else {
+            Outcome::Pending
+        });
+    };
+    if clock(conn, &change.hash, &change.field)?.is_some_and(|current| current >= stamp) {
+        return Ok(Outcome::Outdated);
+    }
+
+    if let Some(tag) = change.field.strip_prefix("tag:") {
+        set_tag(conn, id, tag, change.value == Value::Bool(true))?;
+    } else if let Some(key) = change.field.strip_prefix("highlight:") {
+        set_highlight(conn, id, key, &change.value)?;
+    } else if COLUMNS.contains(&change.field.as_str()) {
+        conn.execute(
+            &format!("UPDATE articles SET {} = ?1 WHERE id = ?2", change.field),
+            params![sql_value(&change.value), id],
+        )?;
+    } else {
+        // A field from a newer version of stash
+        return Ok(Outcome::Outdated);
+    }
+    set_clock(conn, change, device)?;
+    Ok(Outcome::Applied)
+}
+
+fn take_pending(conn: &Connection) -> Result<Vec<(String, Change)>> {
+    let mut stmt =
+        conn.prepare("SELECT device, seq, changed_at, hash, field, value FROM sync_pending")?;
+    let pending = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read pending changes")?;
+    conn.execute("DELETE FROM sync_pending", [])?;
+
+    pending
+        .into_iter()
+        .map(|(device, seq, at, hash, field, value)| {
+            Ok((
+                device,
+                Change {
+                    seq,
+                    at,
+                    hash,
+                    field,
+                    value: serde_json::from_str(&value)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn keep_pending(conn: &Connection, change: &Change, device: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sync_pending (hash, field, value, changed_at, device, seq)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            change.hash,
+            change.field,
+            change.value.to_string(),
+            change.at,
+            device,
+            change.seq
+        ],
+    )?;
+    Ok(())
+}
+
+/// Exchange changes with the other devices syncing through `dir`
+pub fn sync(conn: &Connection, dir: &Path) -> Result<Summary> {
+    fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    let tx = conn.unchecked_transaction()?;
+
+    let device = match device_id(&tx)? {
+        Some(device) => device,
+        None => register(&tx)?,
+    };
+    let mut summary = Summary {
+        written: write_local(&tx, &device, &log_path(dir, &device))?,
+        ..Summary::default()
+    };
+
+    // Everything new from the other devices, merged in order
+    let mut incoming = take_pending(&tx)?;
+    let mut entries = fs::read_dir(dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let Some(other) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|_| path.extension().is_some_and(|ext| ext == LOG_EXTENSION))
+        else {
+            continue;
+        };
+        if other == device {
+            continue;
+        }
+        summary.devices += 1;
+
+        let applied: i64 = tx
+            .query_row(
+                "SELECT applied FROM sync_peers WHERE device = ?1",
+                params![other],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(0);
+        let (changes, malformed) = read_log(&path)?;
+        summary.malformed += malformed;
+        let last = changes
+            .iter()
+            .map(|c| c.seq)
+            .max()
+            .unwrap_or(0)
+            .max(applied);
+        incoming.extend(
+            changes
+                .into_iter()
+                .filter(|change| change.seq > applied)
+                .map(|change| (other.to_string(), change)),
+        );
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_peers (device, applied) VALUES (?1, ?2)",
+            params![other, last],
+        )?;
+    }
+    incoming.sort_by_cached_key(|(device, change)| stamp(change, device));
+
+    tx.execute("UPDATE sync_device SET applying = 1", [])?;
+    for (other, change) in &incoming {
+        match merge(&tx, change, other)? {
+            Outcome::Applied => summary.applied += 1,
+            Outcome::Outdated => {}
+            Outcome::Pending => {
+                keep_pending(&tx, change, other)?;
+                summary.pending += 1;
+            }
+        }
+    }
+    tx.execute("UPDATE sync_device SET applying = 0", [])?;
+
+    tx.commit()?;
+    summary.device = device;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_log_leaves_partial_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("laptop.ndjson");
+        fs::write(
+            &path,
+            "{\"seq\":1,\"at\":5,\"hash\":\"abc\",\"field\":\"read\",\"value\":true}\nnot json\n{\"seq\":2,",
+        )
+        .unwrap();
+
+        let (changes, malformed) = read_log(&path).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "read");
+        assert_eq!(malformed, 1);
+    }
+
+    #[test]
+    fn test_new_device_id_is_a_file_name() {
+        let id = new_device_id();
+        assert!(!id.is_empty());
+        assert!(id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+    }
+}
@@ -0,0 +1,4 @@
+// Keeping copies of the library outside the database up to date
+
+pub mod folder;
+pub mod obsidian;
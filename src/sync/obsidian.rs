@@ -0,0 +1,402 @@
+// One note per article in an Obsidian vault folder, plus an index note
+//
+// Notes are named after the article title and tracked by article hash in a
+// hidden state file, along with a digest of what was last written. Later
+// runs only touch notes whose rendering changed, rename notes whose title
+// changed, and remove notes of deleted articles. The `## Note` section can
+// be edited in the vault and pulled back into the article's note; anything
+// else edited there is overwritten on the next sync.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::db::{models::Article, queries};
+
+pub const INDEX_NAME: &str = "Stash Index";
+
+const STATE_FILE: &str = ".stash-sync.json";
+const NOTE_HEADING: &str = "## Note";
+const HIGHLIGHTS_HEADING: &str = "## Highlights";
+
+/// Characters Obsidian doesn't allow in note names or that break links
+const FORBIDDEN: &[char] = &[
+    '*', '"', '\\', '/', '<', '>', ':', '|', '?', '#', '^', '[', ']',
+];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    /// Written notes by article hash
+    #[serde(default)]
+    notes: BTreeMap<String, Written>,
+    #[serde(default)]
+    index: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Written {
+    file: String,
+    digest: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub written: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    /// Notes edited in the vault whose `## Note` went back into the library
+    pub pulled: usize,
+    /// Notes edited in the vault that were written over
+    pub overwritten: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct FrontMatter<'a> {
+    stash_id: i64,
+    hash: &'a str,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    site: Option<&'a str>,
+    tags: &'a [String],
+    read: bool,
+    archived: bool,
+    starred: bool,
+    saved: String,
+}
+
+fn digest(content: &str) -> String {
+    blake3::hash(content.as_bytes()).to_hex().to_string()
+}
+
+fn sanitize(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| if FORBIDDEN.contains(&c) { ' ' } else { c })
+        .filter(|c| !c.is_control())
+        .collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    // A leading dot would hide the note
+    name.trim_start_matches('.').chars().take(100).collect()
+}
+
+/// Note names (without `.md`) by article hash. Articles sharing a title, by
+/// case as file systems may ignore it, get their hash appended, with the
+/// oldest keeping the plain name.
+pub fn note_names(articles: &[Article]) -> HashMap<String, String> {
+    let mut sorted: Vec<&Article> = articles.iter().collect();
+    sorted.sort_by_key(|a| a.id);
+
+    let mut taken: HashSet<String> = HashSet::from([INDEX_NAME.to_lowercase()]);
+    let mut names = HashMap::new();
+    for article in sorted {
+        let mut name = sanitize(article.title.as_deref().unwrap_or(&article.url));
+        if name.is_empty() {
+            name = article.hash.clone();
+        }
+        if !taken.insert(name.to_lowercase()) {
+            name = format!("{} ({})", name, article.hash);
+            taken.insert(name.to_lowercase());
+        }
+        names.insert(article.hash.clone(), name);
+    }
+    names
+}
+
+fn quote(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("> {}", line).trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn render_note(article: &Article) -> Result<String> {
+    let saved = article
+        .saved_at
+        .with_timezone(&Local)
+        .format("%Y-%m-%d")
+        .to_string();
+    let front_matter = FrontMatter {
+        stash_id: article.id,
+        hash: &article.hash,
+        url: &article.url,
+        site: article.site.as_deref(),
+        tags: &article.tags,
+        read: article.read,
+        archived: article.archived,
+        starred: article.starred,
+        saved: saved.clone(),
+    };
+    let yaml = serde_yaml::to_string(&front_matter).context("Failed to serialize front matter")?;
+
+    let mut note = format!(
+        "---\n{}---\n\n# {}\n\n",
+        yaml,
+        article.title.as_deref().unwrap_or(&article.url)
+    );
+    if let Some(description) = &article.description {
+        note.push_str(&format!("{}\n\n", quote(description)));
+    }
+    note.push_str(&format!(
+        "[Original]({}) · {} · saved {}\n\n",
+        article.url,
+        article.site.as_deref().unwrap_or("unknown site"),
+        saved
+    ));
+    if !article.tags.is_empty() {
+        let tags: Vec<String> = article.tags.iter().map(|t| format!("#{}", t)).collect();
+        note.push_str(&format!("{}\n\n", tags.join(" ")));
+    }
+
+    note.push_str(&format!("{}\n\n", NOTE_HEADING));
+    if let Some(text) = article
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+    {
+        note.push_str(&format!("{}\n\n", text));
+    }
+
+    note.push_str(HIGHLIGHTS_HEADING);
+    note.push('\n');
+    for highlight in &article.highlights {
+        note.push_str(&format!("\n{}\n", quote(&highlight.text)));
+        if let Some(comment) = &highlight.comment {
+            note.push_str(&format!("\n{}\n", comment));
+        }
+    }
+
+    Ok(note)
+}
+
+/// The text of the `## Note` section: `None` when the note has no such
+/// section, `Some(None)` when it's empty
+pub fn parse_note_section(note: &str) -> Option<Option<String>> {
+    let mut lines = note.lines();
+    lines
+        .by_ref()
+        .find(|line| line.trim_end() == NOTE_HEADING)?;
+
+    let text = lines
+        .take_while(|line| line.trim_end() != HIGHLIGHTS_HEADING)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+    Some((!text.is_empty()).then(|| text.to_string()))
+}
+
+/// Wiki links to every article: unread and starred ones first, then all of
+/// them by tag, newest first
+pub fn render_index(articles: &[Article], names: &HashMap<String, String>) -> String {
+    let mut sorted: Vec<&Article> = articles.iter().collect();
+    sorted.sort_by_key(|a| Reverse(a.saved_at));
+
+    let link = |article: &Article| {
+        format!(
+            "- [[{}]] · {}\n",
+            names[&article.hash],
+            article.site.as_deref().unwrap_or("unknown site")
+        )
+    };
+    let section = |index: &mut String, heading: &str, articles: Vec<&&Article>| {
+        if !articles.is_empty() {
+            index.push_str(&format!("\n{}\n\n", heading));
+            for article in articles {
+                index.push_str(&link(article));
+            }
+        }
+    };
+
+    let mut index = format!("# {}\n", INDEX_NAME);
+    section(
+        &mut index,
+        "## Unread",
+        sorted.iter().filter(|a| !a.read && !a.archived).collect(),
+    );
+    section(
+        &mut index,
+        "## Starred",
+        sorted.iter().filter(|a| a.starred).collect(),
+    );
+
+    let mut tags: Vec<&String> = sorted.iter().flat_map(|a| &a.tags).collect();
+    tags.sort();
+    tags.dedup();
+    for tag in tags {
+        section(
+            &mut index,
+            &format!("## #{}", tag),
+            sorted.iter().filter(|a| a.tags.contains(tag)).collect(),
+        );
+    }
+    section(
+        &mut index,
+        "## Untagged",
+        sorted.iter().filter(|a| a.tags.is_empty()).collect(),
+    );
+
+    index
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .context(format!("Failed to read {}", path.display()))
+}
+
+fn load_state(dir: &Path) -> Result<State> {
+    match read_if_exists(&dir.join(STATE_FILE))? {
+        Some(json) => serde_json::from_str(&json).context("Failed to parse the sync state file"),
+        None => Ok(State::default()),
+    }
+}
+
+/// Bring the notes in `dir` up to date with the library, first pulling
+/// `## Note` edits made in the vault back when `pull_notes` is set
+pub fn sync(conn: &Connection, dir: &Path, pull_notes: bool) -> Result<Summary> {
+    fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    let state = load_state(dir)?;
+    let mut summary = Summary::default();
+
+    let mut articles = queries::list_articles(conn, i64::MAX, true)?;
+    queries::attach_highlights(conn, &mut articles)?;
+
+    // Notes edited since the last sync, by hash
+    let mut edited = HashSet::new();
+    for (hash, written) in &state.notes {
+        if let Some(content) = read_if_exists(&dir.join(&written.file))?
+            && digest(&content) != written.digest
+        {
+            edited.insert(hash.clone());
+
+            if pull_notes
+                && let Some(note) = parse_note_section(&content)
+                && let Some(article) = articles.iter_mut().find(|a| a.hash == *hash)
+                && note.as_deref() != article.note.as_deref().map(str::trim)
+            {
+                *article = queries::update_note(conn, article.id, note)?;
+                summary.pulled += 1;
+            }
+        }
+    }
+    // Pulling reloaded articles without their highlights
+    queries::attach_highlights(conn, &mut articles)?;
+
+    let names = note_names(&articles);
+    let mut new_state = State::default();
+
+    for article in &articles {
+        let file = format!("{}.md", names[&article.hash]);
+        let content = render_note(article)?;
+        let written = Written {
+            file: file.clone(),
+            digest: digest(&content),
+        };
+
+        let previous = state.notes.get(&article.hash);
+        let on_disk = read_if_exists(&dir.join(&file))?;
+        if previous.is_some_and(|p| p.file == file && p.digest == written.digest)
+            && on_disk.as_deref() == Some(content.as_str())
+        {
+            summary.unchanged += 1;
+        } else {
+            if let Some(previous) = previous.filter(|p| p.file != file) {
+                let old = dir.join(&previous.file);
+                if old.exists() {
+                    fs::remove_file(&old).context(format!("Failed to remove {}", old.display()))?;
+                }
+            }
+            if edited.contains(&article.hash) && !pull_notes {
+                summary.overwritten.push(file.clone());
+            }
+            fs::write(dir.join(&file), &content).context(format!("Failed to write {}", file))?;
+            summary.written += 1;
+        }
+
+        new_state.notes.insert(article.hash.clone(), written);
+    }
+
+    for (hash, written) in &state.notes {
+        if !new_state.notes.contains_key(hash) {
+            let path = dir.join(&written.file);
+            if path.exists() {
+                fs::remove_file(&path).context(format!("Failed to remove {}", path.display()))?;
+            }
+            summary.removed += 1;
+        }
+    }
+
+    let index = render_index(&articles, &names);
+    let index_digest = digest(&index);
+    let index_path = dir.join(format!("{}.md", INDEX_NAME));
+    if state.index.as_deref() != Some(index_digest.as_str())
+        || read_if_exists(&index_path)?.as_deref() != Some(index.as_str())
+    {
+        fs::write(&index_path, &index)
+            .context(format!("Failed to write {}", index_path.display()))?;
+    }
+    new_state.index = Some(index_digest);
+
+    let json =
+        serde_json::to_string_pretty(&new_state).context("Failed to serialize sync state")?;
+    fs::write(dir.join(STATE_FILE), json).context("Failed to write the sync state file")?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::imported_article;
+
+    fn article(id: i64, url: &str, title: &str) -> Article {
+        let mut article = imported_article(url);
+        article.id = id;
+        article.title = Some(title.to_string());
+        article
+    }
+
+    #[test]
+    fn test_note_names() {
+        let articles = vec![
+            article(2, "https://b.com", "Rust: the [good] parts?"),
+            article(1, "https://a.com", "rust  the  good  parts"),
+            article(3, "https://c.com", "Stash Index"),
+        ];
+        let names = note_names(&articles);
+        assert_eq!(names[&articles[1].hash], "rust the good parts");
+        assert_eq!(
+            names[&articles[0].hash],
+            format!("Rust the good parts ({})", articles[0].hash)
+        );
+        assert_eq!(
+            names[&articles[2].hash],
+            format!("Stash Index ({})", articles[2].hash)
+        );
+    }
+
+    #[test]
+    fn test_note_section_round_trip() {
+        let mut a = article(1, "https://a.com", "A");
+        a.tags = vec!["lang/rust".to_string()];
+        a.note = Some("First line\n\n- a list".to_string());
+        let note = render_note(&a).unwrap();
+        assert!(note.contains("\n#lang/rust\n"));
+        assert_eq!(
+            parse_note_section(&note),
+            Some(Some("First line\n\n- a list".to_string()))
+        );
+
+        a.note = None;
+        assert_eq!(parse_note_section(&render_note(&a).unwrap()), Some(None));
+        assert_eq!(parse_note_section("# No sections here"), None);
+    }
+}
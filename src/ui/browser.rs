@@ -1,15 +1,34 @@
 use anyhow::Result;
+use rusqlite::Connection;
 use tiny_http::{Response, Server};
 
-use crate::db::models::Article;
+use crate::db::models::{Article, SavedView};
+use crate::db::queries;
+use crate::export::feed;
+use crate::similarity;
 use crate::ui::formatters::datetime_humanize;
 
-pub fn render_browser(articles: &[Article], all: bool, archived: bool) -> Result<()> {
-    let html = generate_html(articles, all, archived);
-    start_server(html)
+/// Serve `articles` on localhost. Saved views are listed in the header and
+/// served from `/view/<name>`, re-evaluated on every request. Feeds of the
+/// library are at `/feed.atom` and `/feed.rss` (see `feed_page`).
+pub fn render_browser(
+    conn: &Connection,
+    articles: &[Article],
+    all: bool,
+    archived: bool,
+    active_view: Option<&str>,
+) -> Result<()> {
+    start_server(conn, articles, all, archived, active_view)
 }
 
-fn generate_html(articles: &[Article], all: bool, archived: bool) -> String {
+fn generate_html(
+    articles: &[Article],
+    all: bool,
+    archived: bool,
+    views: &[SavedView],
+    active_view: Option<&str>,
+    heading: Option<&str>,
+) -> String {
     let mut article_rows = String::new();
     
     for article in articles {
@@ -72,6 +91,12 @@ fn generate_html(articles: &[Article], all: bool, archived: bool) -> String {
                             <path d="M1.38 8.28a.87.87 0 010-.566 7.003 7.003 0 0113.238 0 .87.87 0 010 .566A7.003 7.003 0 011.379 8.28zM11 8a3 3 0 11-6 0 3 3 0 016 0z"/>
                         </svg>
                     </button>
+                    <a class="action-btn" href="/related/{}" title="Related articles">
+                        <svg width="14" height="14" viewBox="0 0 16 16" fill="currentColor">
+                            <path d="M4 2a2 2 0 100 4 2 2 0 000-4zm8 4a2 2 0 100 4 2 2 0 000-4zM4 10a2 2 0 100 4 2 2 0 000-4z"/>
+                            <path d="M5.7 4.9l4.6 2.2-.4.9-4.6-2.2zM5.3 11.1l4.6-2.2.4.9-4.6 2.2z"/>
+                        </svg>
+                    </a>
                     <button class="action-btn" title="Edit">
                         <svg width="14" height="14" viewBox="0 0 16 16" fill="currentColor">
                             <path d="M12.146.146a.5.5 0 01.708 0l3 3a.5.5 0 010 .708l-10 10a.5.5 0 01-.168.11l-5 2a.5.5 0 01-.65-.65l2-5a.5.5 0 01.11-.168l10-10z"/>
@@ -94,7 +119,8 @@ fn generate_html(articles: &[Article], all: bool, archived: bool) -> String {
             site,
             tags_html,
             saved,
-            archived_cell
+            archived_cell,
+            article.id
         ));
     }
     
@@ -111,6 +137,8 @@ fn generate_html(articles: &[Article], all: bool, archived: bool) -> String {
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>Stash - Articles</title>
+    <link rel="alternate" type="application/atom+xml" title="Stash" href="/feed.atom">
+    <link rel="alternate" type="application/rss+xml" title="Stash" href="/feed.rss">
     <style>
         * {{
             margin: 0;
@@ -235,6 +263,42 @@ fn generate_html(articles: &[Article], all: bool, archived: bool) -> String {
             overflow-x: auto;
         }}
         
+        .views-nav {{
+            padding: 0.75rem 2rem;
+            border-bottom: 1px solid #e5e7eb;
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.5rem;
+            align-items: center;
+            font-size: 0.8125rem;
+        }}
+        
+        .views-label {{
+            color: #6b7280;
+            font-weight: 500;
+            margin-right: 0.25rem;
+        }}
+        
+        .view-link {{
+            padding: 0.25rem 0.75rem;
+            border: 1px solid #d1d5db;
+            border-radius: 9999px;
+            color: #374151;
+            text-decoration: none;
+            transition: all 0.15s;
+        }}
+        
+        .view-link:hover {{
+            background: #f9fafb;
+            border-color: #9ca3af;
+        }}
+        
+        .view-link.active {{
+            background: #3b82f6;
+            border-color: #3b82f6;
+            color: white;
+        }}
+        
         table {{
             width: 100%;
             border-collapse: separate;
@@ -529,9 +593,11 @@ fn generate_html(articles: &[Article], all: bool, archived: bool) -> String {
             </div>
         </div>
         
+        {}
+        
         <div class="table-wrapper">
             <div class="table-header">
-                <div class="table-title">Your saved articles</div>
+                <div class="table-title">{}</div>
                 <div class="table-controls">
                     <button class="control-btn">
                         <span>Show 8 Row</span>
@@ -588,11 +654,51 @@ fn generate_html(articles: &[Article], all: bool, archived: bool) -> String {
 </body>
 </html>"#,
         articles.len(),
+        format_views_nav(views, active_view),
+        match (heading, active_view) {
+            (Some(heading), _) => html_escape(heading),
+            (None, Some(name)) => format!("View: {}", html_escape(name)),
+            (None, None) => "Your saved articles".to_string(),
+        },
         archived_header,
         article_rows
     )
 }
 
+fn format_views_nav(views: &[SavedView], active_view: Option<&str>) -> String {
+    if views.is_empty() {
+        return String::new();
+    }
+
+    let mut links = vec![format!(
+        r#"<a class="view-link{}" href="/">Current list</a>"#,
+        if active_view.is_none() { " active" } else { "" }
+    )];
+
+    for view in views {
+        links.push(format!(
+            r#"<a class="view-link{}" href="/view/{}" title="{}">{}</a>"#,
+            if active_view == Some(view.name.as_str()) { " active" } else { "" },
+            view.name,
+            html_escape(&view.query),
+            html_escape(&view.name)
+        ));
+    }
+
+    format!(
+        r#"<nav class="views-nav"><span class="views-label">Views</span>{}</nav>"#,
+        links.join("")
+    )
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
 fn format_tags_colored(tags: &[String]) -> String {
     let colors = ["blue", "purple", "green", "orange", "pink", "gray"];
     
@@ -606,7 +712,101 @@ fn format_tags_colored(tags: &[String]) -> String {
         .join("")
 }
 
-fn start_server(html: String) -> Result<()> {
+fn related_page(
+    conn: &Connection,
+    id: &str,
+    views: &[SavedView],
+) -> std::result::Result<String, (u16, String)> {
+    let article = id
+        .parse::<i64>()
+        .ok()
+        .and_then(|id| queries::get_article_by_id(conn, id).ok().flatten())
+        .ok_or_else(|| (404, format!("No article with ID '{}'", html_escape(id))))?;
+
+    let related = similarity::related_articles(conn, &article, 20)
+        .map_err(|e| (500, format!("Failed to find related articles: {:#}", e)))?;
+    let found: Vec<Article> = related.into_iter().map(|r| r.article).collect();
+
+    let heading = format!(
+        "Related to: {}",
+        article.title.as_deref().unwrap_or("<no title>")
+    );
+    Ok(generate_html(&found, true, false, views, None, Some(&heading)))
+}
+
+/// Articles shown in a served feed unless `limit` says otherwise
+const FEED_LIMIT: i64 = 50;
+
+/// Decode `%XX` escapes and `+` in a query string value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// A feed of the library, narrowed by the query string: `tag` (repeated or
+/// comma-separated, all must match), `starred=1` and `limit`
+fn feed_page(
+    conn: &Connection,
+    format: &str,
+    query: &str,
+) -> std::result::Result<String, (u16, String)> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut starred = false;
+    let mut limit = FEED_LIMIT;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        let value = percent_decode(value);
+        match key {
+            "tag" | "tags" => tags.extend(
+                value
+                    .split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty()),
+            ),
+            "starred" => starred = value == "1" || value == "true",
+            "limit" => {
+                limit = value
+                    .parse()
+                    .map_err(|_| (400, format!("Bad limit '{}'", html_escape(&value))))?;
+            }
+            _ => {}
+        }
+    }
+
+    let mut articles = queries::list_articles_filtered(
+        conn, limit, true, false, starred, false, &tags, "time", false,
+    )
+    .map_err(|e| (500, format!("Failed to list articles: {:#}", e)))?;
+    queries::attach_highlights(conn, &mut articles)
+        .map_err(|e| (500, format!("Failed to load highlights: {:#}", e)))?;
+
+    feed::generate_feed(format, &articles, &feed::feed_title(&tags, starred))
+        .map_err(|e| (500, format!("{:#}", e)))
+}
+
+fn start_server(
+    conn: &Connection,
+    articles: &[Article],
+    all: bool,
+    archived: bool,
+    active_view: Option<&str>,
+) -> Result<()> {
     let server = Server::http("127.0.0.1:8080")
         .map_err(|e| anyhow::anyhow!("Failed to start server on localhost:8080: {}", e))?;
     
@@ -622,11 +822,46 @@ fn start_server(html: String) -> Result<()> {
     
     // Handle requests
     for request in server.incoming_requests() {
-        let response = Response::from_string(html.clone())
-            .with_header(
-                tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..])
-                    .unwrap()
-            );
+        // Views are loaded per request so newly saved ones show up on reload
+        let views = queries::list_saved_views(conn).unwrap_or_default();
+
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let mut content_type = "text/html; charset=utf-8";
+        let feed_format = path
+            .strip_prefix("/feed.")
+            .filter(|format| ["atom", "rss"].contains(format));
+        let page = if let Some(format) = feed_format {
+            content_type = if format == "atom" {
+                "application/atom+xml; charset=utf-8"
+            } else {
+                "application/rss+xml; charset=utf-8"
+            };
+            feed_page(conn, format, query)
+        } else if let Some(name) = url.strip_prefix("/view/") {
+            match views.iter().find(|v| v.name == name) {
+                Some(view) => queries::evaluate_saved_view(conn, view, 100)
+                    .map(|found| generate_html(&found, false, false, &views, Some(&view.name), None))
+                    .map_err(|e| (500, format!("Failed to evaluate view: {:#}", e))),
+                None => Err((404, format!("No view named '{}'", html_escape(name)))),
+            }
+        } else if let Some(id) = url.strip_prefix("/related/") {
+            related_page(conn, id, &views)
+        } else {
+            Ok(generate_html(articles, all, archived, &views, active_view, None))
+        };
+
+        let response = match page {
+            Ok(html) => Response::from_string(html),
+            Err((status, message)) => {
+                content_type = "text/html; charset=utf-8";
+                Response::from_string(message).with_status_code(status)
+            }
+        }
+        .with_header(
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes())
+                .unwrap()
+        );
         
         if let Err(e) = request.respond(response) {
             eprintln!("Error responding to request: {}", e);
@@ -635,4 +870,3 @@ fn start_server(html: String) -> Result<()> {
     
     Ok(())
 }
-
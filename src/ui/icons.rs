@@ -4,6 +4,8 @@ pub enum Icons {
     CircleEmpty,
     Deleted,
     Note,
+    Snoozed,
+    WokeUp,
 }
 
 impl Icons {
@@ -14,6 +16,8 @@ impl Icons {
             Self::CircleEmpty => "\u{f4c3}",
             Self::Deleted => "\u{f00d}",
             Self::Note => "\u{f249}",  // Note icon
+            Self::Snoozed => "\u{f236}", // Bed icon
+            Self::WokeUp => "\u{f0f3}",  // Bell icon
         }
     }
 }
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::Local;
 use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table, presets};
 
 use crate::{
@@ -45,6 +46,21 @@ fn get_row_style(article: &Article, theme: &Theme) -> (Color, bool, Option<Color
     }
 }
 
+/// Title prefixed with the snooze state, if any
+fn title_with_marker(article: &Article) -> String {
+    let title = article.title.as_deref().unwrap_or("<no title>");
+    match article.snoozed_until {
+        Some(until) if article.is_snoozed() => format!(
+            "{} until {}  {}",
+            Icons::Snoozed.glyph(),
+            until.with_timezone(&Local).format("%b %-d"),
+            title
+        ),
+        Some(_) => format!("{} woke up  {}", Icons::WokeUp.glyph(), title),
+        None => title.to_string(),
+    }
+}
+
 pub fn render_table(articles: &[Article], all: bool, archived: bool) -> Result<()> {
     // Detect terminal theme
     let theme = Theme::detect();
@@ -88,7 +104,7 @@ pub fn render_table(articles: &[Article], all: bool, archived: bool) -> Result<(
     table.set_header(headers);
 
     for article in articles {
-        let (color, _bold, _bg) = get_row_style(&article, &theme);
+        let (color, _bold, _bg) = get_row_style(article, &theme);
 
         // Truncate note for display
         let note_display = article.note.as_ref().map(|n| {
@@ -106,7 +122,7 @@ pub fn render_table(articles: &[Article], all: bool, archived: bool) -> Result<(
             } else {
                 ""
             }),
-            Cell::new(article.title.as_deref().unwrap_or("<no title>"))
+            Cell::new(title_with_marker(article))
                 .fg(color)
                 .add_attribute(Attribute::Bold),
             Cell::new(if article.read {
@@ -3,4 +3,5 @@ pub mod theme;
 pub mod icons;
 pub mod formatters;
 pub mod browser;
-pub mod tui;
\ No newline at end of file
+pub mod tui;
+pub mod tags;
\ No newline at end of file
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use crate::db::{models::TagCount, tags};
+
+/// Render nested tags as a tree, with each count covering the whole subtree:
+///
+/// ```text
+/// lang (5)
+/// ├── go (2)
+/// └── rust (3)
+///     └── async (1)
+/// ```
+pub fn render_tag_tree(counts: &[TagCount]) {
+    for line in tag_tree_lines(counts) {
+        println!("{}", line);
+    }
+}
+
+pub fn tag_tree_lines(counts: &[TagCount]) -> Vec<String> {
+    // Counts include every ancestor, so each nested tag's parent is present
+    let mut children: BTreeMap<Option<&str>, Vec<&TagCount>> = BTreeMap::new();
+    for count in counts {
+        children
+            .entry(tags::parent(&count.tag))
+            .or_default()
+            .push(count);
+    }
+
+    let mut lines = Vec::new();
+    for root in children.get(&None).into_iter().flatten() {
+        lines.push(format!("{} ({})", root.tag, root.total));
+        push_children(&children, &root.tag, "", &mut lines);
+    }
+
+    lines
+}
+
+fn push_children(
+    children: &BTreeMap<Option<&str>, Vec<&TagCount>>,
+    parent: &str,
+    indent: &str,
+    lines: &mut Vec<String>,
+) {
+    let Some(nodes) = children.get(&Some(parent)) else {
+        return;
+    };
+
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i == nodes.len() - 1;
+        let (branch, next_indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        lines.push(format!(
+            "{}{}{} ({})",
+            indent,
+            branch,
+            tags::leaf(&node.tag),
+            node.total
+        ));
+        push_children(children, &node.tag, &format!("{}{}", indent, next_indent), lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(tag: &str, total: usize) -> TagCount {
+        TagCount {
+            tag: tag.to_string(),
+            direct: total,
+            total,
+        }
+    }
+
+    #[test]
+    fn test_tag_tree_lines() {
+        let counts = vec![
+            count("infra", 1),
+            count("infra/k8s", 1),
+            count("lang", 5),
+            count("lang/go", 2),
+            count("lang/rust", 3),
+            count("lang/rust/async", 1),
+        ];
+
+        assert_eq!(
+            tag_tree_lines(&counts),
+            vec![
+                "infra (1)",
+                "└── k8s (1)",
+                "lang (5)",
+                "├── go (2)",
+                "└── rust (3)",
+                "    └── async (1)",
+            ]
+        );
+    }
+}
@@ -3,24 +3,52 @@ use cursive::align::HAlign;
 use cursive::event::Key;
 use cursive::traits::*;
 use cursive::utils::markup::StyledString;
-use cursive::views::{Dialog, LinearLayout, NamedView, Panel, ScrollView, SelectView, TextView};
+use cursive::views::{
+    Dialog, EditView, LinearLayout, NamedView, Panel, ScrollView, SelectView, TextView,
+};
 use cursive::Cursive;
 use rusqlite::Connection;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::db::models::Article;
+use crate::db::models::{Article, SavedView};
 use crate::db::queries;
+use crate::db::tags as tag_tree;
+use crate::queue::{self, Placement};
+use crate::similarity::{self, Document};
 use crate::ui::formatters::datetime_humanize;
 use crate::ui::theme::Theme;
 
 // browser is the `open` crate aliased in Cargo.toml
 extern crate browser;
 
-// Filter state to track if showing all articles or just unread
+// Filter state to track if showing all articles or just unread,
+// the saved view currently selected with `v`, or the reading queue (`Q`)
 #[derive(Clone)]
 struct FilterState {
     show_all: bool,
+    view: Option<SavedView>,
+    queue: bool,
+}
+
+impl FilterState {
+    fn title(&self) -> String {
+        match &self.view {
+            Some(view) => format!("Stash - View: {}", view.name),
+            None if self.queue => "Stash - Reading Queue".to_string(),
+            None if self.show_all => "Stash - All Articles".to_string(),
+            None => "Stash - Unread Articles".to_string(),
+        }
+    }
+
+    // Views are re-run on every load so new articles show up automatically
+    fn load_articles(&self, conn: &Connection) -> Result<Vec<Article>> {
+        match &self.view {
+            Some(view) => queries::evaluate_saved_view(conn, view, 100),
+            None if self.queue => queries::list_queue(conn),
+            None => queries::list_articles(conn, 100, self.show_all),
+        }
+    }
 }
 
 pub fn launch_tui(conn: Connection) -> Result<()> {
@@ -43,15 +71,18 @@ pub fn launch_tui(conn: Connection) -> Result<()> {
     siv.set_theme(theme);
     
     // Initialize filter state (default: show only unread)
-    let filter_state = Rc::new(RefCell::new(FilterState { show_all: false }));
+    let filter_state = Rc::new(RefCell::new(FilterState {
+        show_all: false,
+        view: None,
+        queue: false,
+    }));
     
     // Store connection, filter state, and detected theme in user data
     siv.set_user_data((conn, filter_state.clone(), detected_theme));
     
     // Load articles
-    let show_all = filter_state.borrow().show_all;
     let conn_ref = &siv.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap().0;
-    let articles = queries::list_articles(conn_ref, 100, show_all)?;
+    let articles = filter_state.borrow().load_articles(conn_ref)?;
     
     if articles.is_empty() {
         siv.add_layer(
@@ -94,11 +125,7 @@ fn build_article_list(siv: &mut Cursive, articles: Vec<Article>) {
     
     // Get filter state to show in title
     let filter_state = siv.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap().1.clone();
-    let title = if filter_state.borrow().show_all {
-        "Stash - All Articles"
-    } else {
-        "Stash - Unread Articles"
-    };
+    let title = filter_state.borrow().title();
     
     let layout = LinearLayout::vertical()
         .child(build_header(theme))
@@ -118,6 +145,14 @@ fn build_article_list(siv: &mut Cursive, articles: Vec<Article>) {
     siv.add_global_callback('j', move_down);
     siv.add_global_callback('k', move_up);
     siv.add_global_callback('R', refresh_list);
+    siv.add_global_callback('v', show_view_picker);
+    siv.add_global_callback('t', show_tag_editor);
+    siv.add_global_callback('l', show_related);
+    siv.add_global_callback('Q', toggle_queue);
+    siv.add_global_callback('+', |s| set_queued(s, true));
+    siv.add_global_callback('-', |s| set_queued(s, false));
+    siv.add_global_callback('K', |s| shift_in_queue(s, -1));
+    siv.add_global_callback('J', |s| shift_in_queue(s, 1));
     
     siv.add_fullscreen_layer(layout);
 }
@@ -140,9 +175,16 @@ fn format_article_line(article: &Article, _theme: Theme) -> StyledString {
     let note_icon = if article.note.is_some() { "📝" } else { " " };
     line.push_str(&format!("{}  ", note_icon));
     
-    // Title column
+    // Title column, marked when it just came back from a snooze
     let title = article.title.as_deref().unwrap_or("<no title>");
-    line.push_str(&format!("{:58}", truncate(title, 56)));
+    let title = if article.woke_up() {
+        format!("⏰ {}", title)
+    } else if article.is_snoozed() {
+        format!("💤 {}", title)
+    } else {
+        title.to_string()
+    };
+    line.push_str(&format!("{:58}", truncate(&title, 56)));
     
     // Site column
     let site = article.site.as_deref().unwrap_or("-");
@@ -192,7 +234,7 @@ fn build_header(_theme: Theme) -> TextView {
 }
 
 fn build_footer(_theme: Theme) -> TextView {
-    let footer = "  o/Enter Open  │ r Read  u Unread  │ s Star  A Archive  │ a Filter  R Refresh  │ q/Esc Quit  ";
+    let footer = "  o/Enter Open  │ r Read  u Unread  │ s Star  A Archive  │ t Tags  l Related  │ Q Queue  +/- Queue add/remove  J/K Reorder  │ a Filter  v Views  R Refresh  │ q/Esc Quit  ";
     
     TextView::new(footer)
 }
@@ -284,10 +326,245 @@ fn toggle_filter(s: &mut Cursive) {
         let current = filter_state.borrow().show_all;
         !current
     };
-    filter_state.borrow_mut().show_all = new_show_all;
+    {
+        let mut state = filter_state.borrow_mut();
+        state.show_all = new_show_all;
+        state.view = None;
+        state.queue = false;
+    }
     refresh_list(s);
 }
 
+fn toggle_queue(s: &mut Cursive) {
+    let (_, filter_state, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+    {
+        let mut state = filter_state.borrow_mut();
+        state.queue = !state.queue;
+        state.view = None;
+    }
+    refresh_list(s);
+}
+
+fn set_queued(s: &mut Cursive, queued: bool) {
+    if let Some(article) = get_selected_article(s) {
+        let (conn, _, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+        let result = if queued {
+            queue::enqueue(conn, &[article.id], Placement::Bottom).map(|_| ())
+        } else {
+            queue::dequeue(conn, &[article.id]).map(|_| ())
+        };
+        if let Err(e) = result {
+            show_error(s, &format!("Failed to update queue: {}", e));
+        } else {
+            refresh_list(s);
+        }
+    }
+}
+
+// Move the selected article up (negative) or down the reading queue
+fn shift_in_queue(s: &mut Cursive, offset: isize) {
+    if let Some(article) = get_selected_article(s) {
+        let (conn, _, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+        match queue::shift(conn, article.id, offset) {
+            Ok(true) => refresh_list(s),
+            Ok(false) => {}
+            Err(e) => show_error(s, &format!("Failed to reorder queue: {}", e)),
+        }
+    }
+}
+
+fn show_view_picker(s: &mut Cursive) {
+    let views = {
+        let (conn, _, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+        queries::list_saved_views(conn)
+    };
+
+    let views = match views {
+        Ok(views) => views,
+        Err(e) => {
+            show_error(s, &format!("Failed to load saved views: {}", e));
+            return;
+        }
+    };
+
+    let mut select = SelectView::<Option<SavedView>>::new().h_align(HAlign::Left);
+    select.add_item("(no view)  Unread / all articles", None);
+    for view in views {
+        let label = format!("{}  {}", view.name, view.query);
+        select.add_item(label, Some(view));
+    }
+
+    select.set_on_submit(|s, choice: &Option<SavedView>| {
+        s.pop_layer();
+        let (_, filter_state, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+        {
+            let mut state = filter_state.borrow_mut();
+            state.view = choice.clone();
+            state.queue = false;
+        }
+        refresh_list(s);
+    });
+
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title("Saved Views")
+            .button("Cancel", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+
+fn show_related(s: &mut Cursive) {
+    let Some(article) = get_selected_article(s) else {
+        return;
+    };
+
+    let related = {
+        let (conn, _, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+        similarity::related_articles(conn, &article, 15)
+    };
+
+    let related = match related {
+        Ok(related) => related,
+        Err(e) => {
+            show_error(s, &format!("Failed to find related articles: {}", e));
+            return;
+        }
+    };
+
+    if related.is_empty() {
+        s.add_layer(
+            Dialog::text("No related articles found.")
+                .title("Related")
+                .button("Ok", |s| {
+                    s.pop_layer();
+                }),
+        );
+        return;
+    }
+
+    let mut select = SelectView::<i64>::new().h_align(HAlign::Left);
+    for item in related {
+        let title = item.article.title.as_deref().unwrap_or("<no title>");
+        let label = format!(
+            "{:>4}  {:3.0}%  {}",
+            item.article.id,
+            item.score * 100.0,
+            truncate(title, 60).trim_end()
+        );
+        select.add_item(label, item.article.id);
+    }
+
+    select.set_on_submit(|s, id: &i64| {
+        s.pop_layer();
+        open_article(s, *id);
+    });
+
+    let title = article.title.as_deref().unwrap_or("<no title>");
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title(format!("Related to: {}", truncate(title, 50).trim_end()))
+            .button("Close", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+
+fn show_tag_editor(s: &mut Cursive) {
+    let Some(article) = get_selected_article(s) else {
+        return;
+    };
+
+    // Suggestions are a nice-to-have; don't block editing if they fail
+    let suggested: Vec<String> = {
+        let (conn, _, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+        similarity::suggest_tags(conn, &Document::from_article(&article), 5)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|suggestion| suggestion.tag)
+            .collect()
+    };
+
+    let id = article.id;
+    let hint = if suggested.is_empty() {
+        "No suggestions".to_string()
+    } else {
+        format!("Suggested: {}", suggested.join(", "))
+    };
+
+    let editor = EditView::new()
+        .content(article.tags.join(", "))
+        .on_submit(move |s, text| save_tags(s, id, text))
+        .with_name("tag_editor")
+        .fixed_width(60);
+
+    let layout = LinearLayout::vertical()
+        .child(TextView::new("Tags (comma-separated)"))
+        .child(editor)
+        .child(TextView::new(hint));
+
+    s.add_layer(
+        Dialog::around(layout)
+            .title(format!("Tags for #{}", id))
+            .button("Add suggested", move |s| {
+                s.call_on_name("tag_editor", |view: &mut EditView| {
+                    let mut tags = parse_tags(&view.get_content());
+                    for tag in &suggested {
+                        if !tags.contains(tag) {
+                            tags.push(tag.clone());
+                        }
+                    }
+                    view.set_content(tags.join(", "));
+                });
+            })
+            .button("Save", move |s| {
+                let text = s
+                    .call_on_name("tag_editor", |view: &mut EditView| view.get_content())
+                    .unwrap_or_default();
+                save_tags(s, id, &text);
+            })
+            .button("Cancel", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+
+fn parse_tags(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn save_tags(s: &mut Cursive, id: i64, text: &str) {
+    let mut tags = parse_tags(text);
+    if let Some(invalid) = tags.iter().find(|tag| !tag_tree::is_valid(tag)) {
+        show_error(
+            s,
+            &format!(
+                "Invalid tag '{}'. Tags must be lowercase alphanumeric with hyphens, nested with '/'.",
+                invalid
+            ),
+        );
+        return;
+    }
+    tags.sort();
+    tags.dedup();
+
+    let result = {
+        let (conn, _, _) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
+        queries::update_tags(conn, id, tags)
+    };
+
+    match result {
+        Ok(_) => {
+            s.pop_layer();
+            refresh_list(s);
+        }
+        Err(e) => show_error(s, &format!("Failed to update tags: {}", e)),
+    }
+}
+
 fn get_selected_article(s: &mut Cursive) -> Option<Article> {
     s.call_on_name("select", |view: &mut SelectView<Article>| {
         view.selection().map(|rc| rc.as_ref().clone())
@@ -322,18 +599,13 @@ fn refresh_list(s: &mut Cursive) {
         .flatten();
     
     // Reload articles (borrow conn, filter state, and theme separately)
-    let (articles, show_all, theme) = {
+    let (articles, title, theme) = {
         let (conn, filter_state, theme) = s.user_data::<(Connection, Rc<RefCell<FilterState>>, Theme)>().unwrap();
-        let show_all = filter_state.borrow().show_all;
-        (queries::list_articles(conn, 100, show_all), show_all, *theme)
+        let state = filter_state.borrow();
+        (state.load_articles(conn), state.title(), *theme)
     };
     
     // Update panel title
-    let title = if show_all {
-        "Stash - All Articles"
-    } else {
-        "Stash - Unread Articles"
-    };
     
     s.call_on_name("panel", |view: &mut Panel<ScrollView<NamedView<SelectView<Article>>>>| {
         view.set_title(title);
@@ -1,33 +1,52 @@
 // Common test utilities and fixtures
 #![allow(dead_code)] // Test helpers are used across different test files
 
+use chrono::Utc;
 use rusqlite::Connection;
 use stash::db::models::{Article, NewArticle};
-use chrono::Utc;
 
 /// Creates an in-memory SQLite database with migrations applied
 pub fn setup_test_db() -> Connection {
     let mut conn = Connection::open(":memory:").expect("Failed to create in-memory database");
-    
+
     // Embed and run migrations
     mod embedded {
         use refinery::embed_migrations;
         embed_migrations!("src/db/migrations");
     }
-    
+
     embedded::migrations::runner()
         .run(&mut conn)
         .expect("Failed to run migrations");
-    
+
     // Drop FTS triggers and table for testing since we're not testing search functionality
     // This prevents FTS-related errors in unit tests
-    conn.execute_batch(r#"
+    conn.execute_batch(
+        r#"
         DROP TRIGGER IF EXISTS articles_fts_insert;
         DROP TRIGGER IF EXISTS articles_fts_update;
         DROP TRIGGER IF EXISTS articles_fts_delete;
         DROP TABLE IF EXISTS articles_fts;
-    "#).expect("Failed to drop FTS table and triggers");
-    
+    "#,
+    )
+    .expect("Failed to drop FTS table and triggers");
+
+    conn
+}
+
+/// Creates an in-memory SQLite database with migrations applied, keeping the FTS index
+pub fn setup_test_db_with_fts() -> Connection {
+    let mut conn = Connection::open(":memory:").expect("Failed to create in-memory database");
+
+    mod embedded {
+        use refinery::embed_migrations;
+        embed_migrations!("src/db/migrations");
+    }
+
+    embedded::migrations::runner()
+        .run(&mut conn)
+        .expect("Failed to run migrations");
+
     conn
 }
 
@@ -56,6 +75,9 @@ pub fn create_test_article(
         starred: false,
         note: None,
         tags: tags.iter().map(|s| s.to_string()).collect(),
+        queue_position: None,
+        snoozed_until: None,
+        highlights: Vec::new(),
     }
 }
 
@@ -67,6 +89,7 @@ pub fn create_new_article(
     tags: Vec<&str>,
 ) -> NewArticle {
     NewArticle {
+        id: None,
         hash: hash.to_string(),
         url: url.to_string(),
         canonical_url: url.to_string(),
@@ -76,6 +99,7 @@ pub fn create_new_article(
         favicon_url: None,
         content_markdown: None,
         tags: tags.iter().map(|s| s.to_string()).collect(),
+        saved_at: None,
     }
 }
 
@@ -149,4 +173,3 @@ pub const HTML_EMPTY: &str = r#"
 "#;
 
 pub const HTML_MINIMAL: &str = "<html><head></head><body></body></html>";
-
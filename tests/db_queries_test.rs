@@ -2,7 +2,7 @@
 mod common;
 
 use stash::db::queries;
-use common::{setup_test_db, create_new_article};
+use common::{setup_test_db, setup_test_db_with_fts, create_new_article};
 
 // CRUD Operations Tests
 
@@ -28,6 +28,17 @@ fn test_insert_article_success() {
     assert!(!article.archived);
 }
 
+#[test]
+fn test_insert_article_keeps_saved_at() {
+    let conn = setup_test_db();
+    let saved_at = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let mut new_article = create_new_article("abc12345", "https://example.com/a", None, vec![]);
+    new_article.saved_at = Some(saved_at);
+
+    let article = queries::insert_article(&conn, new_article).unwrap();
+    assert_eq!(article.saved_at, saved_at);
+}
+
 #[test]
 fn test_insert_article_duplicate_hash_fails() {
     let conn = setup_test_db();
@@ -196,7 +207,7 @@ fn test_list_articles_filtered_by_single_tag() {
     queries::insert_article(&conn, article2).unwrap();
     queries::insert_article(&conn, article3).unwrap();
 
-    let result = queries::list_articles_filtered(&conn, 10, true, false, false, &["rust".to_string()], "time", false);
+    let result = queries::list_articles_filtered(&conn, 10, true, false, false, false, &["rust".to_string()], "time", false);
     assert!(result.is_ok());
     
     let articles = result.unwrap();
@@ -216,7 +227,7 @@ fn test_list_articles_filtered_by_multiple_tags_and_logic() {
     queries::insert_article(&conn, article3).unwrap();
 
     let result = queries::list_articles_filtered(
-        &conn, 10, true, false, false,
+        &conn, 10, true, false, false, false,
         &["rust".to_string(), "cli".to_string()],
         "time", false
     );
@@ -239,7 +250,7 @@ fn test_list_articles_filtered_by_starred() {
     
     queries::set_starred_by_ids(&conn, &[id2], true).unwrap();
 
-    let result = queries::list_articles_filtered(&conn, 10, true, false, true, &[], "time", false);
+    let result = queries::list_articles_filtered(&conn, 10, true, false, true, false, &[], "time", false);
     assert!(result.is_ok());
     
     let articles = result.unwrap();
@@ -259,7 +270,7 @@ fn test_list_articles_filtered_by_archived() {
     
     queries::archive_by_ids(&conn, &[id2]).unwrap();
 
-    let result = queries::list_articles_filtered(&conn, 10, true, true, false, &[], "time", false);
+    let result = queries::list_articles_filtered(&conn, 10, true, true, false, false, &[], "time", false);
     assert!(result.is_ok());
     
     let articles = result.unwrap();
@@ -279,7 +290,7 @@ fn test_list_articles_sorted_by_title() {
     queries::insert_article(&conn, article2).unwrap();
     queries::insert_article(&conn, article3).unwrap();
 
-    let result = queries::list_articles_filtered(&conn, 10, true, false, false, &[], "title", false);
+    let result = queries::list_articles_filtered(&conn, 10, true, false, false, false, &[], "title", false);
     assert!(result.is_ok());
     
     let articles = result.unwrap();
@@ -298,7 +309,7 @@ fn test_list_articles_sorted_by_title_reverse() {
     queries::insert_article(&conn, article1).unwrap();
     queries::insert_article(&conn, article2).unwrap();
 
-    let result = queries::list_articles_filtered(&conn, 10, true, false, false, &[], "title", true);
+    let result = queries::list_articles_filtered(&conn, 10, true, false, false, false, &[], "title", true);
     assert!(result.is_ok());
     
     let articles = result.unwrap();
@@ -532,3 +543,356 @@ fn test_update_article_metadata() {
     assert!(!updated.archived);
 }
 
+
+// Saved View Tests
+
+#[test]
+fn test_upsert_saved_view_inserts_and_overwrites() {
+    let conn = setup_test_db();
+
+    let view = queries::upsert_saved_view(&conn, "rust-starred", "tag:rust is:starred").unwrap();
+    assert_eq!(view.name, "rust-starred");
+    assert_eq!(view.query, "tag:rust is:starred");
+
+    let updated = queries::upsert_saved_view(&conn, "rust-starred", "tag:rust").unwrap();
+    assert_eq!(updated.id, view.id);
+    assert_eq!(updated.query, "tag:rust");
+    assert_eq!(queries::list_saved_views(&conn).unwrap().len(), 1);
+}
+
+#[test]
+fn test_find_and_delete_saved_view() {
+    let conn = setup_test_db();
+    queries::upsert_saved_view(&conn, "go", "tag:go").unwrap();
+
+    assert!(queries::find_saved_view_by_name(&conn, "go").unwrap().is_some());
+    assert!(queries::find_saved_view_by_name(&conn, "rust").unwrap().is_none());
+
+    assert_eq!(queries::delete_saved_view(&conn, "go").unwrap(), 1);
+    assert_eq!(queries::delete_saved_view(&conn, "go").unwrap(), 0);
+    assert!(queries::list_saved_views(&conn).unwrap().is_empty());
+}
+
+#[test]
+fn test_saved_view_picks_up_new_articles() {
+    let conn = setup_test_db();
+    let view = queries::upsert_saved_view(&conn, "rust", "tag:rust").unwrap();
+
+    queries::insert_article(&conn, create_new_article("hash1", "https://example.com/1", Some("A"), vec!["rust"])).unwrap();
+    assert_eq!(queries::evaluate_saved_view(&conn, &view, 10).unwrap().len(), 1);
+
+    // Views store the query, not the results, so later articles show up too
+    queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", Some("B"), vec!["rust"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash3", "https://example.com/3", Some("C"), vec!["go"])).unwrap();
+    assert_eq!(queries::evaluate_saved_view(&conn, &view, 10).unwrap().len(), 2);
+}
+
+#[test]
+fn test_count_saved_view_matches_evaluation() {
+    let conn = setup_test_db_with_fts();
+    let view = queries::upsert_saved_view(&conn, "rust", "tag:rust").unwrap();
+    assert_eq!(queries::count_saved_view(&conn, &view).unwrap(), 0);
+
+    let a = queries::insert_article(&conn, create_new_article("hash1", "https://example.com/1", Some("A"), vec!["rust"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", Some("B"), vec!["rust"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash3", "https://example.com/3", Some("C"), vec!["go"])).unwrap();
+    assert_eq!(queries::count_saved_view(&conn, &view).unwrap(), 2);
+
+    // Read articles drop out of the count just as they do from the results
+    queries::mark_read_by_ids(&conn, &[a.id]).unwrap();
+    assert_eq!(queries::count_saved_view(&conn, &view).unwrap(), 1);
+    assert_eq!(queries::evaluate_saved_view(&conn, &view, 10).unwrap().len(), 1);
+}
+
+// Hierarchical Tag Tests
+
+#[test]
+fn test_list_articles_filtered_by_parent_tag_matches_descendants() {
+    let conn = setup_test_db();
+    queries::insert_article(&conn, create_new_article("hash1", "https://example.com/1", Some("A"), vec!["lang/rust"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", Some("B"), vec!["lang/go"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash3", "https://example.com/3", Some("C"), vec!["language"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash4", "https://example.com/4", Some("D"), vec!["lang"])).unwrap();
+
+    let articles = queries::list_articles_filtered(&conn, 10, true, false, false, false, &["lang".to_string()], "title", false).unwrap();
+    let titles: Vec<_> = articles.iter().map(|a| a.title.clone().unwrap()).collect();
+    assert_eq!(titles, vec!["A", "B", "D"]);
+
+    let articles = queries::list_articles_filtered(&conn, 10, true, false, false, false, &["lang/rust".to_string()], "title", false).unwrap();
+    assert_eq!(articles.len(), 1);
+}
+
+#[test]
+fn test_tag_counts_aggregate_parents() {
+    let conn = setup_test_db();
+    queries::insert_article(&conn, create_new_article("hash1", "https://example.com/1", Some("A"), vec!["lang/rust", "lang/go"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", Some("B"), vec!["lang/rust/async"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash3", "https://example.com/3", Some("C"), vec!["lang"])).unwrap();
+
+    let counts = queries::get_tag_counts(&conn).unwrap();
+    let find = |tag: &str| counts.iter().find(|c| c.tag == tag).unwrap();
+
+    // Article A has two lang/* tags but counts once towards lang
+    assert_eq!((find("lang").total, find("lang").direct), (3, 1));
+    assert_eq!((find("lang/rust").total, find("lang/rust").direct), (2, 1));
+    assert_eq!((find("lang/rust/async").total, find("lang/rust/async").direct), (1, 1));
+    assert_eq!((find("lang/go").total, find("lang/go").direct), (1, 1));
+
+    let with_counts = queries::get_all_tags_with_counts(&conn).unwrap();
+    assert!(with_counts.contains(&("lang".to_string(), 3)));
+}
+
+#[test]
+fn test_rename_tag_moves_subtree() {
+    let conn = setup_test_db();
+    queries::insert_article(&conn, create_new_article("hash1", "https://example.com/1", Some("A"), vec!["lang/rust", "cli"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", Some("B"), vec!["lang/rust/async", "lang/rs"])).unwrap();
+    queries::insert_article(&conn, create_new_article("hash3", "https://example.com/3", Some("C"), vec!["lang/rusty"])).unwrap();
+
+    let updated = queries::rename_tag(&conn, "lang/rust", "lang/rs").unwrap();
+    assert_eq!(updated, 2);
+
+    let a = queries::get_article_by_id(&conn, 1).unwrap().unwrap();
+    assert_eq!(a.tags, vec!["lang/rs", "cli"]);
+    let b = queries::get_article_by_id(&conn, 2).unwrap().unwrap();
+    assert_eq!(b.tags, vec!["lang/rs/async", "lang/rs"]);
+    let c = queries::get_article_by_id(&conn, 3).unwrap().unwrap();
+    assert_eq!(c.tags, vec!["lang/rusty"]);
+}
+
+// Highlight Tests
+
+#[test]
+fn test_highlights_crud_and_ordering() {
+    let conn = setup_test_db();
+    let article = queries::insert_article(
+        &conn,
+        create_new_article("hash1", "https://example.com/1", Some("Article 1"), vec![]),
+    )
+    .unwrap();
+
+    let later = queries::insert_highlight(&conn, article.id, "second quote", Some(40), None).unwrap();
+    let first = queries::insert_highlight(&conn, article.id, "first quote", Some(3), Some("good point")).unwrap();
+    let loose = queries::insert_highlight(&conn, article.id, "no position", None, None).unwrap();
+
+    let highlights = queries::list_highlights(&conn, Some(article.id)).unwrap();
+    let ids: Vec<i64> = highlights.iter().map(|h| h.id).collect();
+    assert_eq!(ids, vec![first.id, later.id, loose.id]);
+    assert_eq!(highlights[0].comment.as_deref(), Some("good point"));
+
+    assert_eq!(queries::delete_highlight(&conn, later.id).unwrap(), 1);
+    assert_eq!(queries::delete_highlight(&conn, later.id).unwrap(), 0);
+    assert_eq!(queries::list_highlights(&conn, None).unwrap().len(), 2);
+}
+
+#[test]
+fn test_highlights_follow_their_article() {
+    let conn = setup_test_db();
+    let a = queries::insert_article(
+        &conn,
+        create_new_article("hash1", "https://example.com/1", Some("Article 1"), vec![]),
+    )
+    .unwrap();
+    let b = queries::insert_article(
+        &conn,
+        create_new_article("hash2", "https://example.com/2", Some("Article 2"), vec![]),
+    )
+    .unwrap();
+    queries::insert_highlight(&conn, a.id, "from a", None, None).unwrap();
+    queries::insert_highlight(&conn, b.id, "from b", None, None).unwrap();
+
+    let mut articles = queries::find_by_ids(&conn, &[a.id, b.id]).unwrap();
+    queries::attach_highlights(&conn, &mut articles).unwrap();
+    for article in &articles {
+        assert_eq!(article.highlights.len(), 1);
+        assert_eq!(article.highlights[0].article_id, article.id);
+    }
+
+    // Deleting an article removes its highlights
+    queries::delete_by_ids(&conn, &[a.id]).unwrap();
+    let remaining = queries::list_highlights(&conn, None).unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].text, "from b");
+}
+
+// Snooze Tests
+
+#[test]
+fn test_snoozed_articles_are_hidden_until_they_wake_up() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=3)
+        .map(|n| {
+            queries::insert_article(
+                &conn,
+                create_new_article(
+                    &format!("hash{}", n),
+                    &format!("https://example.com/{}", n),
+                    Some(&format!("Article {}", n)),
+                    vec![],
+                ),
+            )
+            .unwrap()
+            .id
+        })
+        .collect();
+
+    let now = chrono::Utc::now();
+    queries::snooze_by_ids(&conn, &[ids[1]], Some(now + chrono::Duration::days(3))).unwrap();
+    queries::snooze_by_ids(&conn, &[ids[0]], Some(now - chrono::Duration::hours(1))).unwrap();
+
+    // The pending snooze is hidden; the one that woke up comes first
+    let listed: Vec<i64> = queries::list_articles(&conn, 10, false)
+        .unwrap()
+        .iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(listed, vec![ids[0], ids[2]]);
+
+    let listed = queries::list_articles_filtered(&conn, 10, false, false, false, false, &[], "time", false).unwrap();
+    assert_eq!(listed.len(), 2);
+    assert!(listed[0].woke_up());
+
+    let snoozed = queries::list_articles_filtered(&conn, 10, false, false, false, true, &[], "time", false).unwrap();
+    assert_eq!(snoozed.len(), 1);
+    assert_eq!(snoozed[0].id, ids[1]);
+    assert!(snoozed[0].is_snoozed());
+
+    // Reading clears the woke up marker
+    let read = queries::mark_read_by_ids(&conn, &[ids[0]]).unwrap();
+    assert_eq!(read[0].snoozed_until, None);
+
+    // Clearing a snooze brings the article straight back
+    queries::snooze_by_ids(&conn, &[ids[1]], None).unwrap();
+    assert_eq!(queries::list_articles(&conn, 10, false).unwrap().len(), 2);
+}
+
+#[test]
+fn test_reading_records_last_opened_at() {
+    let conn = setup_test_db();
+    let a = queries::insert_article(&conn, create_new_article("hash1", "https://example.com/1", None, vec![])).unwrap();
+    let b = queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", None, vec![])).unwrap();
+    assert!(a.last_opened_at.is_none());
+
+    let opened = queries::mark_read_by_ids(&conn, &[a.id]).unwrap();
+    assert!(opened[0].last_opened_at.is_some());
+
+    let marked = queries::set_read_by_ids(&conn, &[b.id], true).unwrap();
+    assert!(marked[0].last_opened_at.is_some());
+
+    // Marking unread keeps the history
+    let unread = queries::set_read_by_ids(&conn, &[a.id], false).unwrap();
+    assert!(unread[0].last_opened_at.is_some());
+}
+
+#[test]
+fn test_restore_state_and_fetch_queue() {
+    let conn = setup_test_db();
+    let mut imported = queries::insert_article(&conn, create_new_article("hash1", "https://example.com/1", None, vec![])).unwrap();
+    let other = queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", Some("Kept"), vec![])).unwrap();
+
+    imported.read = true;
+    imported.starred = true;
+    imported.note = Some("from another app".to_string());
+    let restored = queries::restore_state(&conn, imported.id, &imported).unwrap();
+    assert!(restored.read && restored.starred && !restored.archived);
+    assert_eq!(restored.note.as_deref(), Some("from another app"));
+
+    assert_eq!(queries::queue_fetch(&conn, &[imported.id, other.id]).unwrap(), 2);
+    assert_eq!(queries::list_fetch_pending(&conn).unwrap().len(), 2);
+
+    let fetched = queries::save_fetched_content(
+        &conn,
+        other.id,
+        Some("Fetched".to_string()),
+        None,
+        None,
+        Some("Body".to_string()),
+    )
+    .unwrap();
+    assert_eq!(fetched.title.as_deref(), Some("Kept"));
+    assert_eq!(fetched.content_markdown.as_deref(), Some("Body"));
+
+    let pending = queries::list_fetch_pending(&conn).unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].id, imported.id);
+}
+
+#[test]
+fn test_insert_with_id_and_update_article() {
+    let conn = setup_test_db();
+    let mut new_article = create_new_article("hash1", "https://example.com/1", Some("Old"), vec!["rust"]);
+    new_article.id = Some(42);
+    let mut article = queries::insert_article(&conn, new_article).unwrap();
+    assert_eq!(article.id, 42);
+
+    article.title = Some("New".to_string());
+    article.tags.push("cli".to_string());
+    article.note = Some("merged".to_string());
+    article.archived = true;
+    let updated = queries::update_article(&conn, &article).unwrap();
+    assert_eq!(updated.title.as_deref(), Some("New"));
+    assert_eq!(updated.tags, vec!["rust", "cli"]);
+    assert_eq!(updated.note.as_deref(), Some("merged"));
+    assert!(updated.archived);
+
+    let next = queries::insert_article(&conn, create_new_article("hash2", "https://example.com/2", None, vec![])).unwrap();
+    assert_eq!(next.id, 43);
+}
+
+#[test]
+fn test_list_matching_combines_filters() {
+    let conn = setup_test_db_with_fts();
+    let now = chrono::Utc::now();
+    let add = |hash: &str, title: &str, site: &str, days_ago: i64| {
+        let mut article = create_new_article(hash, &format!("https://{}/{}", site, hash), Some(title), vec!["rust"]);
+        article.site = Some(site.to_string());
+        article.saved_at = Some(now - chrono::Duration::days(days_ago));
+        queries::insert_article(&conn, article).unwrap()
+    };
+    let old = add("h1", "Async Rust", "docs.rs", 30);
+    let recent = add("h2", "Async Python", "blog.github.com", 2);
+    let other = add("h3", "Gardening", "example.org", 1);
+    queries::set_read_by_ids(&conn, &[other.id], true).unwrap();
+
+    let ids = |filter: &queries::ArticleFilter| -> Vec<i64> {
+        queries::list_matching(&conn, filter).unwrap().iter().map(|a| a.id).collect()
+    };
+
+    // Newest first, read and unread alike
+    assert_eq!(ids(&queries::ArticleFilter::default()), vec![other.id, recent.id, old.id]);
+    assert_eq!(
+        ids(&queries::ArticleFilter { read: Some(false), ..Default::default() }),
+        vec![recent.id, old.id]
+    );
+    assert_eq!(
+        ids(&queries::ArticleFilter {
+            since: Some(now - chrono::Duration::days(7)),
+            sites: vec!["github.com".to_string(), "docs.rs".to_string()],
+            ..Default::default()
+        }),
+        vec![recent.id]
+    );
+    assert_eq!(
+        ids(&queries::ArticleFilter {
+            queries: vec!["async".to_string()],
+            until: Some(now - chrono::Duration::days(7)),
+            ..Default::default()
+        }),
+        vec![old.id]
+    );
+    assert_eq!(
+        ids(&queries::ArticleFilter { ids: Some(vec![old.id, other.id]), read: Some(true), ..Default::default() }),
+        vec![other.id]
+    );
+    assert!(queries::any_matching(&conn, &queries::ArticleFilter::default()).unwrap());
+    assert!(!queries::any_matching(
+        &conn,
+        &queries::ArticleFilter { tags: vec!["nosuchtag".to_string()], ..Default::default() }
+    )
+    .unwrap());
+    assert!(queries::list_matching(&conn, &queries::ArticleFilter {
+        queries: vec!["(unbalanced".to_string()],
+        ..Default::default()
+    })
+    .is_err());
+}
@@ -0,0 +1,165 @@
+// Near-duplicate detection and merge tests
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{create_new_article, create_test_article, setup_test_db};
+use stash::db::{
+    models::{Article, Highlight},
+    queries,
+};
+use stash::dedupe;
+
+const PRESS_RELEASE: &str = "Acme Corp today announced the general availability of its new \
+    widget platform, which lets teams build, test and ship widgets faster than ever before. \
+    The platform includes a visual editor, automated testing and one-click deployment to \
+    every major cloud provider. Pricing starts at ten dollars per seat per month.";
+
+fn article(id: i64, title: &str, content: Option<&str>) -> Article {
+    let mut article = create_test_article(
+        id,
+        &format!("hash{}", id),
+        &format!("https://site{}.example.com/post", id),
+        Some(title),
+        vec![],
+    );
+    article.content_markdown = content.map(|c| c.to_string());
+    article
+}
+
+#[test]
+fn test_syndicated_copies_are_clustered() {
+    let syndicated = format!("{} Reprinted with permission.", PRESS_RELEASE);
+    let articles = vec![
+        article(1, "Acme launches widget platform", Some(PRESS_RELEASE)),
+        article(
+            2,
+            "Unrelated",
+            Some(
+                "A long essay about gardening, soil health, compost \
+            and the best time of the year to plant tomatoes in a temperate climate.",
+            ),
+        ),
+        article(3, "Acme Corp launches widget platform", Some(&syndicated)),
+        article(4, "Acme widget platform pricing", None),
+    ];
+
+    let clusters = dedupe::find_clusters(&articles, dedupe::DEFAULT_THRESHOLD);
+    assert_eq!(clusters.len(), 1);
+
+    let mut ids: Vec<i64> = clusters[0].articles.iter().map(|a| a.id).collect();
+    ids.sort();
+    assert_eq!(ids, vec![1, 3]);
+}
+
+#[test]
+fn test_title_only_duplicates_need_content_free_articles() {
+    let articles = vec![
+        article(1, "Release notes for version two", None),
+        article(2, "Release Notes for Version Two", None),
+        article(3, "Hello", None),
+        article(4, "hello", None),
+    ];
+
+    let clusters = dedupe::find_clusters(&articles, dedupe::DEFAULT_THRESHOLD);
+    // Very short titles are too ambiguous to merge on their own
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0].articles.len(), 2);
+    assert_eq!(clusters[0].articles[0].id, 1);
+}
+
+#[test]
+fn test_apply_merge_folds_state_into_kept_article() {
+    let conn = setup_test_db();
+    let keep = queries::insert_article(
+        &conn,
+        create_new_article("hash1", "https://example.com/a", Some("A"), vec!["news"]),
+    )
+    .unwrap();
+    let dup = queries::insert_article(
+        &conn,
+        create_new_article(
+            "hash2",
+            "https://mirror.example.com/a",
+            Some("A"),
+            vec!["acme", "news"],
+        ),
+    )
+    .unwrap();
+
+    let keep = queries::update_note(&conn, keep.id, Some("first".to_string())).unwrap();
+    queries::update_note(&conn, dup.id, Some("second".to_string())).unwrap();
+    queries::set_starred_by_ids(&conn, &[dup.id], true).unwrap();
+    queries::set_read_by_ids(&conn, &[dup.id], true).unwrap();
+    queries::insert_highlight(&conn, dup.id, "quote", None, None).unwrap();
+    let dup = queries::get_article_by_id(&conn, dup.id).unwrap().unwrap();
+
+    dedupe::apply_merge(&conn, &keep, &[&dup]).unwrap();
+
+    let kept = queries::get_article_by_id(&conn, keep.id).unwrap().unwrap();
+    assert_eq!(kept.tags, vec!["acme", "news"]);
+    assert_eq!(kept.note.as_deref(), Some("first\n\nsecond"));
+    assert!(kept.starred);
+    assert!(kept.read);
+    assert!(!kept.archived);
+    assert_eq!(
+        queries::list_highlights(&conn, Some(keep.id))
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let archived = queries::get_article_by_id(&conn, dup.id).unwrap().unwrap();
+    assert!(archived.archived);
+}
+
+#[test]
+fn test_suggested_keep_prefers_highlights() {
+    let mut plain = article(1, "A", Some(PRESS_RELEASE));
+    plain.saved_at = Utc::now() - Duration::days(2);
+    let mut highlighted = article(2, "A", Some(PRESS_RELEASE));
+    highlighted.highlights.push(Highlight {
+        id: 1,
+        article_id: 2,
+        text: "quote".to_string(),
+        comment: None,
+        position: None,
+        created_at: Utc::now(),
+    });
+
+    let cluster = dedupe::Cluster {
+        articles: vec![plain, highlighted],
+    };
+    assert_eq!(cluster.suggested_keep(), 1);
+
+    // Without highlights the earliest saved one wins
+    let mut cluster = cluster;
+    cluster.articles[1].highlights.clear();
+    assert_eq!(cluster.suggested_keep(), 0);
+}
+
+#[test]
+fn test_apply_merge_keeps_latest_last_opened_at() {
+    let conn = setup_test_db();
+    let keep = queries::insert_article(
+        &conn,
+        create_new_article("hash1", "https://example.com/a", Some("A"), vec![]),
+    )
+    .unwrap();
+    let dup = queries::insert_article(
+        &conn,
+        create_new_article("hash2", "https://mirror.example.com/a", Some("A"), vec![]),
+    )
+    .unwrap();
+
+    let opened = Utc::now() - Duration::days(3);
+    queries::set_last_opened_at(&conn, keep.id, Some(opened - Duration::days(7))).unwrap();
+    queries::set_last_opened_at(&conn, dup.id, Some(opened)).unwrap();
+    let keep = queries::get_article_by_id(&conn, keep.id).unwrap().unwrap();
+    let dup = queries::get_article_by_id(&conn, dup.id).unwrap().unwrap();
+
+    let merged = dedupe::apply_merge(&conn, &keep, &[&dup]).unwrap();
+    assert_eq!(merged.last_opened_at.map(|t| t.timestamp()), Some(opened.timestamp()));
+
+    let kept = queries::get_article_by_id(&conn, keep.id).unwrap().unwrap();
+    assert_eq!(kept.last_opened_at.map(|t| t.timestamp()), Some(opened.timestamp()));
+}
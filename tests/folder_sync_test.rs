@@ -0,0 +1,191 @@
+// Syncing two libraries through a shared folder
+mod common;
+
+use common::{create_new_article, setup_test_db};
+use rusqlite::Connection;
+use stash::db::{models::Article, queries};
+use stash::sync::folder;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::Duration;
+
+fn article(conn: &Connection, hash: &str) -> Option<Article> {
+    let mut article = queries::find_by_hash(conn, hash).unwrap()?;
+    article.tags.sort();
+    Some(article)
+}
+
+/// Sync one device, then the other, then the first again so both have
+/// everything
+fn sync_both(laptop: &Connection, desktop: &Connection, dir: &Path) {
+    folder::sync(laptop, dir).unwrap();
+    folder::sync(desktop, dir).unwrap();
+    folder::sync(laptop, dir).unwrap();
+}
+
+#[test]
+fn test_sync_merges_field_by_field() {
+    let dir = tempfile::tempdir().unwrap();
+    let laptop = setup_test_db();
+    let desktop = setup_test_db();
+
+    let saved = queries::insert_article(
+        &laptop,
+        create_new_article(
+            "hash1",
+            "https://example.com/1",
+            Some("First"),
+            vec!["rust"],
+        ),
+    )
+    .unwrap();
+    queries::insert_highlight(&laptop, saved.id, "A good line", None, Some("agreed")).unwrap();
+
+    let summary = folder::sync(&laptop, dir.path()).unwrap();
+    // The article, its tag and its highlight
+    assert_eq!(summary.written, 3);
+    let summary = folder::sync(&desktop, dir.path()).unwrap();
+    assert_eq!(summary.devices, 1);
+    let copy = article(&desktop, "hash1").unwrap();
+    assert_eq!(copy.title.as_deref(), Some("First"));
+    assert_eq!(copy.tags, vec!["rust"]);
+    assert_eq!(
+        queries::list_highlights(&desktop, Some(copy.id))
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Both devices tag and change state before hearing from the other
+    queries::update_tags(&laptop, saved.id, vec!["rust".into(), "cli".into()]).unwrap();
+    queries::mark_read_by_ids(&laptop, &[saved.id]).unwrap();
+    queries::update_tags(&desktop, copy.id, vec!["rust".into(), "async".into()]).unwrap();
+    queries::update_note(&desktop, copy.id, Some("From the desktop".into())).unwrap();
+    sync_both(&laptop, &desktop, dir.path());
+
+    for conn in [&laptop, &desktop] {
+        let merged = article(conn, "hash1").unwrap();
+        assert_eq!(merged.tags, vec!["async", "cli", "rust"]);
+        assert!(merged.read);
+        assert_eq!(merged.note.as_deref(), Some("From the desktop"));
+    }
+
+    // The later of two edits to the same field wins on both
+    queries::update_note(&desktop, copy.id, Some("Earlier".into())).unwrap();
+    sleep(Duration::from_millis(5));
+    queries::update_note(&laptop, saved.id, Some("Later".into())).unwrap();
+    queries::update_tags(&laptop, saved.id, vec!["rust".into()]).unwrap();
+    sync_both(&laptop, &desktop, dir.path());
+
+    for conn in [&laptop, &desktop] {
+        let merged = article(conn, "hash1").unwrap();
+        assert_eq!(merged.note.as_deref(), Some("Later"));
+        assert_eq!(merged.tags, vec!["rust"]);
+    }
+
+    // Nothing new, nothing to do
+    let summary = folder::sync(&desktop, dir.path()).unwrap();
+    assert_eq!((summary.written, summary.applied), (0, 0));
+}
+
+#[test]
+fn test_sync_keeps_deletes() {
+    let dir = tempfile::tempdir().unwrap();
+    let laptop = setup_test_db();
+    let desktop = setup_test_db();
+    let saved = queries::insert_article(
+        &laptop,
+        create_new_article("hash1", "https://example.com/1", Some("First"), vec![]),
+    )
+    .unwrap();
+    sync_both(&laptop, &desktop, dir.path());
+    let copy = article(&desktop, "hash1").unwrap();
+
+    // Starred on the laptop, then deleted on the desktop
+    queries::set_starred_by_ids(&laptop, &[saved.id], true).unwrap();
+    sleep(Duration::from_millis(5));
+    queries::delete_by_ids(&desktop, &[copy.id]).unwrap();
+    sync_both(&laptop, &desktop, dir.path());
+    folder::sync(&desktop, dir.path()).unwrap();
+
+    assert!(article(&laptop, "hash1").is_none());
+    assert!(article(&desktop, "hash1").is_none());
+
+    // Saving it again brings it back everywhere
+    queries::insert_article(
+        &laptop,
+        create_new_article("hash1", "https://example.com/1", Some("Again"), vec![]),
+    )
+    .unwrap();
+    sync_both(&laptop, &desktop, dir.path());
+    assert_eq!(
+        article(&desktop, "hash1").unwrap().title.as_deref(),
+        Some("Again")
+    );
+}
+
+#[test]
+fn test_sync_merges_copies_of_one_library() {
+    let dir = tempfile::tempdir().unwrap();
+    let laptop = setup_test_db();
+    let desktop = setup_test_db();
+    for (conn, tag) in [(&laptop, "rust"), (&desktop, "cli")] {
+        queries::insert_article(
+            conn,
+            create_new_article("hash1", "https://example.com/1", Some("First"), vec![tag]),
+        )
+        .unwrap();
+    }
+    let copy = article(&desktop, "hash1").unwrap();
+    queries::set_starred_by_ids(&desktop, &[copy.id], true).unwrap();
+
+    sync_both(&laptop, &desktop, dir.path());
+
+    let merged = article(&laptop, "hash1").unwrap();
+    assert_eq!(merged.tags, vec!["cli", "rust"]);
+    assert!(merged.starred);
+    let other = article(&desktop, "hash1").unwrap();
+    assert_eq!(other.tags, merged.tags);
+    assert!(other.starred);
+}
+
+#[test]
+fn test_sync_waits_for_articles_from_other_devices() {
+    let dir = tempfile::tempdir().unwrap();
+    let shared = tempfile::tempdir().unwrap();
+    let laptop = setup_test_db();
+    let desktop = setup_test_db();
+    let phone = setup_test_db();
+
+    queries::insert_article(
+        &laptop,
+        create_new_article("hash1", "https://example.com/1", Some("First"), vec![]),
+    )
+    .unwrap();
+    sync_both(&laptop, &desktop, dir.path());
+    let copy = article(&desktop, "hash1").unwrap();
+    queries::mark_read_by_ids(&desktop, &[copy.id]).unwrap();
+    folder::sync(&desktop, dir.path()).unwrap();
+
+    // The phone's folder has the desktop's log but not yet the laptop's
+    let desktop_log = format!("{}.ndjson", folder::device_id(&desktop).unwrap().unwrap());
+    std::fs::copy(
+        dir.path().join(&desktop_log),
+        shared.path().join(&desktop_log),
+    )
+    .unwrap();
+    let summary = folder::sync(&phone, shared.path()).unwrap();
+    // Marked read, and last opened
+    assert_eq!(summary.pending, 2);
+    assert!(article(&phone, "hash1").is_none());
+
+    let laptop_log = format!("{}.ndjson", folder::device_id(&laptop).unwrap().unwrap());
+    std::fs::copy(
+        dir.path().join(&laptop_log),
+        shared.path().join(&laptop_log),
+    )
+    .unwrap();
+    let summary = folder::sync(&phone, shared.path()).unwrap();
+    assert_eq!(summary.pending, 0);
+    assert!(article(&phone, "hash1").unwrap().read);
+}
@@ -0,0 +1,70 @@
+// Reading goal and bankruptcy tests
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{create_new_article, setup_test_db};
+use rusqlite::{Connection, params};
+use stash::db::queries;
+use stash::goals::{self, Progress};
+
+fn insert_saved_days_ago(conn: &Connection, n: i64, days: i64) -> i64 {
+    let article = queries::insert_article(
+        conn,
+        create_new_article(
+            &format!("hash{}", n),
+            &format!("https://example.com/{}", n),
+            Some(&format!("Article {}", n)),
+            vec![],
+        ),
+    )
+    .unwrap();
+    let saved_at = (Utc::now() - Duration::days(days)).timestamp();
+    conn.execute(
+        "UPDATE articles SET saved_at = ?1 WHERE id = ?2",
+        params![saved_at, article.id],
+    )
+    .unwrap();
+    article.id
+}
+
+#[test]
+fn test_progress_load() {
+    let conn = setup_test_db();
+    let old = insert_saved_days_ago(&conn, 1, 100);
+    let recent = insert_saved_days_ago(&conn, 2, 1);
+    insert_saved_days_ago(&conn, 3, 30);
+    queries::mark_read_by_ids(&conn, &[recent]).unwrap();
+
+    let now = Utc::now();
+    let progress = Progress::load(&conn, now).unwrap();
+    assert_eq!(progress.unread, 2);
+    assert_eq!(progress.read_this_week, 1);
+    assert_eq!(progress.oldest_unread_days(now), Some(100));
+
+    queries::archive_by_ids(&conn, &[old]).unwrap();
+    let progress = Progress::load(&conn, now).unwrap();
+    assert_eq!(progress.oldest_unread_days(now), Some(30));
+}
+
+#[test]
+fn test_bankruptcy_keeps_starred_and_snoozed() {
+    let conn = setup_test_db();
+    let old = insert_saved_days_ago(&conn, 1, 100);
+    let starred = insert_saved_days_ago(&conn, 2, 100);
+    let snoozed = insert_saved_days_ago(&conn, 3, 100);
+    insert_saved_days_ago(&conn, 4, 10);
+
+    queries::set_starred_by_ids(&conn, &[starred], true).unwrap();
+    queries::snooze_by_ids(&conn, &[snoozed], Some(Utc::now() + Duration::days(3))).unwrap();
+
+    let candidates = goals::bankruptcy_candidates(
+        queries::list_articles(&conn, i64::MAX, false).unwrap(),
+        90,
+        Utc::now(),
+    );
+    let ids: Vec<i64> = candidates.iter().map(|a| a.id).collect();
+    assert_eq!(ids, vec![old]);
+
+    let all = queries::list_articles(&conn, i64::MAX, false).unwrap();
+    assert!(goals::bankruptcy_candidates(all, u32::MAX, Utc::now()).is_empty());
+}
@@ -0,0 +1,76 @@
+// Obsidian vault sync tests
+mod common;
+
+use common::{create_new_article, setup_test_db};
+use stash::db::queries;
+use stash::sync::obsidian::{self, Summary};
+use std::fs;
+
+#[test]
+fn test_sync_writes_only_changes_and_pulls_notes() {
+    let conn = setup_test_db();
+    let dir = tempfile::tempdir().unwrap();
+    let first = queries::insert_article(
+        &conn,
+        create_new_article(
+            "hash1",
+            "https://example.com/1",
+            Some("First"),
+            vec!["rust"],
+        ),
+    )
+    .unwrap();
+    let second = queries::insert_article(
+        &conn,
+        create_new_article("hash2", "https://example.com/2", Some("Second"), vec![]),
+    )
+    .unwrap();
+    queries::insert_highlight(&conn, first.id, "A good line", None, Some("agreed")).unwrap();
+
+    let summary = obsidian::sync(&conn, dir.path(), false).unwrap();
+    assert_eq!(summary.written, 2);
+    let note = fs::read_to_string(dir.path().join("First.md")).unwrap();
+    assert!(note.contains("#rust"));
+    assert!(note.contains("> A good line\n\nagreed"));
+    let index = fs::read_to_string(dir.path().join("Stash Index.md")).unwrap();
+    assert!(index.contains("[[First]]") && index.contains("[[Second]]"));
+
+    let summary = obsidian::sync(&conn, dir.path(), false).unwrap();
+    assert_eq!(
+        summary,
+        Summary {
+            unchanged: 2,
+            ..Summary::default()
+        }
+    );
+
+    // Edit the note in the vault, rename an article and delete another
+    fs::write(
+        dir.path().join("First.md"),
+        note.replace("## Note\n\n", "## Note\n\nWritten in Obsidian\n\n"),
+    )
+    .unwrap();
+    let mut renamed = queries::get_article_by_id(&conn, first.id)
+        .unwrap()
+        .unwrap();
+    renamed.title = Some("First, renamed".to_string());
+    queries::update_article(&conn, &renamed).unwrap();
+    queries::delete_by_ids(&conn, &[second.id]).unwrap();
+
+    let summary = obsidian::sync(&conn, dir.path(), true).unwrap();
+    assert_eq!(summary.pulled, 1);
+    assert_eq!(summary.written, 1);
+    assert_eq!(summary.removed, 1);
+    assert_eq!(
+        queries::get_article_by_id(&conn, first.id)
+            .unwrap()
+            .unwrap()
+            .note
+            .as_deref(),
+        Some("Written in Obsidian")
+    );
+    assert!(!dir.path().join("First.md").exists());
+    assert!(!dir.path().join("Second.md").exists());
+    let note = fs::read_to_string(dir.path().join("First, renamed.md")).unwrap();
+    assert!(note.contains("## Note\n\nWritten in Obsidian\n"));
+}
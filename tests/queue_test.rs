@@ -0,0 +1,161 @@
+// Reading queue tests
+mod common;
+
+use common::{create_new_article, setup_test_db};
+use rusqlite::Connection;
+use stash::db::queries;
+use stash::dedupe;
+use stash::queue::{self, Placement};
+
+fn insert(conn: &Connection, n: i64) -> i64 {
+    queries::insert_article(
+        conn,
+        create_new_article(
+            &format!("hash{}", n),
+            &format!("https://example.com/{}", n),
+            Some(&format!("Article {}", n)),
+            vec![],
+        ),
+    )
+    .unwrap()
+    .id
+}
+
+fn queued_ids(conn: &Connection) -> Vec<i64> {
+    queries::list_queue(conn)
+        .unwrap()
+        .iter()
+        .map(|a| a.id)
+        .collect()
+}
+
+#[test]
+fn test_enqueue_and_reorder() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=4).map(|n| insert(&conn, n)).collect();
+
+    queue::enqueue(&conn, &[ids[0], ids[1]], Placement::Bottom).unwrap();
+    queue::enqueue(&conn, &[ids[2]], Placement::Top).unwrap();
+    queue::enqueue(&conn, &[ids[3]], Placement::After(ids[2])).unwrap();
+    assert_eq!(queued_ids(&conn), vec![ids[2], ids[3], ids[0], ids[1]]);
+
+    assert!(queue::shift(&conn, ids[1], -1).unwrap());
+    assert!(!queue::shift(&conn, ids[2], -1).unwrap());
+    assert_eq!(queued_ids(&conn), vec![ids[2], ids[3], ids[1], ids[0]]);
+
+    assert_eq!(queue::head(&conn).unwrap().unwrap().id, ids[2]);
+    assert_eq!(queue::dequeue(&conn, &[ids[2], 999]).unwrap(), 1);
+    assert_eq!(queue::head(&conn).unwrap().unwrap().id, ids[3]);
+}
+
+#[test]
+fn test_read_and_archived_articles_leave_the_queue() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=3).map(|n| insert(&conn, n)).collect();
+    queue::enqueue(&conn, &ids, Placement::Bottom).unwrap();
+
+    queries::mark_read_by_ids(&conn, &[ids[0]]).unwrap();
+    queries::archive_by_ids(&conn, &[ids[1]]).unwrap();
+    queries::set_read_by_ids(&conn, &[ids[2]], false).unwrap();
+    assert_eq!(queued_ids(&conn), vec![ids[2]]);
+
+    let article = queries::get_article_by_id(&conn, ids[0]).unwrap().unwrap();
+    assert_eq!(article.queue_position, None);
+
+    // Archived articles can't be queued until restored
+    assert!(queue::enqueue(&conn, &[ids[1]], Placement::Top).is_err());
+    assert!(queue::enqueue(&conn, &[999], Placement::Top).is_err());
+}
+
+fn queue_position(conn: &Connection, id: i64) -> Option<i64> {
+    queries::get_article_by_id(conn, id).unwrap().unwrap().queue_position
+}
+
+#[test]
+fn test_stale_positions_are_not_listed() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=2).map(|n| insert(&conn, n)).collect();
+    queue::enqueue(&conn, &ids, Placement::Bottom).unwrap();
+
+    // Written behind the setters' back, as older versions could leave it
+    conn.execute("UPDATE articles SET read = 1 WHERE id = ?1", [ids[0]])
+        .unwrap();
+    assert_eq!(queued_ids(&conn), vec![ids[1]]);
+    assert_eq!(queue::head(&conn).unwrap().unwrap().id, ids[1]);
+}
+
+#[test]
+fn test_editing_read_or_archived_leaves_the_queue() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=3).map(|n| insert(&conn, n)).collect();
+    queue::enqueue(&conn, &ids, Placement::Bottom).unwrap();
+
+    for (id, read, archived) in [(ids[0], true, false), (ids[1], false, true), (ids[2], false, false)] {
+        let article = queries::get_article_by_id(&conn, id).unwrap().unwrap();
+        queries::update_article_metadata(
+            &conn,
+            id,
+            article.title,
+            article.url,
+            None,
+            vec![],
+            false,
+            read,
+            archived,
+        )
+        .unwrap();
+    }
+
+    assert_eq!(queue_position(&conn, ids[0]), None);
+    assert_eq!(queue_position(&conn, ids[1]), None);
+    assert_eq!(queued_ids(&conn), vec![ids[2]]);
+}
+
+#[test]
+fn test_import_merge_leaves_the_queue_when_read() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=2).map(|n| insert(&conn, n)).collect();
+    queue::enqueue(&conn, &ids, Placement::Bottom).unwrap();
+
+    let mut merged = queries::get_article_by_id(&conn, ids[0]).unwrap().unwrap();
+    merged.read = true;
+    queries::update_article(&conn, &merged).unwrap();
+    let unchanged = queries::get_article_by_id(&conn, ids[1]).unwrap().unwrap();
+    queries::update_article(&conn, &unchanged).unwrap();
+
+    assert_eq!(queue_position(&conn, ids[0]), None);
+    assert_eq!(queued_ids(&conn), vec![ids[1]]);
+}
+
+#[test]
+fn test_restored_state_leaves_the_queue_when_archived() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=2).map(|n| insert(&conn, n)).collect();
+    queue::enqueue(&conn, &ids, Placement::Bottom).unwrap();
+
+    let mut from = queries::get_article_by_id(&conn, ids[0]).unwrap().unwrap();
+    from.archived = true;
+    queries::restore_state(&conn, ids[0], &from).unwrap();
+    let unchanged = queries::get_article_by_id(&conn, ids[1]).unwrap().unwrap();
+    queries::restore_state(&conn, ids[1], &unchanged).unwrap();
+
+    assert_eq!(queue_position(&conn, ids[0]), None);
+    assert_eq!(queued_ids(&conn), vec![ids[1]]);
+}
+
+#[test]
+fn test_merged_duplicates_leave_the_queue() {
+    let conn = setup_test_db();
+    let ids: Vec<i64> = (1..=3).map(|n| insert(&conn, n)).collect();
+    queue::enqueue(&conn, &ids, Placement::Bottom).unwrap();
+
+    // The duplicate was read, so the merged article is read too
+    queries::set_read_by_ids(&conn, &[ids[1]], true).unwrap();
+    let keep = queries::get_article_by_id(&conn, ids[0]).unwrap().unwrap();
+    let dup = queries::get_article_by_id(&conn, ids[1]).unwrap().unwrap();
+    dedupe::apply_merge(&conn, &keep, &[&dup]).unwrap();
+
+    assert_eq!(queue_position(&conn, ids[0]), None);
+    assert_eq!(queue_position(&conn, ids[1]), None);
+    assert_eq!(queued_ids(&conn), vec![ids[2]]);
+}
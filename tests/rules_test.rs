@@ -0,0 +1,64 @@
+// Automatic tagging rule tests (applied against the test database)
+mod common;
+
+use common::{create_new_article, setup_test_db};
+use stash::config::schema::Config;
+use stash::db::queries;
+use stash::rules::{self, Candidate};
+
+fn compiled(toml_str: &str) -> Vec<rules::CompiledRule> {
+    let config: Config = toml::from_str(toml_str).unwrap();
+    rules::compile(&config.rules).unwrap()
+}
+
+#[test]
+fn test_apply_to_new_article() {
+    let conn = setup_test_db();
+    let rules = compiled(
+        r#"
+        [[rules]]
+        name = "github"
+        site = "github.com"
+        tags = ["code"]
+        star = true
+        note = "Repo on {site}"
+        "#,
+    );
+
+    let mut new_article =
+        create_new_article("hash1", "https://github.com/rust-lang/rust", Some("Rust"), vec!["rust"]);
+    new_article.site = Some("github.com".to_string());
+    let article = queries::insert_article(&conn, new_article).unwrap();
+
+    let matched = rules::apply_to_new_article(&conn, &rules, &article).unwrap();
+    assert_eq!(matched, vec!["github"]);
+
+    let updated = queries::get_article_by_id(&conn, article.id).unwrap().unwrap();
+    assert_eq!(updated.tags, vec!["code", "rust"]);
+    assert!(updated.starred);
+    assert_eq!(updated.note.as_deref(), Some("Repo on github.com"));
+}
+
+#[test]
+fn test_changes_never_overwrite_existing_values() {
+    let conn = setup_test_db();
+    let rules = compiled(
+        r#"
+        [[rules]]
+        keywords = ["rust"]
+        tags = ["rust"]
+        note = "auto"
+        "#,
+    );
+
+    let article = queries::insert_article(
+        &conn,
+        create_new_article("hash1", "https://example.com/a", Some("Rust tips"), vec!["rust"]),
+    )
+    .unwrap();
+    let article = queries::update_note(&conn, article.id, Some("mine".to_string())).unwrap();
+
+    let outcome = rules::evaluate(&rules, &Candidate::from_article(&article));
+    assert_eq!(outcome.matched.len(), 1);
+    assert!(rules::changes_for(&article, &outcome).is_empty());
+}
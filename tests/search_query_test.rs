@@ -0,0 +1,204 @@
+// Structured search query tests (run against the real FTS index)
+mod common;
+
+use common::{create_new_article, setup_test_db_with_fts};
+use rusqlite::{Connection, params};
+use stash::db::queries;
+
+fn seed(conn: &Connection) {
+    let articles = [
+        (
+            "hash1",
+            "https://github.com/tokio-rs/tokio",
+            "Async Rust with Tokio",
+            vec!["rust", "async"],
+        ),
+        (
+            "hash2",
+            "https://blog.rust-lang.org/ownership",
+            "Understanding Rust ownership",
+            vec!["rust"],
+        ),
+        (
+            "hash3",
+            "https://go.dev/blog/errors",
+            "Error handling in Go",
+            vec!["go"],
+        ),
+        (
+            "hash4",
+            "https://gist.github.com/someone/notes",
+            "Zero cost abstractions",
+            vec!["rust", "perf"],
+        ),
+    ];
+
+    for (hash, url, title, tags) in articles {
+        let mut article = create_new_article(hash, url, Some(title), tags);
+        article.site = stash::fetch::http::extract_site(url);
+        queries::insert_article(conn, article).unwrap();
+    }
+}
+
+fn search(conn: &Connection, query: &str) -> Vec<i64> {
+    let mut ids: Vec<i64> = queries::search_articles(conn, query, 50, true, false, false, &[])
+        .unwrap()
+        .iter()
+        .map(|a| a.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_search_plain_terms() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    // Tags are part of the FTS index, so the `rust` tag on #4 matches too
+    assert_eq!(search(&conn, "rust"), vec![1, 2, 4]);
+    assert_eq!(search(&conn, "rust ownership"), vec![2]);
+    // A colon that isn't a qualifier is part of the text
+    assert!(search(&conn, "10:30").is_empty());
+}
+
+#[test]
+fn test_search_not_and_or() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    assert_eq!(search(&conn, "rust NOT async"), vec![2, 4]);
+    assert_eq!(search(&conn, "rust -tokio -perf"), vec![2]);
+    assert_eq!(search(&conn, "ownership OR go"), vec![2, 3]);
+}
+
+#[test]
+fn test_search_prefix_and_phrase() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    assert_eq!(search(&conn, "owner*"), vec![2]);
+    assert_eq!(search(&conn, "\"error handling\""), vec![3]);
+    assert_eq!(search(&conn, "\"handling error\""), Vec::<i64>::new());
+}
+
+#[test]
+fn test_search_site_matches_subdomains() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    assert_eq!(search(&conn, "site:github.com"), vec![1, 4]);
+    assert_eq!(search(&conn, "site:gist.github.com"), vec![4]);
+}
+
+#[test]
+fn test_search_tag_and_title_qualifiers() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    assert_eq!(search(&conn, "tag:rust -tag:async"), vec![2, 4]);
+    assert_eq!(search(&conn, "title:zero"), vec![4]);
+}
+
+#[test]
+fn test_search_note_qualifier() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+    queries::update_note(&conn, 3, Some("Compare with anyhow".to_string())).unwrap();
+
+    assert_eq!(search(&conn, "note:anyhow"), vec![3]);
+    // Articles without a note or site still count as not matching
+    assert_eq!(search(&conn, "rust -note:anyhow"), vec![1, 2, 4]);
+    conn.execute("UPDATE articles SET site = NULL WHERE id = 2", [])
+        .unwrap();
+    assert_eq!(search(&conn, "rust -site:github.com"), vec![2]);
+}
+
+#[test]
+fn test_search_state_qualifiers_override_defaults() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+    queries::archive_by_ids(&conn, &[2]).unwrap();
+    queries::set_read_by_ids(&conn, &[3], true).unwrap();
+    queries::set_starred_by_ids(&conn, &[1], true).unwrap();
+
+    // Default search hides read and archived articles
+    let unread = queries::search_articles(
+        &conn,
+        "tokio OR ownership OR go",
+        50,
+        false,
+        false,
+        false,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(unread.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1]);
+
+    let archived =
+        queries::search_articles(&conn, "is:archived", 50, false, false, false, &[]).unwrap();
+    assert_eq!(archived.iter().map(|a| a.id).collect::<Vec<_>>(), vec![2]);
+
+    assert_eq!(search(&conn, "is:read"), vec![3]);
+    assert_eq!(search(&conn, "is:starred"), vec![1]);
+}
+
+#[test]
+fn test_search_saved_date_ranges() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    // 2024-06-15 12:00 UTC, far enough from midnight for any local timezone offset
+    conn.execute(
+        "UPDATE articles SET saved_at = ?1 WHERE id = 1",
+        params![1718452800],
+    )
+    .unwrap();
+
+    assert_eq!(search(&conn, "saved:<2025-01-01"), vec![1]);
+    assert_eq!(search(&conn, "saved:2024-06-15"), vec![1]);
+    assert_eq!(search(&conn, "saved:>2025-01-01"), vec![2, 3, 4]);
+}
+
+#[test]
+fn test_search_after_tag_update_keeps_index_consistent() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    queries::update_tags(&conn, 3, vec!["golang".to_string()]).unwrap();
+
+    assert_eq!(search(&conn, "golang"), vec![3]);
+    assert_eq!(search(&conn, "errors"), vec![3]);
+
+    // Old tokens must be removed from the index, not the new ones
+    queries::update_tags(&conn, 1, vec!["code".to_string()]).unwrap();
+    queries::update_note(&conn, 1, Some("later".to_string())).unwrap();
+    conn.execute(
+        "INSERT INTO articles_fts(articles_fts, rank) VALUES ('integrity-check', 1)",
+        [],
+    )
+    .unwrap();
+    assert_eq!(search(&conn, "code"), vec![1]);
+}
+
+#[test]
+fn test_search_matches_highlights() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    queries::insert_highlight(&conn, 2, "Moves transfer ownership of the borrowed value", None, Some("memorable"))
+        .unwrap();
+
+    assert_eq!(search(&conn, "borrowed"), vec![2]);
+    assert_eq!(search(&conn, "highlight:memorable"), vec![2]);
+    assert!(search(&conn, "highlight:rust").is_empty());
+}
+
+#[test]
+fn test_search_reports_syntax_errors() {
+    let conn = setup_test_db_with_fts();
+    seed(&conn);
+
+    let err = queries::search_articles(&conn, "(rust", 50, true, false, false, &[]).unwrap_err();
+    assert!(format!("{:#}", err).contains("Unclosed '(' at position 1"));
+}
@@ -0,0 +1,185 @@
+// Tag suggestion and related-article tests over a small in-memory library
+mod common;
+
+use common::create_test_article;
+use std::collections::HashMap;
+use stash::db::models::{Article, ArticleTags};
+use stash::similarity::{self, Document, TermVector};
+
+fn article(id: i64, title: &str, content: &str, tags: Vec<&str>) -> Article {
+    let mut article = create_test_article(
+        id,
+        &format!("hash{}", id),
+        &format!("https://example.com/{}", id),
+        Some(title),
+        tags,
+    );
+    article.content_markdown = Some(content.to_string());
+    article
+}
+
+fn frequencies(library: &[Article]) -> Vec<TermVector> {
+    library
+        .iter()
+        .map(|a| similarity::term_frequencies(a.title.as_deref(), a.content_markdown.as_deref()))
+        .collect()
+}
+
+fn article_tags(library: &[Article]) -> Vec<ArticleTags> {
+    library
+        .iter()
+        .map(|a| ArticleTags { id: a.id, tags: a.tags.clone() })
+        .collect()
+}
+
+fn suggest(library: &[Article], document: &Document) -> Vec<String> {
+    similarity::suggest_tags_from(&article_tags(library), frequencies(library), document, 5)
+        .into_iter()
+        .map(|s| s.tag)
+        .collect()
+}
+
+fn library() -> Vec<Article> {
+    vec![
+        article(1, "Ownership in Rust", "The borrow checker enforces ownership and lifetimes.", vec!["rust"]),
+        article(2, "Async Rust with Tokio", "Futures, executors and the borrow checker.", vec!["rust", "async"]),
+        article(3, "Sourdough basics", "Flour, water, salt and a starter. Bake in a dutch oven.", vec!["cooking"]),
+        article(4, "Pizza dough", "High hydration dough with flour and water, baked hot.", vec!["cooking"]),
+        article(5, "Untagged notes", "Random thoughts about nothing in particular.", vec![]),
+    ]
+}
+
+#[test]
+fn test_suggests_tags_from_similar_articles() {
+    let library = library();
+    let document = Document {
+        id: None,
+        title: Some("Lifetimes and the borrow checker"),
+        content: Some("Understanding ownership rules in Rust."),
+        tags: &[],
+    };
+
+    let suggestions = suggest(&library, &document);
+    assert_eq!(suggestions.first().map(String::as_str), Some("rust"));
+    assert!(!suggestions.contains(&"cooking".to_string()));
+}
+
+#[test]
+fn test_skips_existing_tags_and_the_article_itself() {
+    let library = library();
+    let target = &library[3];
+
+    let suggestions = suggest(&library, &Document::from_article(target));
+    assert!(!suggestions.contains(&"cooking".to_string()));
+
+    let mut untagged = target.clone();
+    untagged.tags.clear();
+    let suggestions = suggest(&library, &Document::from_article(&untagged));
+    assert_eq!(suggestions.first().map(String::as_str), Some("cooking"));
+}
+
+#[test]
+fn test_no_suggestions_for_unrelated_or_empty_documents() {
+    let library = library();
+    let document = Document {
+        id: None,
+        title: None,
+        content: None,
+        tags: &[],
+    };
+    assert!(suggest(&library, &document).is_empty());
+    assert!(suggest(&[], &document).is_empty());
+}
+
+fn related(library: &[Article], target: &Article) -> Vec<i64> {
+    similarity::related_articles_from(&article_tags(library), frequencies(library), target, 5)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect()
+}
+
+#[test]
+fn test_related_articles_rank_by_topic() {
+    let library = library();
+
+    assert_eq!(related(&library, &library[0]), vec![2]);
+    assert_eq!(related(&library, &library[2]), vec![4]);
+}
+
+#[test]
+fn test_shared_tags_make_articles_related() {
+    let mut library = library();
+    library.push(article(6, "Zig comptime", "Compile-time evaluation.", vec!["lang/zig"]));
+    library.push(article(7, "Odin arrays", "Fixed arrays and slices.", vec!["lang/odin"]));
+
+    // No words in common, but both sit under `lang`
+    assert_eq!(related(&library, &library[5]), vec![7]);
+}
+
+#[test]
+fn test_related_articles_load_only_the_matches() {
+    let conn = common::setup_test_db();
+    let mut ids = Vec::new();
+    for (i, title) in ["Tokio runtime internals", "Tokio runtime tuning", "Sourdough starter"].iter().enumerate() {
+        let new = common::create_new_article(&format!("hash{}", i), &format!("https://example.com/{}", i), Some(title), vec![]);
+        ids.push(stash::db::queries::insert_article(&conn, new).unwrap().id);
+    }
+    stash::db::queries::archive_by_ids(&conn, &[ids[1]]).unwrap();
+    let target = stash::db::queries::get_article_by_id(&conn, ids[0]).unwrap().unwrap();
+
+    // Archived articles are left out
+    assert!(similarity::related_articles(&conn, &target, 5).unwrap().is_empty());
+
+    stash::db::queries::unarchive_by_ids(&conn, &[ids[1]]).unwrap();
+    let related = similarity::related_articles(&conn, &target, 5).unwrap();
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0].article.title.as_deref(), Some("Tokio runtime tuning"));
+}
+
+#[test]
+fn test_cached_term_frequencies_are_invalidated_on_edit() {
+    let conn = common::setup_test_db();
+    let inserted = stash::db::queries::insert_article(
+        &conn,
+        common::create_new_article("hash1", "https://example.com/1", Some("Tokio runtime"), vec![]),
+    )
+    .unwrap();
+
+    let first = similarity::cached_term_frequencies(&conn, &[inserted.id]).unwrap();
+    assert!(first[0].contains_key("tokio"));
+    assert_eq!(stash::db::queries::get_term_vectors(&conn).unwrap().len(), 1);
+
+    stash::db::queries::update_article_metadata(
+        &conn,
+        inserted.id,
+        Some("Gardening tips".to_string()),
+        inserted.url.clone(),
+        None,
+        vec![],
+        false,
+        false,
+        false,
+    )
+    .unwrap();
+    assert!(stash::db::queries::get_term_vectors(&conn).unwrap().is_empty());
+
+    let second = similarity::cached_term_frequencies(&conn, &[inserted.id]).unwrap();
+    assert!(second[0].contains_key("gardening"));
+}
+
+#[test]
+fn test_cached_term_frequencies_skip_loading_cached_articles() {
+    let conn = common::setup_test_db();
+    let inserted = stash::db::queries::insert_article(
+        &conn,
+        common::create_new_article("hash1", "https://example.com/1", Some("Tokio runtime"), vec![]),
+    )
+    .unwrap();
+    similarity::cached_term_frequencies(&conn, &[inserted.id]).unwrap();
+
+    // Changing the cache directly shows whether the article was loaded again
+    let cached = HashMap::from([("cached".to_string(), 1.0)]);
+    stash::db::queries::save_term_vectors(&conn, &[(inserted.id, &cached)]).unwrap();
+    let frequencies = similarity::cached_term_frequencies(&conn, &[inserted.id, 999]).unwrap();
+    assert_eq!(frequencies, vec![cached, TermVector::new()]);
+}
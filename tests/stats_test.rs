@@ -0,0 +1,111 @@
+// Reading statistics tests
+mod common;
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use common::create_test_article;
+use stash::db::models::Article;
+use stash::stats;
+
+fn article(
+    id: i64,
+    site: &str,
+    saved_days_ago: i64,
+    read_days_ago: Option<i64>,
+    now: DateTime<Utc>,
+) -> Article {
+    let mut article = create_test_article(
+        id,
+        &format!("hash{}", id),
+        &format!("https://{}/{}", site, id),
+        Some("Title"),
+        vec!["rust"],
+    );
+    article.site = Some(site.to_string());
+    article.saved_at = now - Duration::days(saved_days_ago);
+    article.last_opened_at = read_days_ago.map(|days| now - Duration::days(days));
+    article.read = read_days_ago.is_some();
+    article
+}
+
+#[test]
+fn test_compute_totals_and_time_to_read() {
+    let now = Utc::now();
+    let articles = vec![
+        article(1, "a.com", 10, Some(0), now),
+        article(2, "a.com", 5, Some(1), now),
+        article(3, "a.com", 3, Some(2), now),
+        article(4, "b.com", 40, None, now),
+        article(5, "b.com", 200, None, now),
+        article(6, "b.com", 2, None, now),
+    ];
+
+    let stats = stats::compute(&articles, None, now);
+    assert_eq!((stats.saved, stats.read, stats.backlog), (6, 3, 3));
+    // 10, 4 and 1 days from save to read
+    assert_eq!(stats.avg_days_to_read, Some(5.0));
+    assert_eq!(stats.median_days_to_read, Some(4.0));
+
+    let backlog: Vec<usize> = stats.backlog_age.iter().map(|b| b.count).collect();
+    assert_eq!(backlog, vec![1, 0, 1, 1, 0]);
+
+    assert_eq!(stats.top_sites.len(), 2);
+    assert_eq!(stats.top_sites[0].name, "a.com");
+    assert_eq!(stats.top_sites[0].ratio, 1.0);
+    assert_eq!(stats.top_tags[0].read, 3);
+
+    assert_eq!(stats.weeks.len(), stats::DEFAULT_WEEKS as usize);
+    assert_eq!(stats.weeks.iter().map(|w| w.read).sum::<usize>(), 3);
+    assert_eq!(stats.streak.current, 3);
+    assert_eq!(stats.streak.longest, 3);
+}
+
+#[test]
+fn test_compute_since_window() {
+    let now = Utc::now();
+    let articles = vec![
+        article(1, "a.com", 30, Some(20), now),
+        article(2, "a.com", 5, Some(4), now),
+        article(3, "a.com", 30, Some(2), now),
+    ];
+
+    let stats = stats::compute(&articles, Some(now - Duration::days(7)), now);
+    assert_eq!(stats.saved, 1);
+    assert_eq!(stats.read, 2);
+    assert_eq!(stats.daily_reads.values().sum::<usize>(), 2);
+    // Nothing read today or yesterday
+    assert_eq!(stats.streak.current, 0);
+    assert_eq!(stats.streak.longest, 1);
+}
+
+#[test]
+fn test_weeks_start_at_the_first_activity() {
+    let now = Utc::now();
+    let articles = vec![
+        article(1, "a.com", 20, Some(10), now),
+        article(2, "a.com", 3, None, now),
+    ];
+
+    let since = Utc.with_ymd_and_hms(1, 1, 1, 0, 0, 0).unwrap();
+    let stats = stats::compute(&articles, Some(since), now);
+    let first = stats::week_start(stats::local_day(articles[0].saved_at));
+    assert_eq!(stats.weeks.first().map(|w| w.week), Some(first));
+    assert_eq!(stats.weeks.iter().map(|w| w.saved).sum::<usize>(), 2);
+
+    // The earliest date chrono can represent doesn't overflow
+    let stats = stats::compute(&articles, Some(DateTime::<Utc>::MIN_UTC), now);
+    assert_eq!(stats.weeks.first().map(|w| w.week), Some(first));
+    assert_eq!(stats.saved, 2);
+
+    // Nothing saved: just the current week
+    let stats = stats::compute(&[], Some(since), now);
+    assert_eq!(stats.weeks.len(), 1);
+}
+
+#[test]
+fn test_week_start_saturates() {
+    assert_eq!(stats::week_start(NaiveDate::MIN), NaiveDate::MIN);
+    assert_eq!(
+        stats::week_start(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()),
+        NaiveDate::from_ymd_opt(2026, 10, 12).unwrap()
+    );
+}
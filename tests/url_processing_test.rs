@@ -1,5 +1,4 @@
 // URL processing and hash generation tests
-use blake3;
 
 // Hash Generation Tests
 